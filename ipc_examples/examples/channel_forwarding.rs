@@ -14,7 +14,7 @@ async fn main() -> Result<()> {
     // Connect to q process running on localhost:5000
     let stream = TcpStream::connect("127.0.0.1:5000")
         .await
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+        .map_err(Error::NetworkError)?;
 
     let codec = KdbCodec::new(true);
     let framed = Framed::new(stream, codec);
@@ -74,13 +74,13 @@ async fn forward_messages_safely(
                         framed
                             .feed(msg)
                             .await
-                            .map_err(|e| Error::NetworkError(e.to_string()))?;
+                            .map_err(Error::NetworkError)?;
 
                         // Step 2: flush() sends all buffered messages
                         // After this point, the message is guaranteed sent
                         SinkExt::<KdbMessage>::flush(&mut framed)
                             .await
-                            .map_err(|e| Error::NetworkError(e.to_string()))?;
+                            .map_err(Error::NetworkError)?;
 
                         messages_sent += 1;
 
@@ -122,7 +122,7 @@ async fn unsafe_batching_example(
                     // ❌ WRONG: Multiple feed() calls without flush() in between
                     // This batches messages, which might not be what you want
                     framed.feed(msg).await
-                        .map_err(|e| Error::NetworkError(e.to_string()))?;
+                        .map_err(Error::NetworkError)?;
 
                     // If you receive more messages before flush(), they accumulate
                     // and are all sent together on the next flush()
@@ -136,7 +136,7 @@ async fn unsafe_batching_example(
     // All buffered messages sent here
     SinkExt::<KdbMessage>::flush(&mut framed)
         .await
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+        .map_err(Error::NetworkError)?;
 
     Ok(())
 }
@@ -156,13 +156,13 @@ async fn safe_batching_example(
                 if let Some(msg) = msg_opt {
                     // ✅ CORRECT: Intentionally batch messages for performance
                     framed.feed(msg).await
-                        .map_err(|e| Error::NetworkError(e.to_string()))?;
+                        .map_err(Error::NetworkError)?;
                     batch_count += 1;
 
                     // Flush after reaching batch size
                     if batch_count >= BATCH_SIZE {
                         SinkExt::<KdbMessage>::flush(&mut framed).await
-                            .map_err(|e| Error::NetworkError(e.to_string()))?;
+                            .map_err(Error::NetworkError)?;
                         println!("Flushed batch of {} messages", batch_count);
                         batch_count = 0;
                     }
@@ -170,7 +170,7 @@ async fn safe_batching_example(
                     // Channel closed - flush any remaining messages
                     if batch_count > 0 {
                         SinkExt::<KdbMessage>::flush(&mut framed).await
-                            .map_err(|e| Error::NetworkError(e.to_string()))?;
+                            .map_err(Error::NetworkError)?;
                         println!("Flushed final batch of {} messages", batch_count);
                     }
                     break;
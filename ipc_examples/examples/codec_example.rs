@@ -13,7 +13,7 @@ async fn main() -> Result<()> {
     // Connect to q process running on localhost:5000
     let stream = TcpStream::connect("127.0.0.1:5000")
         .await
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+        .map_err(Error::NetworkError)?;
 
     println!("Connected using kdb-codec pattern!");
 
@@ -34,10 +34,7 @@ async fn main() -> Result<()> {
         ]),
     );
 
-    framed
-        .send(query1)
-        .await
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+    framed.send(query1).await.map_err(Error::NetworkError)?;
 
     // Receive response
     if let Some(result) = framed.next().await {
@@ -57,10 +54,7 @@ async fn main() -> Result<()> {
         K::new_compound_list(vec![K::new_symbol(String::from("til")), K::new_long(5)]),
     );
 
-    framed
-        .send(query2)
-        .await
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+    framed.send(query2).await.map_err(Error::NetworkError)?;
 
     // Receive response
     if let Some(result) = framed.next().await {
@@ -5,6 +5,7 @@
 
 use futures::{SinkExt, StreamExt};
 use kdb_codec::*;
+use std::io;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio_util::codec::Framed;
@@ -14,7 +15,7 @@ async fn main() -> Result<()> {
     // Connect to q process running on localhost:5000
     let stream = TcpStream::connect("127.0.0.1:5000")
         .await
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+        .map_err(Error::NetworkError)?;
 
     let codec = KdbCodec::new(true);
     let framed = Framed::new(stream, codec);
@@ -90,14 +91,10 @@ async fn forward_with_split(
     let mut messages_sent = 0;
     while let Some(msg) = rx.recv().await {
         // feed() buffers the message
-        sink.feed(msg)
-            .await
-            .map_err(|e| Error::NetworkError(e.to_string()))?;
+        sink.feed(msg).await.map_err(Error::NetworkError)?;
 
         // flush() sends the buffered message
-        sink.flush()
-            .await
-            .map_err(|e| Error::NetworkError(e.to_string()))?;
+        sink.flush().await.map_err(Error::NetworkError)?;
 
         messages_sent += 1;
     }
@@ -137,11 +134,11 @@ async fn bidirectional_with_split(
     let (send_result, recv_result) = tokio::join!(send_handle, recv_handle);
 
     send_result
-        .map_err(|e| Error::NetworkError(e.to_string()))?
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+        .map_err(|e| Error::NetworkError(e.into()))?
+        .map_err(|e| Error::NetworkError(io::Error::other(e)))?;
     recv_result
-        .map_err(|e| Error::NetworkError(e.to_string()))?
-        .map_err(|e| Error::NetworkError(e.to_string()))?;
+        .map_err(|e| Error::NetworkError(e.into()))?
+        .map_err(|e| Error::NetworkError(io::Error::other(e)))?;
 
     Ok(())
 }
@@ -200,9 +197,9 @@ async fn forward_with_select(
                 match msg_opt {
                     Some(msg) => {
                         framed.feed(msg).await
-                            .map_err(|e| Error::NetworkError(e.to_string()))?;
+                            .map_err(Error::NetworkError)?;
                         SinkExt::<KdbMessage>::flush(&mut framed).await
-                            .map_err(|e| Error::NetworkError(e.to_string()))?;
+                            .map_err(Error::NetworkError)?;
 
                         messages_sent += 1;
 
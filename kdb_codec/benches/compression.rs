@@ -0,0 +1,75 @@
+//! Benchmark of the kdb+ IPC compression used by `KdbCodec`.
+//!
+//! Run with: `cargo bench --bench compression`
+//!
+//! The payload is a trade table typical of tick data (timestamp, symbol, price and size columns)
+//!  so that the match loop of `compress_sync` is exercised on realistic repetition.
+
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use kdb_codec::*;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROWS: usize = 50_000;
+const ITERATIONS: u32 = 50;
+
+/// Build an IPC message (header included) of a trade table.
+fn trade_table_message() -> Vec<u8> {
+    let symbols = ["AAPL", "MSFT", "GOOG", "AMZN", "IBM"];
+    let open = Utc.with_ymd_and_hms(2026, 1, 2, 9, 30, 0).unwrap();
+    let time = (0..ROWS as i64)
+        .map(|i| open + ChronoDuration::milliseconds(i))
+        .collect::<Vec<_>>();
+    let sym = (0..ROWS)
+        .map(|i| symbols[i % symbols.len()].to_string())
+        .collect::<Vec<String>>();
+    let price = (0..ROWS)
+        .map(|i| 100.0 + (i % 100) as f64 * 0.01)
+        .collect::<Vec<f64>>();
+    let size = (0..ROWS as i64)
+        .map(|i| 100 * (1 + i % 10))
+        .collect::<Vec<i64>>();
+
+    let table = k!(table: {
+        "time" => K::new_timestamp_list(time, qattribute::NONE),
+        "sym" => K::new_symbol_list(sym, qattribute::NONE),
+        "price" => K::new_float_list(price, qattribute::NONE),
+        "size" => K::new_long_list(size, qattribute::NONE)
+    });
+    table.ipc_msg_encode(qmsg_type::synchronous, false)
+}
+
+fn report(name: &str, bytes: usize, elapsed: Duration) {
+    let per_iteration = elapsed / ITERATIONS;
+    let throughput = bytes as f64 / per_iteration.as_secs_f64() / (1024.0 * 1024.0);
+    println!(
+        "{:<12} {:>10} bytes {:>12?}/iter {:>10.1} MiB/s",
+        name, bytes, per_iteration, throughput
+    );
+}
+
+fn main() {
+    let raw = trade_table_message();
+    let (compressed, compressed_bytes) = compress_sync(raw.clone());
+    assert!(compressed, "representative payload should be compressible");
+    println!(
+        "payload: {} bytes -> {} bytes ({:.1}%)",
+        raw.len(),
+        compressed_bytes.len(),
+        compressed_bytes.len() as f64 / raw.len() as f64 * 100.0
+    );
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(compress_sync(black_box(raw.clone())));
+    }
+    report("compress", raw.len(), start.elapsed());
+
+    let encoding = raw[0];
+    let body = compressed_bytes[MessageHeader::size()..].to_vec();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(decompress_sync(black_box(body.clone()), encoding, None).unwrap());
+    }
+    report("decompress", raw.len(), start.elapsed());
+}
@@ -8,12 +8,16 @@
 // >> Load Libraries
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+use super::conversions::q_datetime_to_q_timestamp;
 use super::deserialize_sync::q_ipc_decode_sync;
 use super::serialize::ENCODING;
-use super::{Error, Result, K};
+#[cfg(feature = "transport-compression")]
+use super::transport::{TransportCompression, TransportLayer};
+use super::{k0_inner, k0_list, qattribute, qtype, Error, Result, F, J, K};
 use bytes::{BufMut, BytesMut};
 use std::convert::TryInto;
-use std::io;
+use std::sync::Arc;
+use std::{fmt, io};
 use tokio_util::codec::{Decoder, Encoder};
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -26,11 +30,26 @@ const HEADER_SIZE: usize = 8;
 /// Compression threshold - messages larger than this may be compressed
 const COMPRESSION_THRESHOLD: usize = 2000;
 
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Types
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Transformation applied to the payload of each outgoing message before serialization.
+pub type EncodeHook = Arc<dyn Fn(K) -> K + Send + Sync>;
+
+/// Decoder of a type unknown to this crate. It receives the type byte, the whole payload and the
+///  cursor just after the type byte, and returns the decoded object with the cursor after its
+///  last byte.
+pub type UnknownTypeHandler = Arc<dyn Fn(i8, &[u8], usize) -> Result<(K, usize)> + Send + Sync>;
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Enums
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 /// Compression behavior for encoding messages
+///
+/// Whatever the mode, messages are not compressed for a peer whose capacity is below 3 or does
+/// not support compression (see [`Capability`]).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionMode {
     /// Automatically compress based on message size and connection type (default behavior)
@@ -68,6 +87,62 @@ impl Default for ValidationMode {
     }
 }
 
+/// Handling of the deprecated datetime type in decoded messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DatetimePolicy {
+    /// Keep datetime atoms and lists as they are (default behavior)
+    #[default]
+    Preserve,
+    /// Convert datetime atoms and lists into timestamps, including those nested in compound lists,
+    /// dictionaries and tables. Datetime has millisecond precision so no information is lost.
+    ConvertToTimestamp,
+}
+
+/// Features supported by an IPC peer, derived from the capacity byte exchanged in the handshake.
+///  A client sends its capacity after the credential and the server answers with the common one.
+///  See [`ipc_capability`] for the mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Capacity 0 (kdb+ 2.5 and earlier): no compression, timestamp, timespan or GUID
+    Basic,
+    /// Capacity 1 and 2 (kdb+ 2.6 to 2.8): compression, timestamp and timespan
+    Compression,
+    /// Capacity 3 (kdb+ 3.0 and later): compression, timestamp, timespan and GUID
+    Guid,
+    /// Capacity 5: same as `Guid` and messages larger than 2 GB with vectors of up to 2 billion elements
+    LargeMessage,
+    /// Capacity 6: same as `LargeMessage` and vectors of more than 2 billion elements
+    LargeVector,
+    /// Reserved or unknown capacity. A capacity above 6 is assumed to support at least the
+    ///  features of `LargeVector`.
+    Unknown(u8),
+}
+
+impl Capability {
+    /// Check if the peer can exchange compressed messages
+    pub fn supports_compression(&self) -> bool {
+        match self {
+            Capability::Basic => false,
+            Capability::Unknown(byte) => *byte > 6,
+            _ => true,
+        }
+    }
+
+    /// Check if the peer understands timestamp and timespan types
+    pub fn supports_timestamp(&self) -> bool {
+        self.supports_compression()
+    }
+
+    /// Check if the peer understands GUID type
+    pub fn supports_guid(&self) -> bool {
+        match self {
+            Capability::Guid | Capability::LargeMessage | Capability::LargeVector => true,
+            Capability::Unknown(byte) => *byte > 6,
+            _ => false,
+        }
+    }
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Structs
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -143,12 +218,58 @@ impl MessageHeader {
     }
 }
 
+/// Limits applied while deserializing objects from a peer.
+///
+/// Fields are public so that only some of them can be changed from the defaults:
+/// ```
+/// use kdb_codec::DeserializeLimits;
+///
+/// // Refuse lambdas and other functions from untrusted peers
+/// let limits = DeserializeLimits {
+///     allow_functions: false,
+///     ..Default::default()
+/// };
+/// assert_eq!(limits.max_total_elements, None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializeLimits {
+    /// Maximum number of elements of a single list (default: 100 million)
+    pub max_list_size: usize,
+    /// Maximum nesting depth of lists, dictionaries, tables and functions (default: 100)
+    pub max_recursion_depth: usize,
+    /// Maximum number of list elements of a whole object summed over all nested lists
+    ///  (None = unlimited)
+    pub max_total_elements: Option<usize>,
+    /// Whether lambdas, primitives, projections, compositions, iterators and foreign objects are
+    ///  accepted. `Error::DisallowedType` is returned otherwise. The generic null `::` is always
+    ///  accepted.
+    pub allow_functions: bool,
+    /// Whether tables (including keyed tables) are accepted. `Error::DisallowedType` is returned
+    ///  otherwise.
+    pub allow_tables: bool,
+    /// Maximum length in bytes of a symbol, an error message or a lambda context (default: 64 KB)
+    pub max_symbol_len: usize,
+}
+
+impl Default for DeserializeLimits {
+    fn default() -> Self {
+        DeserializeLimits {
+            max_list_size: crate::MAX_LIST_SIZE,
+            max_recursion_depth: crate::MAX_RECURSION_DEPTH,
+            max_total_elements: None,
+            allow_functions: true,
+            allow_tables: true,
+            max_symbol_len: crate::MAX_SYMBOL_LENGTH,
+        }
+    }
+}
+
 /// Kdb+ Protocol Codec
 ///
 /// This codec handles encoding and decoding of kdb+ IPC messages.
 /// It manages the message framing, compression, and serialization/deserialization
 /// of K objects.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct KdbCodec {
     /// Whether the connection is local (affects compression in Auto mode)
     is_local: bool,
@@ -156,14 +277,65 @@ pub struct KdbCodec {
     compression_mode: CompressionMode,
     /// Validation mode for decoding
     validation_mode: ValidationMode,
-    /// Maximum allowed list size during deserialization
-    max_list_size: usize,
-    /// Maximum recursion depth for nested structures
-    max_recursion_depth: usize,
+    /// Limits applied during deserialization
+    limits: DeserializeLimits,
     /// Maximum allowed message size in bytes (None = unlimited)
     max_message_size: Option<usize>,
     /// Maximum allowed decompressed message size in bytes (None = unlimited)
     max_decompressed_size: Option<usize>,
+    /// Handling of datetime objects in decoded messages
+    datetime_policy: DatetimePolicy,
+    /// Whether decoded messages carry the raw payload bytes
+    keep_raw_payload: bool,
+    /// Whether an element of a compound list failing to decode is replaced by an error object
+    element_error_recovery: bool,
+    /// Transformation applied to outgoing payloads before serialization (None = no transformation)
+    encode_hook: Option<EncodeHook>,
+    /// Decoder of types unknown to this crate (None = `Error::InvalidType`)
+    unknown_type_handler: Option<UnknownTypeHandler>,
+    /// Endianness of outgoing messages (None = endianness of the host)
+    force_encoding: Option<u8>,
+    /// Capacity byte agreed with the peer in the handshake (None = unknown)
+    peer_capacity: Option<u8>,
+    /// Total number of bytes of encoded messages
+    encoded_bytes: u64,
+    /// Total number of bytes of decoded messages
+    decoded_bytes: u64,
+    /// Compression wrapping the whole byte stream (None = plain kdb+ IPC)
+    #[cfg(feature = "transport-compression")]
+    transport: Option<TransportLayer>,
+}
+
+impl fmt::Debug for KdbCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("KdbCodec");
+        debug
+            .field("is_local", &self.is_local)
+            .field("compression_mode", &self.compression_mode)
+            .field("validation_mode", &self.validation_mode)
+            .field("limits", &self.limits)
+            .field("max_message_size", &self.max_message_size)
+            .field("max_decompressed_size", &self.max_decompressed_size)
+            .field("datetime_policy", &self.datetime_policy)
+            .field("keep_raw_payload", &self.keep_raw_payload)
+            .field("element_error_recovery", &self.element_error_recovery)
+            .field(
+                "encode_hook",
+                &self.encode_hook.as_ref().map(|_| "Fn(K) -> K"),
+            )
+            .field(
+                "unknown_type_handler",
+                &self
+                    .unknown_type_handler
+                    .as_ref()
+                    .map(|_| "Fn(i8, &[u8], usize) -> Result<(K, usize)>"),
+            )
+            .field("force_encoding", &self.force_encoding)
+            .field("peer_capacity", &self.peer_capacity);
+        #[cfg(feature = "transport-compression")]
+        debug.field("transport", &self.transport);
+        debug.finish()
+    }
 }
 
 #[bon::bon]
@@ -177,10 +349,20 @@ impl KdbCodec {
             is_local,
             compression_mode: CompressionMode::Auto,
             validation_mode: ValidationMode::Strict,
-            max_list_size: crate::MAX_LIST_SIZE,
-            max_recursion_depth: crate::MAX_RECURSION_DEPTH,
+            limits: DeserializeLimits::default(),
             max_message_size: Some(crate::MAX_MESSAGE_SIZE),
             max_decompressed_size: Some(crate::MAX_DECOMPRESSED_SIZE),
+            datetime_policy: DatetimePolicy::Preserve,
+            keep_raw_payload: false,
+            element_error_recovery: false,
+            encode_hook: None,
+            unknown_type_handler: None,
+            force_encoding: None,
+            peer_capacity: None,
+            encoded_bytes: 0,
+            decoded_bytes: 0,
+            #[cfg(feature = "transport-compression")]
+            transport: None,
         }
     }
 
@@ -217,10 +399,24 @@ impl KdbCodec {
             is_local,
             compression_mode,
             validation_mode,
-            max_list_size,
-            max_recursion_depth,
+            limits: DeserializeLimits {
+                max_list_size,
+                max_recursion_depth,
+                ..Default::default()
+            },
             max_message_size: Some(crate::MAX_MESSAGE_SIZE),
             max_decompressed_size: Some(crate::MAX_DECOMPRESSED_SIZE),
+            datetime_policy: DatetimePolicy::Preserve,
+            keep_raw_payload: false,
+            element_error_recovery: false,
+            encode_hook: None,
+            unknown_type_handler: None,
+            force_encoding: None,
+            peer_capacity: None,
+            encoded_bytes: 0,
+            decoded_bytes: 0,
+            #[cfg(feature = "transport-compression")]
+            transport: None,
         }
     }
 
@@ -228,7 +424,7 @@ impl KdbCodec {
     ///
     /// # Example
     /// ```
-    /// use kdb_codec::{KdbCodec, CompressionMode, ValidationMode};
+    /// use kdb_codec::{CompressionMode, Error, KdbCodec, ValidationMode};
     ///
     /// // Using builder pattern with default limits enabled
     /// let codec = KdbCodec::builder()
@@ -237,12 +433,21 @@ impl KdbCodec {
     ///     .validation_mode(ValidationMode::Strict)
     ///     .max_list_size(5_000_000)
     ///     .max_recursion_depth(50)
+    ///     .max_total_elements(10_000_000)
+    ///     .allow_functions(false)  // Refuse lambdas and other functions
+    ///     .max_symbol_len(4096)
     ///     .max_message_size(128 * 1024 * 1024)  // 128 MB  
     ///     .max_decompressed_size(256 * 1024 * 1024)  // 256 MB
+    ///     .encode_hook(|payload| payload)  // Transform outgoing payloads
+    ///     .unknown_type_handler(|qtype, _, _| Err(Error::InvalidType(qtype)))  // Decode new types
+    ///     .keep_raw_payload(true)  // Keep payload bytes of decoded messages
+    ///     .element_error_recovery(true)  // Salvage partially corrupt compound lists
+    ///     .force_encoding(0)  // Emit big endian messages
+    ///     .peer_capacity(3)  // Capacity agreed in the handshake
     ///     .build();
     ///
-    /// // Note: max_message_size and max_decompressed_size default to None (no limit)
-    /// // It's recommended to set these for untrusted connections
+    /// // Note: max_message_size and max_decompressed_size default to None (no limit), unlike
+    /// // `KdbCodec::new`. It's recommended to set these for untrusted connections
     /// ```
     #[builder]
     pub fn builder(
@@ -251,20 +456,67 @@ impl KdbCodec {
         #[builder(default)] validation_mode: ValidationMode,
         #[builder(default = crate::MAX_LIST_SIZE)] max_list_size: usize,
         #[builder(default = crate::MAX_RECURSION_DEPTH)] max_recursion_depth: usize,
+        max_total_elements: Option<usize>,
+        #[builder(default = true)] allow_functions: bool,
+        #[builder(default = true)] allow_tables: bool,
+        #[builder(default = crate::MAX_SYMBOL_LENGTH)] max_symbol_len: usize,
         max_message_size: Option<usize>,
         max_decompressed_size: Option<usize>,
+        #[builder(default)] datetime_policy: DatetimePolicy,
+        #[builder(default)] keep_raw_payload: bool,
+        #[builder(default)] element_error_recovery: bool,
+        #[builder(
+            with = |hook: impl Fn(K) -> K + Send + Sync + 'static| Arc::new(hook) as EncodeHook
+        )]
+        encode_hook: Option<EncodeHook>,
+        #[builder(
+            with = |handler: impl Fn(i8, &[u8], usize) -> Result<(K, usize)>
+                         + Send
+                         + Sync
+                         + 'static| Arc::new(handler) as UnknownTypeHandler
+        )]
+        unknown_type_handler: Option<UnknownTypeHandler>,
+        force_encoding: Option<u8>,
+        peer_capacity: Option<u8>,
     ) -> Self {
         KdbCodec {
             is_local,
             compression_mode,
             validation_mode,
-            max_list_size,
-            max_recursion_depth,
+            limits: DeserializeLimits {
+                max_list_size,
+                max_recursion_depth,
+                max_total_elements,
+                allow_functions,
+                allow_tables,
+                max_symbol_len,
+            },
             max_message_size,
             max_decompressed_size,
+            datetime_policy,
+            keep_raw_payload,
+            element_error_recovery,
+            encode_hook,
+            unknown_type_handler,
+            force_encoding,
+            peer_capacity,
+            encoded_bytes: 0,
+            decoded_bytes: 0,
+            #[cfg(feature = "transport-compression")]
+            transport: None,
         }
     }
 
+    /// Check if the connection is treated as local (local connections are never compressed in `Auto` mode)
+    pub fn is_local(&self) -> bool {
+        self.is_local
+    }
+
+    /// Set whether the connection is treated as local
+    pub fn set_is_local(&mut self, is_local: bool) {
+        self.is_local = is_local;
+    }
+
     /// Set the compression mode
     pub fn set_compression_mode(&mut self, mode: CompressionMode) {
         self.compression_mode = mode;
@@ -285,24 +537,80 @@ impl KdbCodec {
         self.validation_mode
     }
 
+    /// Set the handling of datetime objects in decoded messages
+    pub fn set_datetime_policy(&mut self, policy: DatetimePolicy) {
+        self.datetime_policy = policy;
+    }
+
+    /// Get the current handling of datetime objects in decoded messages
+    pub fn datetime_policy(&self) -> DatetimePolicy {
+        self.datetime_policy
+    }
+
+    /// Set whether decoded messages carry the raw payload bytes in [`KdbMessage::raw`]. Useful for
+    ///  comparing a decoded object against the bytes on the wire; off by default to avoid the copy.
+    pub fn set_keep_raw_payload(&mut self, keep: bool) {
+        self.keep_raw_payload = keep;
+    }
+
+    /// Check if decoded messages carry the raw payload bytes
+    pub fn keep_raw_payload(&self) -> bool {
+        self.keep_raw_payload
+    }
+
+    /// Set whether an element of a compound list failing to decode, e.g. because of an unknown
+    ///  type byte, is replaced by an error object (`qtype::ERROR`) holding the decode error instead
+    ///  of failing the whole message. Only an element whose extent is known from its type and
+    ///  length header, e.g. a string holding invalid UTF-8, is skipped; otherwise, e.g. for an
+    ///  unknown type byte, the message still fails. Errors raised by the deserialization limits
+    ///  are never recovered from. Off by default.
+    pub fn set_element_error_recovery(&mut self, recover: bool) {
+        self.element_error_recovery = recover;
+    }
+
+    /// Check if elements of a compound list failing to decode are replaced by error objects
+    pub fn element_error_recovery(&self) -> bool {
+        self.element_error_recovery
+    }
+
+    /// Set all limits applied during deserialization at once
+    pub fn set_deserialize_limits(&mut self, limits: DeserializeLimits) {
+        self.limits = limits;
+    }
+
+    /// Get the limits applied during deserialization
+    pub fn deserialize_limits(&self) -> &DeserializeLimits {
+        &self.limits
+    }
+
     /// Set the maximum list size
     pub fn set_max_list_size(&mut self, size: usize) {
-        self.max_list_size = size;
+        self.limits.max_list_size = size;
     }
 
     /// Get the current maximum list size
     pub fn max_list_size(&self) -> usize {
-        self.max_list_size
+        self.limits.max_list_size
     }
 
     /// Set the maximum recursion depth
     pub fn set_max_recursion_depth(&mut self, depth: usize) {
-        self.max_recursion_depth = depth;
+        self.limits.max_recursion_depth = depth;
     }
 
     /// Get the current maximum recursion depth
     pub fn max_recursion_depth(&self) -> usize {
-        self.max_recursion_depth
+        self.limits.max_recursion_depth
+    }
+
+    /// Set the maximum symbol length
+    pub fn set_max_symbol_len(&mut self, length: usize) {
+        self.limits.max_symbol_len = length;
+    }
+
+    /// Get the current maximum symbol length
+    pub fn max_symbol_len(&self) -> usize {
+        self.limits.max_symbol_len
     }
 
     /// Set the maximum message size (None = unlimited)
@@ -324,6 +632,48 @@ impl KdbCodec {
     pub fn max_decompressed_size(&self) -> Option<usize> {
         self.max_decompressed_size
     }
+
+    /// Set the transformation applied to outgoing payloads before serialization (None = no transformation)
+    pub fn set_encode_hook(&mut self, hook: Option<EncodeHook>) {
+        self.encode_hook = hook;
+    }
+
+    /// Check if a transformation of outgoing payloads is installed
+    pub fn has_encode_hook(&self) -> bool {
+        self.encode_hook.is_some()
+    }
+
+    /// Set the decoder of types unknown to this crate (None = fail with `Error::InvalidType`)
+    pub fn set_unknown_type_handler(&mut self, handler: Option<UnknownTypeHandler>) {
+        self.unknown_type_handler = handler;
+    }
+
+    /// Check if a decoder of unknown types is installed
+    pub fn has_unknown_type_handler(&self) -> bool {
+        self.unknown_type_handler.is_some()
+    }
+
+    /// Set the endianness of outgoing messages: `0` for big endian and `1` for little endian
+    ///  (None = endianness of the host). Encoding fails for any other value.
+    pub fn set_force_encoding(&mut self, encoding: Option<u8>) {
+        self.force_encoding = encoding;
+    }
+
+    /// Get the forced endianness of outgoing messages
+    pub fn force_encoding(&self) -> Option<u8> {
+        self.force_encoding
+    }
+
+    /// Set the capacity byte agreed with the peer (None = unknown). Messages are never compressed
+    ///  for a peer whose capacity is below 3 or does not support compression.
+    pub fn set_peer_capacity(&mut self, capacity: Option<u8>) {
+        self.peer_capacity = capacity;
+    }
+
+    /// Get the capacity byte agreed with the peer
+    pub fn peer_capacity(&self) -> Option<u8> {
+        self.peer_capacity
+    }
 }
 
 /// Message type for encoding
@@ -333,6 +683,12 @@ pub struct KdbMessage {
     pub message_type: u8,
     /// The K object payload
     pub payload: K,
+    /// Raw payload bytes after decompression (without the header) the payload was decoded from.
+    ///  Only set by a decoder with `keep_raw_payload` enabled.
+    pub raw: Option<Vec<u8>>,
+    /// Endianness to serialize the message with (`0` for big endian and `1` for little endian),
+    ///  overriding the one of the codec. Not set on decoded messages.
+    pub encoding: Option<u8>,
 }
 
 impl KdbMessage {
@@ -341,8 +697,26 @@ impl KdbMessage {
         KdbMessage {
             message_type,
             payload,
+            raw: None,
+            encoding: None,
         }
     }
+
+    /// Serialize the message in the given endianness regardless of the codec settings. Encoding
+    ///  fails unless `encoding` is `0` or `1`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let message = KdbMessage::new(qmsg_type::asynchronous, K::new_int(1)).with_encoding(0);
+    ///     assert_eq!(message.encoding, Some(0));
+    /// }
+    /// ```
+    pub fn with_encoding(mut self, encoding: u8) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -353,50 +727,42 @@ impl Encoder<KdbMessage> for KdbCodec {
     type Error = io::Error;
 
     fn encode(&mut self, item: KdbMessage, dst: &mut BytesMut) -> io::Result<()> {
+        #[cfg(feature = "transport-compression")]
+        if let Some(mut transport) = self.transport.take() {
+            // Build the plain frame, then count only the compressed bytes
+            let mut frame = BytesMut::new();
+            let encoded_bytes = self.encoded_bytes;
+            let result = self.encode(item, &mut frame);
+            self.encoded_bytes = encoded_bytes;
+            let start = dst.len();
+            let result = result.and_then(|()| transport.compress(&frame, dst));
+            self.encoded_bytes += (dst.len() - start) as u64;
+            self.transport = Some(transport);
+            return result;
+        }
+
+        let encoding = self.encoding(item.encoding)?;
+        let start = dst.len();
+        // Apply the transformation if any
+        let payload = match &self.encode_hook {
+            Some(hook) => hook(item.payload),
+            None => item.payload,
+        };
         // Serialize the K object to bytes
-        let payload_bytes = item.payload.q_ipc_encode();
+        let payload_bytes = payload.q_ipc_encode_with(encoding);
         let message_length = payload_bytes.len();
         let total_length = (HEADER_SIZE + message_length) as u32;
 
-        // Determine if compression should be attempted based on compression mode
-        let should_compress = match self.compression_mode {
-            CompressionMode::Never => false,
-            CompressionMode::Always => message_length > COMPRESSION_THRESHOLD - HEADER_SIZE,
-            CompressionMode::Auto => {
-                // Auto mode: compress if message is large and connection is not local
-                message_length > COMPRESSION_THRESHOLD - HEADER_SIZE && !self.is_local
-            }
-        };
-
-        if should_compress {
+        if self.should_compress(message_length) {
             // Prepare raw message with placeholder header and payload
             let mut raw = Vec::with_capacity(HEADER_SIZE + message_length);
-            raw.extend_from_slice(&[ENCODING, item.message_type, 0, 0, 0, 0, 0, 0]);
+            raw.extend_from_slice(&[encoding, item.message_type, 0, 0, 0, 0, 0, 0]);
             raw.extend_from_slice(&payload_bytes);
-
-            // Try to compress
-            match compress_sync(raw) {
-                (true, compressed) => {
-                    // Message was compressed successfully
-                    dst.reserve(compressed.len());
-                    dst.put_slice(&compressed);
-                }
-                (false, mut uncompressed) => {
-                    // Message was not compressed (compressed size >= half of original)
-                    // Write original total data size
-                    let total_length_bytes = match ENCODING {
-                        0 => total_length.to_be_bytes(),
-                        _ => total_length.to_le_bytes(),
-                    };
-                    uncompressed[4..8].copy_from_slice(&total_length_bytes);
-                    dst.reserve(uncompressed.len());
-                    dst.put_slice(&uncompressed);
-                }
-            }
+            put_compressed(raw, dst);
         } else {
             // Uncompressed message
             let header = MessageHeader {
-                encoding: ENCODING,
+                encoding,
                 message_type: item.message_type,
                 compressed: 0,
                 _unused: 0,
@@ -408,8 +774,195 @@ impl Encoder<KdbMessage> for KdbCodec {
             dst.put_slice(&payload_bytes);
         }
 
+        self.encoded_bytes += (dst.len() - start) as u64;
+        Ok(())
+    }
+}
+
+impl KdbCodec {
+    /// Encode a text query as a char vector message without building a `K` object. The produced
+    ///  bytes are identical to encoding `KdbMessage::new(message_type, K::new_string(query, qattribute::NONE))`
+    ///  and the compression mode of the codec is honored. If an encode hook is installed, the query
+    ///  is built as a `K` object and passed to the hook.
+    /// # Parameters
+    /// - `query`: q expression to send.
+    /// - `message_type`: Asynchronous or synchronous.
+    /// - `dst`: Buffer to write the message to.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use bytes::BytesMut;
+    ///
+    /// let mut codec = KdbCodec::new(true);
+    /// let mut buffer = BytesMut::new();
+    /// codec.encode_str("1+1", qmsg_type::synchronous, &mut buffer).unwrap();
+    /// assert_eq!(buffer.len(), 8 + 6 + 3);
+    /// ```
+    pub fn encode_str(
+        &mut self,
+        query: &str,
+        message_type: u8,
+        dst: &mut BytesMut,
+    ) -> io::Result<()> {
+        if self.encode_hook.is_some() || self.has_transport() {
+            let payload = K::new_string(query.to_string(), qattribute::NONE);
+            return self.encode(KdbMessage::new(message_type, payload), dst);
+        }
+
+        let start = dst.len();
+        // Type, attribute, length and chars
+        let message_length = 6 + query.len();
+        let total_length = (HEADER_SIZE + message_length) as u32;
+        let encoding = self.encoding(None)?;
+        let (length_bytes, total_length_bytes) = match encoding {
+            0 => (
+                (query.len() as u32).to_be_bytes(),
+                total_length.to_be_bytes(),
+            ),
+            _ => (
+                (query.len() as u32).to_le_bytes(),
+                total_length.to_le_bytes(),
+            ),
+        };
+
+        if self.should_compress(message_length) {
+            let mut raw = Vec::with_capacity(HEADER_SIZE + message_length);
+            raw.extend_from_slice(&[encoding, message_type, 0, 0, 0, 0, 0, 0]);
+            raw.extend_from_slice(&[qtype::STRING as u8, qattribute::NONE as u8]);
+            raw.extend_from_slice(&length_bytes);
+            raw.extend_from_slice(query.as_bytes());
+            put_compressed(raw, dst);
+        } else {
+            dst.reserve(total_length as usize);
+            dst.put_slice(&[encoding, message_type, 0, 0]);
+            dst.put_slice(&total_length_bytes);
+            dst.put_slice(&[qtype::STRING as u8, qattribute::NONE as u8]);
+            dst.put_slice(&length_bytes);
+            dst.put_slice(query.as_bytes());
+        }
+
+        self.encoded_bytes += (dst.len() - start) as u64;
         Ok(())
     }
+
+    /// Wrap the whole byte stream in the given compression, or go back to plain kdb+ IPC with `None`.
+    ///  Streams restart from scratch, so this must be set before any message is exchanged.
+    /// # Note
+    /// This is not the kdb+ `compressed` flag. It is only for non-standard gateways compressing
+    ///  IPC at the transport layer; a q process does not understand it.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use bytes::BytesMut;
+    /// use tokio_util::codec::{Decoder, Encoder};
+    ///
+    /// let mut sender = KdbCodec::new(true);
+    /// sender.set_transport_compression(Some(TransportCompression::Gzip));
+    /// let mut receiver = KdbCodec::new(true);
+    /// receiver.set_transport_compression(Some(TransportCompression::Gzip));
+    ///
+    /// let mut buffer = BytesMut::new();
+    /// sender
+    ///     .encode(KdbMessage::new(qmsg_type::asynchronous, K::new_long(42)), &mut buffer)
+    ///     .unwrap();
+    /// let message = receiver.decode(&mut buffer).unwrap().unwrap();
+    /// assert_eq!(message.payload.get_long().unwrap(), 42);
+    /// ```
+    #[cfg(feature = "transport-compression")]
+    pub fn set_transport_compression(&mut self, compression: Option<TransportCompression>) {
+        self.transport = compression.map(TransportLayer::new);
+    }
+
+    /// Discard the state of the transport compression, e.g. the gzip streams of a previous socket,
+    ///  keeping the configured compression for a new byte stream.
+    #[cfg(feature = "transport-compression")]
+    pub(crate) fn reset_transport_state(&mut self) {
+        self.set_transport_compression(self.transport_compression());
+    }
+
+    /// Get the compression wrapping the whole byte stream if any.
+    #[cfg(feature = "transport-compression")]
+    pub fn transport_compression(&self) -> Option<TransportCompression> {
+        self.transport.as_ref().map(TransportLayer::compression)
+    }
+
+    /// Check if the byte stream is wrapped in transport compression.
+    fn has_transport(&self) -> bool {
+        #[cfg(feature = "transport-compression")]
+        return self.transport.is_some();
+        #[cfg(not(feature = "transport-compression"))]
+        false
+    }
+
+    /// Endianness used to serialize outgoing messages, preferring `requested` over the one forced
+    ///  on the codec.
+    fn encoding(&self, requested: Option<u8>) -> io::Result<u8> {
+        match requested.or(self.force_encoding).unwrap_or(ENCODING) {
+            encoding @ (0 | 1) => Ok(encoding),
+            encoding => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid encoding: {}. Expected 0 (big endian) or 1 (little endian)",
+                    encoding
+                ),
+            )),
+        }
+    }
+
+    /// Total number of bytes of messages encoded by this codec.
+    pub(crate) fn encoded_bytes(&self) -> u64 {
+        self.encoded_bytes
+    }
+
+    /// Total number of bytes of messages decoded by this codec.
+    pub(crate) fn decoded_bytes(&self) -> u64 {
+        self.decoded_bytes
+    }
+
+    /// Determine if compression should be attempted for a payload of `message_length` bytes
+    ///  based on compression mode.
+    fn should_compress(&self, message_length: usize) -> bool {
+        // kx lists compression from capacity 1 (kdb+ 2.6) on, but the encoder only compresses for
+        //  a peer at capacity 3 (kdb+ 3.0), the capacity sent in our own handshake, or above. See
+        //  https://code.kx.com/q/basics/ipc/#handshake
+        if let Some(capacity) = self.peer_capacity {
+            if capacity < 3 || !ipc_capability(capacity).supports_compression() {
+                return false;
+            }
+        }
+        match self.compression_mode {
+            CompressionMode::Never => false,
+            CompressionMode::Always => message_length > COMPRESSION_THRESHOLD - HEADER_SIZE,
+            CompressionMode::Auto => {
+                // Auto mode: compress if message is large and connection is not local
+                message_length > COMPRESSION_THRESHOLD - HEADER_SIZE && !self.is_local
+            }
+        }
+    }
+}
+
+/// Compress a raw message (header with placeholder length followed by payload) and write it to `dst`.
+///  If compression is not worth it, the original message is written with its actual length.
+fn put_compressed(raw: Vec<u8>, dst: &mut BytesMut) {
+    let total_length = raw.len() as u32;
+    match compress_sync(raw) {
+        (true, compressed) => {
+            // Message was compressed successfully
+            dst.reserve(compressed.len());
+            dst.put_slice(&compressed);
+        }
+        (false, mut uncompressed) => {
+            // Message was not compressed (compressed size >= half of original)
+            // Write original total data size in the encoding of the header
+            let total_length_bytes = match uncompressed[0] {
+                0 => total_length.to_be_bytes(),
+                _ => total_length.to_le_bytes(),
+            };
+            uncompressed[4..8].copy_from_slice(&total_length_bytes);
+            dst.reserve(uncompressed.len());
+            dst.put_slice(&uncompressed);
+        }
+    }
 }
 
 //+++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -421,6 +974,19 @@ impl Decoder for KdbCodec {
     type Error = io::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        #[cfg(feature = "transport-compression")]
+        if let Some(mut transport) = self.transport.take() {
+            // Frames are read from the decompressed bytes; count only the compressed bytes
+            let received = src.len() as u64;
+            let decoded_bytes = self.decoded_bytes;
+            let result = transport
+                .decompress(src)
+                .and_then(|plain| self.decode(plain));
+            self.decoded_bytes = decoded_bytes + received;
+            self.transport = Some(transport);
+            return result;
+        }
+
         // Need at least header to proceed
         if src.len() < HEADER_SIZE {
             // Not enough data yet
@@ -492,6 +1058,7 @@ impl Decoder for KdbCodec {
 
         // We have a complete message, extract it
         let message_data = src.split_to(total_length);
+        self.decoded_bytes += total_length as u64;
 
         // Skip the header, get payload
         let payload_data = &message_data[HEADER_SIZE..];
@@ -509,29 +1076,109 @@ impl Decoder for KdbCodec {
             payload_data.to_vec()
         };
 
-        // Deserialize the K object - now returns Result
-        let k_object = q_ipc_decode_sync(
+        // Deserialize the K object. The frame is already consumed, so after an error the next
+        //  frame can still be read from `src`
+        let mut k_object = q_ipc_decode_sync(
             &decoded_payload,
             header.encoding,
-            self.max_list_size,
-            self.max_recursion_depth,
+            &self.limits,
+            self.element_error_recovery,
+            self.unknown_type_handler.as_ref(),
         )
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
 
+        if self.datetime_policy == DatetimePolicy::ConvertToTimestamp {
+            convert_datetime_to_timestamp(&mut k_object);
+        }
+
         Ok(Some(KdbMessage {
             message_type: header.message_type,
             payload: k_object,
+            raw: self.keep_raw_payload.then_some(decoded_payload),
+            encoding: None,
         }))
     }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        match self.decode(src)? {
+            Some(message) => Ok(Some(message)),
+            None if src.is_empty() => Ok(None),
+            // The peer closed the connection in the middle of a message
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "connection closed with {} bytes of an incomplete message",
+                    src.len()
+                ),
+            )),
+        }
+    }
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Helper Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+/// Interpret the capacity byte exchanged in the IPC handshake.
+/// # Example
+/// ```
+/// use kdb_codec::*;
+///
+/// assert_eq!(ipc_capability(0), Capability::Basic);
+/// assert!(ipc_capability(1).supports_compression());
+/// assert!(!ipc_capability(2).supports_guid());
+/// assert!(ipc_capability(3).supports_guid());
+/// assert_eq!(ipc_capability(4), Capability::Unknown(4));
+/// assert!(ipc_capability(7).supports_guid());
+/// ```
+pub fn ipc_capability(byte: u8) -> Capability {
+    match byte {
+        0 => Capability::Basic,
+        1 | 2 => Capability::Compression,
+        3 => Capability::Guid,
+        5 => Capability::LargeMessage,
+        6 => Capability::LargeVector,
+        _ => Capability::Unknown(byte),
+    }
+}
+
+/// Replace datetime atoms and lists with timestamps in place, descending into compound lists,
+/// dictionaries and tables.
+fn convert_datetime_to_timestamp(object: &mut K) {
+    match object.0.qtype {
+        qtype::DATETIME_ATOM => {
+            if let k0_inner::float(days) = object.0.value {
+                object.0.qtype = qtype::TIMESTAMP_ATOM;
+                object.0.value = k0_inner::long(q_datetime_to_q_timestamp(days));
+            }
+        }
+        qtype::DATETIME_LIST => {
+            if let Ok(days) = object.as_vec::<F>() {
+                let nanos = days
+                    .iter()
+                    .map(|&day| q_datetime_to_q_timestamp(day))
+                    .collect::<Vec<J>>();
+                object.0.qtype = qtype::TIMESTAMP_LIST;
+                object.0.value = k0_inner::list(k0_list::new(nanos));
+            }
+        }
+        qtype::COMPOUND_LIST | qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+            if let Ok(elements) = object.as_mut_vec::<K>() {
+                elements.iter_mut().for_each(convert_datetime_to_timestamp);
+            }
+        }
+        qtype::TABLE => {
+            if let k0_inner::table(dictionary) = &mut object.0.value {
+                convert_datetime_to_timestamp(dictionary);
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Convert IO errors to our Error type
 pub fn io_error_to_kdb_error(err: io::Error) -> Error {
-    Error::NetworkError(err.to_string())
+    Error::NetworkError(err)
 }
 
 /// Compress body synchronously. The combination of serializing the data and compressing will result in
@@ -613,7 +1260,7 @@ pub fn compress_sync(raw: Vec<u8>) -> (bool, Vec<u8>) {
     compressed[2] = 1;
 
     // Write size of raw bytes including a header
-    let raw_size = match ENCODING {
+    let raw_size = match raw[0] {
         0 => (t as u32).to_be_bytes(),
         _ => (t as u32).to_le_bytes(),
     };
@@ -669,7 +1316,7 @@ pub fn compress_sync(raw: Vec<u8>) -> (bool, Vec<u8>) {
     }
     compressed[c] = f;
     // Final compressed data size
-    let compressed_size = match ENCODING {
+    let compressed_size = match raw[0] {
         0 => (d as u32).to_be_bytes(),
         _ => (d as u32).to_le_bytes(),
     };
@@ -721,6 +1368,7 @@ pub fn compress_sync(raw: Vec<u8>) -> (bool, Vec<u8>) {
 /// - Invalid format that doesn't match kdb+ compression structure
 /// - Unexpected end of compressed data
 /// - Insufficient data for back-references
+/// - Size field inconsistent with the compressed data, e.g. compressed bytes left over
 ///
 /// # Note
 /// This function implements the kdb+ IPC compression algorithm which has been tested
@@ -891,6 +1539,16 @@ pub fn decompress_sync(
             i = 0;
         }
     }
+    // The loop only ends once the buffer is full, so a size field larger than the data fails
+    //  inside it. A smaller one leaves compressed bytes over, which means a corrupt frame rather
+    //  than a shorter message.
+    if d != compressed.len() {
+        return Err(Error::DeserializationError(format!(
+            "Invalid compressed data: {} trailing bytes after decompressing declared size {}",
+            compressed.len() - d,
+            decompressed.len()
+        )));
+    }
     Ok(decompressed)
 }
 
@@ -901,7 +1559,8 @@ pub fn decompress_sync(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{k, qmsg_type};
+    use crate::{k, qmsg_type, qnull_base, ONE_DAY_NANOS};
+    use chrono::prelude::*;
 
     #[test]
     fn test_compress_decompress_roundtrip() {
@@ -1000,6 +1659,237 @@ mod tests {
         assert_eq!(buffer[2], 0);
     }
 
+    #[test]
+    fn test_encode_str_matches_string_object() {
+        for (query, compression_mode) in [
+            ("1+1", CompressionMode::Auto),
+            (&*"a".repeat(5000), CompressionMode::Always),
+            (&*"a".repeat(5000), CompressionMode::Never),
+        ] {
+            let mut codec = KdbCodec::builder()
+                .is_local(false)
+                .compression_mode(compression_mode)
+                .build();
+            let mut expected = BytesMut::new();
+            codec
+                .encode(
+                    KdbMessage::new(qmsg_type::synchronous, k!(string: query)),
+                    &mut expected,
+                )
+                .unwrap();
+            let mut buffer = BytesMut::new();
+            codec
+                .encode_str(query, qmsg_type::synchronous, &mut buffer)
+                .unwrap();
+            assert_eq!(buffer, expected);
+
+            let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+            assert_eq!(decoded.message_type, qmsg_type::synchronous);
+            assert_eq!(decoded.payload.as_string().unwrap(), query);
+        }
+    }
+
+    #[test]
+    fn test_encode_hook() {
+        let mut codec = KdbCodec::builder()
+            .is_local(true)
+            .encode_hook(|_| k!(sym: "replaced"))
+            .build();
+        assert!(codec.has_encode_hook());
+        // Plain codec on the receiving side
+        let mut peer = KdbCodec::new(true);
+
+        let mut buffer = BytesMut::new();
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, k!(long: vec![1, 2, 3])),
+                &mut buffer,
+            )
+            .unwrap();
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(received.message_type, qmsg_type::asynchronous);
+        assert_eq!(received.payload.get_symbol().unwrap(), "replaced");
+
+        // Text queries also go through the hook
+        codec
+            .encode_str("1+1", qmsg_type::synchronous, &mut buffer)
+            .unwrap();
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(received.payload.get_symbol().unwrap(), "replaced");
+
+        // Payload is kept once the hook is removed
+        codec.set_encode_hook(None);
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::response, k!(long: 7)),
+                &mut buffer,
+            )
+            .unwrap();
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(received.payload.get_long().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_unknown_type_handler() {
+        // Compound list of a long atom and an object of type 90 carrying an int
+        let mut payload = vec![qtype::COMPOUND_LIST as u8, 0, 2, 0, 0, 0];
+        payload.extend_from_slice(&k!(long: 1).q_ipc_encode_with(1));
+        payload.extend_from_slice(&[90, 7, 0, 0, 0]);
+        let mut message = vec![1, qmsg_type::asynchronous, 0, 0];
+        message.extend_from_slice(&(8 + payload.len() as u32).to_le_bytes());
+        message.extend_from_slice(&payload);
+
+        // Unknown type is an error by default
+        let mut codec = KdbCodec::new(true);
+        assert!(!codec.has_unknown_type_handler());
+        let mut buffer = BytesMut::from(&message[..]);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert!(error.to_string().contains("90"));
+
+        let mut codec = KdbCodec::builder()
+            .unknown_type_handler(|qtype, bytes, cursor| {
+                assert_eq!(qtype, 90);
+                let value = i32::from_le_bytes(bytes[cursor..cursor + 4].try_into().unwrap());
+                Ok((K::new_int(value * 6), cursor + 4))
+            })
+            .build();
+        assert!(codec.has_unknown_type_handler());
+        let mut buffer = BytesMut::from(&message[..]);
+        let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(format!("{}", decoded.payload), String::from("(1;42i)"));
+
+        // Errors of the handler are reported
+        codec
+            .set_unknown_type_handler(Some(Arc::new(|qtype, _, _| Err(Error::InvalidType(qtype)))));
+        let mut buffer = BytesMut::from(&message[..]);
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_force_encoding() {
+        let other = 1 - ENCODING;
+        let mut codec = KdbCodec::builder()
+            .compression_mode(CompressionMode::Never)
+            .force_encoding(other)
+            .build();
+        assert_eq!(codec.force_encoding(), Some(other));
+        let mut peer = KdbCodec::new(true);
+        let payload = k!([
+            k!(long: vec![1, 2, 3]),
+            k!(float: 1.5),
+            k!(sym: vec!["a", "bc"]),
+            k!(string: "hello")
+        ]);
+
+        let mut buffer = BytesMut::new();
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::synchronous, payload.clone()),
+                &mut buffer,
+            )
+            .unwrap();
+        assert_eq!(buffer[0], other);
+        let decoded = K::q_ipc_decode(&buffer[HEADER_SIZE..], other).unwrap();
+        assert_eq!(format!("{}", decoded), format!("{}", payload));
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(format!("{}", received.payload), format!("{}", payload));
+
+        // Text queries and compressed messages honor the encoding as well
+        codec
+            .encode_str("1+1", qmsg_type::synchronous, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer[0], other);
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(received.payload.as_string().unwrap(), "1+1");
+
+        codec.set_compression_mode(CompressionMode::Always);
+        let large = K::new_long_list(vec![42; 1000], qattribute::NONE);
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, large.clone()),
+                &mut buffer,
+            )
+            .unwrap();
+        assert_eq!(buffer[0], other);
+        assert_eq!(buffer[2], 1);
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(received.payload.as_vec::<J>().unwrap(), &vec![42; 1000]);
+
+        // Back to the endianness of the host
+        codec.set_force_encoding(None);
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::response, k!(int: 7)),
+                &mut buffer,
+            )
+            .unwrap();
+        assert_eq!(buffer[0], ENCODING);
+        let received = peer.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(received.payload.get_int().unwrap(), 7);
+
+        // Only 0 and 1 are valid encodings
+        codec.set_force_encoding(Some(2));
+        let error = codec
+            .encode_str("1+1", qmsg_type::synchronous, &mut buffer)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        codec.set_force_encoding(None);
+        let message = KdbMessage::new(qmsg_type::asynchronous, k!(int: 7)).with_encoding(255);
+        assert!(codec.encode(message, &mut buffer).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_peer_capacity_gates_compression() {
+        assert_eq!(ipc_capability(0), Capability::Basic);
+        assert_eq!(ipc_capability(2), Capability::Compression);
+        assert_eq!(ipc_capability(3), Capability::Guid);
+        assert_eq!(ipc_capability(6), Capability::LargeVector);
+        assert_eq!(ipc_capability(7), Capability::Unknown(7));
+        assert!(!ipc_capability(2).supports_guid());
+        assert!(ipc_capability(5).supports_guid());
+        // Reserved capacity 4 supports nothing while a newer one supports at least `LargeVector`
+        assert!(!ipc_capability(4).supports_compression());
+        assert!(!ipc_capability(4).supports_guid());
+        assert!(ipc_capability(7).supports_compression());
+        assert!(ipc_capability(7).supports_timestamp());
+        assert!(ipc_capability(7).supports_guid());
+
+        let payload = K::new_long_list(vec![42; 1000], qattribute::NONE);
+        let encode = |codec: &mut KdbCodec| {
+            let mut buffer = BytesMut::new();
+            codec
+                .encode(
+                    KdbMessage::new(qmsg_type::asynchronous, payload.clone()),
+                    &mut buffer,
+                )
+                .unwrap();
+            buffer
+        };
+
+        // A peer without compression support never receives a compressed message
+        let mut codec = KdbCodec::builder()
+            .compression_mode(CompressionMode::Always)
+            .peer_capacity(0)
+            .build();
+        let buffer = encode(&mut codec);
+        assert_eq!(buffer[2], 0);
+        assert_eq!(buffer.len(), HEADER_SIZE + payload.serialized_size().unwrap());
+
+        // Capacity 2 (kdb+ 2.6 to 2.8) disables compression on encode as well
+        codec.set_peer_capacity(Some(2));
+        assert_eq!(encode(&mut codec)[2], 0);
+        codec.set_peer_capacity(Some(3));
+        assert_eq!(encode(&mut codec)[2], 1);
+        codec.set_peer_capacity(Some(4));
+        assert_eq!(encode(&mut codec)[2], 0);
+        // A newer capacity than 6 keeps compression on
+        codec.set_peer_capacity(Some(7));
+        assert_eq!(encode(&mut codec)[2], 1);
+        codec.set_peer_capacity(None);
+        assert_eq!(encode(&mut codec)[2], 1);
+    }
+
     #[test]
     fn test_message_header_roundtrip() {
         // Test message header serialization/deserialization
@@ -1418,4 +2308,228 @@ mod tests {
         assert_eq!(codec.compression_mode(), CompressionMode::Never);
         assert_eq!(codec.validation_mode(), ValidationMode::Strict); // default
     }
+
+    #[test]
+    fn test_datetime_policy() {
+        // 2000.01.02T12:00:00.000 and null
+        let datetimes = K::new(
+            qtype::DATETIME_LIST,
+            qattribute::NONE,
+            k0_inner::list(k0_list::new(vec![1.5, qnull_base::F])),
+        );
+        let message = K::new_compound_list(vec![
+            K::new_datetime(Utc.timestamp_millis_opt(946_771_200_000).unwrap()),
+            datetimes,
+        ]);
+
+        let mut buffer = BytesMut::new();
+        KdbCodec::new(true)
+            .encode(KdbMessage::new(qmsg_type::response, message), &mut buffer)
+            .unwrap();
+
+        // Preserve is the default
+        let mut codec = KdbCodec::new(true);
+        assert_eq!(codec.datetime_policy(), DatetimePolicy::Preserve);
+        let preserved = codec.decode(&mut buffer.clone()).unwrap().unwrap().payload;
+        let preserved = preserved.as_vec::<K>().unwrap();
+        assert_eq!(preserved[0].get_type(), qtype::DATETIME_ATOM);
+        assert_eq!(preserved[1].get_type(), qtype::DATETIME_LIST);
+
+        let mut codec = KdbCodec::builder()
+            .is_local(true)
+            .datetime_policy(DatetimePolicy::ConvertToTimestamp)
+            .build();
+        let converted = codec.decode(&mut buffer).unwrap().unwrap().payload;
+        let converted = converted.as_vec::<K>().unwrap();
+        assert_eq!(converted[0].get_type(), qtype::TIMESTAMP_ATOM);
+        assert_eq!(
+            converted[0].get_timestamp().unwrap(),
+            Utc.timestamp_millis_opt(946_771_200_000).unwrap()
+        );
+        assert_eq!(converted[1].get_type(), qtype::TIMESTAMP_LIST);
+        assert_eq!(
+            converted[1].as_vec::<J>().unwrap(),
+            &vec![ONE_DAY_NANOS * 3 / 2, qnull_base::J]
+        );
+    }
+
+    #[cfg(feature = "transport-compression")]
+    #[test]
+    fn test_gzip_transport_compression() {
+        let mut codec = KdbCodec::new(false);
+        codec.set_transport_compression(Some(TransportCompression::Gzip));
+        assert_eq!(
+            codec.transport_compression(),
+            Some(TransportCompression::Gzip)
+        );
+        let mut peer = KdbCodec::new(false);
+        peer.set_transport_compression(Some(TransportCompression::Gzip));
+
+        // kdb+ compression still applies inside the gzip stream
+        let large = K::new_long_list(vec![42; 1000], qattribute::NONE);
+        let mut stream = BytesMut::new();
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, large.clone()),
+                &mut stream,
+            )
+            .unwrap();
+        codec
+            .encode_str("1+1", qmsg_type::synchronous, &mut stream)
+            .unwrap();
+        // gzip magic number
+        assert_eq!(&stream[..2], &[0x1f, 0x8b]);
+        assert_eq!(codec.encoded_bytes(), stream.len() as u64);
+
+        // Feed the stream in small chunks as a socket would
+        let total = stream.len() as u64;
+        let mut buffer = BytesMut::new();
+        let mut received = Vec::new();
+        while !stream.is_empty() {
+            let chunk = stream.split_to(stream.len().min(7));
+            buffer.extend_from_slice(&chunk);
+            while let Some(message) = peer.decode(&mut buffer).unwrap() {
+                received.push(message);
+            }
+        }
+        assert_eq!(peer.decoded_bytes(), total);
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].message_type, qmsg_type::asynchronous);
+        assert_eq!(received[0].payload.as_vec::<J>().unwrap(), &vec![42; 1000]);
+        assert_eq!(received[1].message_type, qmsg_type::synchronous);
+        assert_eq!(received[1].payload.as_string().unwrap(), "1+1");
+
+        // Plain kdb+ IPC cannot read the gzip stream
+        let mut plain = KdbCodec::new(false);
+        let mut stream = BytesMut::new();
+        codec
+            .encode(KdbMessage::new(qmsg_type::asynchronous, large), &mut stream)
+            .unwrap();
+        assert!(plain.decode(&mut stream).is_err());
+    }
+
+    #[test]
+    fn test_keep_raw_payload() {
+        let payload = k!([k!(long: vec![1, 2, 3]), k!(sym: vec!["a", "bc"])]);
+        let mut codec = KdbCodec::builder().keep_raw_payload(true).build();
+        assert!(codec.keep_raw_payload());
+
+        let mut buffer = BytesMut::new();
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, payload.clone()),
+                &mut buffer,
+            )
+            .unwrap();
+        let wire = buffer[HEADER_SIZE..].to_vec();
+        let message = codec.decode(&mut buffer).unwrap().unwrap();
+        let raw = message.raw.unwrap();
+        assert_eq!(raw, wire);
+        assert_eq!(raw, payload.q_ipc_encode());
+        let decoded = K::q_ipc_decode(&raw, ENCODING).unwrap();
+        assert_eq!(format!("{}", decoded), format!("{}", message.payload));
+
+        // Raw bytes of a compressed message are the decompressed payload
+        codec.set_compression_mode(CompressionMode::Always);
+        let large = K::new_long_list(vec![42; 1000], qattribute::NONE);
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, large.clone()),
+                &mut buffer,
+            )
+            .unwrap();
+        assert_eq!(buffer[2], 1);
+        let message = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(message.raw.unwrap(), large.q_ipc_encode());
+
+        // Off by default
+        let mut codec = KdbCodec::new(true);
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, payload),
+                &mut buffer,
+            )
+            .unwrap();
+        assert!(codec.decode(&mut buffer).unwrap().unwrap().raw.is_none());
+    }
+
+    #[test]
+    fn test_element_error_recovery() {
+        let payload = k!([k!(long: 42), k!(string: "bc"), k!(sym: "c")]);
+        let mut codec = KdbCodec::builder().element_error_recovery(true).build();
+        assert!(codec.element_error_recovery());
+        let mut corrupt = BytesMut::new();
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, payload),
+                &mut corrupt,
+            )
+            .unwrap();
+        // Type byte of the second element follows the list header and the first element
+        let second = HEADER_SIZE + 6 + 9;
+        assert_eq!(corrupt[second] as i8, qtype::STRING);
+        corrupt[second + 6] = 0xff;
+
+        let mut buffer = corrupt.clone();
+        let message = codec.decode(&mut buffer).unwrap().unwrap();
+        let elements = message.payload.as_vec::<K>().unwrap();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0].get_long().unwrap(), 42);
+        assert_eq!(elements[1].get_type(), qtype::ERROR);
+        assert_eq!(
+            elements[1].get_error_string().unwrap(),
+            Error::InvalidUtf8.to_string()
+        );
+        assert_eq!(elements[2].get_symbol().unwrap(), "c");
+
+        // The whole message fails without recovery
+        codec.set_element_error_recovery(false);
+        let mut buffer = corrupt.clone();
+        assert!(codec.decode(&mut buffer).is_err());
+
+        // The extent of an element with an unknown type byte is not known
+        codec.set_element_error_recovery(true);
+        let mut buffer = corrupt.clone();
+        buffer[second] = 0xce;
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_element_error_recovery_keeps_nested_list_aligned() {
+        let payload = k!([
+            k!(long: 42),
+            k!([k!(float: 2.0), k!(string: "bc")]),
+            k!(sym: "c")
+        ]);
+        let mut codec = KdbCodec::builder().element_error_recovery(true).build();
+        let mut encoded = BytesMut::new();
+        codec
+            .encode(
+                KdbMessage::new(qmsg_type::asynchronous, payload),
+                &mut encoded,
+            )
+            .unwrap();
+        // The nested list follows the first element and holds a float atom then a string
+        let nested = HEADER_SIZE + 6 + 9;
+        assert_eq!(encoded[nested] as i8, qtype::COMPOUND_LIST);
+        let float = nested + 6;
+        assert_eq!(encoded[float] as i8, qtype::FLOAT_ATOM);
+
+        // An unknown type byte inside the nested list fails the message rather than resuming
+        //  from a position where the rest happens to decode
+        let mut buffer = encoded.clone();
+        buffer[float] = 0xce;
+        assert!(codec.decode(&mut buffer).is_err());
+
+        // A string with invalid UTF-8 inside the nested list is replaced in place
+        let mut buffer = encoded.clone();
+        buffer[float + 9 + 6] = 0xff;
+        let message = codec.decode(&mut buffer).unwrap().unwrap();
+        let elements = message.payload.as_vec::<K>().unwrap();
+        assert_eq!(elements.len(), 3);
+        let inner = elements[1].as_vec::<K>().unwrap();
+        assert_eq!(inner[0].get_float().unwrap(), 2.0);
+        assert_eq!(inner[1].get_type(), qtype::ERROR);
+        assert_eq!(elements[2].get_symbol().unwrap(), "c");
+    }
 }
@@ -7,19 +7,31 @@
 // >> Load Libraries
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-use super::codec::{CompressionMode, KdbCodec, KdbMessage, ValidationMode};
-use super::Result;
+use super::codec::{
+    ipc_capability, Capability, CompressionMode, KdbCodec, KdbMessage, MessageHeader,
+    ValidationMode,
+};
+use super::{Error, Result};
 use super::K;
+use crate::{k0_inner, qtype};
+use chrono::{DateTime, Utc};
 use futures::{SinkExt, StreamExt};
 use io::BufRead;
 use once_cell::sync::Lazy;
 use sha1_smol::Sha1;
 use std::collections::HashMap;
-use std::net::{IpAddr, Ipv4Addr};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr, SocketAddrV6};
 use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use std::{env, fs, io, str};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, Join};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
 #[cfg(unix)]
 use tokio::net::{UnixListener, UnixStream};
 use tokio_native_tls::native_tls::{
@@ -27,8 +39,14 @@ use tokio_native_tls::native_tls::{
 };
 use tokio_native_tls::{TlsAcceptor, TlsConnector, TlsStream};
 use tokio_util::codec::Framed;
+use trust_dns_resolver::config::{LookupIpStrategy, ResolverOpts};
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::system_conf::read_system_conf;
 use trust_dns_resolver::TokioAsyncResolver;
 
+// Re-export for `QStream::builder().dns_fallback(...)`
+pub use trust_dns_resolver::config::ResolverConfig;
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Global Variable
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -111,6 +129,12 @@ const DEFAULT_ACCOUNT_FILE: &str = "credential/kdbaccess";
 /// Format: `username:sha1_password` per line.
 const ACCOUNT_FILE_ENV: &str = "KDBPLUS_ACCOUNT_FILE";
 
+/// Maximum length of a client handshake (`username:password`, capacity and null byte) accepted by an acceptor.
+const MAX_HANDSHAKE_LENGTH: usize = 4096;
+
+/// Largest capacity byte sent by a q client.
+const MAX_CAPACITY: u8 = 6;
+
 /// Map from user name to password hashed with SHA1.
 const ACCOUNTS: Lazy<HashMap<String, String>> = Lazy::new(|| {
     // Map from user to password
@@ -151,6 +175,7 @@ const ACCOUNTS: Lazy<HashMap<String, String>> = Lazy::new(|| {
 //%% ConnectionMethod %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Connection method to q/kdb+.
+#[derive(Clone, Copy)]
 pub enum ConnectionMethod {
     TCP = 0,
     TLS = 1,
@@ -173,12 +198,115 @@ pub trait Query: Send + Sync {
 
 //%% FramedStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
+/// Read half of a user supplied transport.
+type BoxedReader = Box<dyn AsyncRead + Send + Sync + Unpin>;
+
+/// Write half of a user supplied transport.
+type BoxedWriter = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+
 /// Type alias for framed streams
 enum FramedStream {
     Tcp(Framed<TcpStream, KdbCodec>),
     Tls(Framed<TlsStream<TcpStream>, KdbCodec>),
     #[cfg(unix)]
     Uds(Framed<UnixStream, KdbCodec>),
+    /// Arbitrary read/write pair joined into a single stream.
+    Io(Framed<Join<BoxedReader, BoxedWriter>, KdbCodec>),
+}
+
+//%% ConnectionStats %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Statistics accumulated over the lifetime of a `QStream`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionStats {
+    /// Number of messages sent, including raw frames.
+    pub messages_sent: u64,
+    /// Number of messages received, including skipped pings.
+    pub messages_received: u64,
+    /// Number of bytes sent on the wire (headers included, after compression).
+    pub bytes_sent: u64,
+    /// Number of bytes received on the wire (headers included, before decompression).
+    pub bytes_received: u64,
+    /// Time of the last message sent or received. `None` until the first message.
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+//%% PeerCred %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Credentials of the process at the other end of a Unix domain socket reported by the OS
+///  (`SO_PEERCRED`).
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCred {
+    /// User ID of the peer process.
+    pub uid: u32,
+    /// Group ID of the peer process.
+    pub gid: u32,
+    /// Process ID of the peer process if available.
+    pub pid: Option<i32>,
+}
+
+//%% Request %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Future of a synchronous request returned by [`QStream::request`]. It resolves to the response
+///  of the remote q process.
+/// # Note
+/// The future borrows the `QStream` mutably until it completes or is dropped. Dropping it before
+///  completion, e.g. when a timeout branch of `tokio::select!` wins, may leave the request unsent
+///  or its response unread in the socket; the next response received then belongs to this
+///  request.
+#[must_use = "futures do nothing unless polled"]
+pub struct Request<'a> {
+    inner: Pin<Box<dyn Future<Output = Result<K>> + Send + 'a>>,
+}
+
+//%% ConnectionLimit %%//vvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Maximum number of concurrent connections accepted by
+///  [`QStream::accept_with_limit`](struct.QStream.html#method.accept_with_limit). Clones share
+///  the same count and a slot is released when the accepted `QStream` is dropped.
+#[derive(Debug, Clone)]
+pub struct ConnectionLimit {
+    /// Free slots.
+    semaphore: Arc<Semaphore>,
+    /// Maximum number of concurrent connections.
+    max_connections: usize,
+    /// Whether to return `Error::TooManyConnections` instead of waiting for a free slot.
+    reject_when_full: bool,
+}
+
+//%% ConnectParameters %%//vvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Parameters of an outgoing connection kept to reconnect.
+#[derive(Clone)]
+struct ConnectParameters {
+    /// Connection method.
+    method: ConnectionMethod,
+    /// Hostname or IP address of the q process.
+    host: String,
+    /// Port of the q process.
+    port: u16,
+    /// Credential in the form of `username:password`.
+    credential: String,
+    /// DNS resolver configuration used if the system one cannot be loaded.
+    dns_fallback: Option<ResolverConfig>,
+}
+
+//%% Timer %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Callback of a timer returning the future doing the periodic work.
+type TimerCallback = Box<
+    dyn for<'a> FnMut(&'a mut QStream) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>
+        + Send
+        + Sync,
+>;
+
+/// Timer registered by [`QStream::on_timer`].
+struct Timer {
+    /// Schedule of the callback.
+    interval: Interval,
+    /// Work to do on each tick.
+    callback: TimerCallback,
 }
 
 //%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
@@ -196,6 +324,18 @@ pub struct QStream {
     /// - `true`: Acceptor
     /// - `false`: Client
     listener: bool,
+    /// Indicator of whether the underlying socket has already been shut down.
+    closed: bool,
+    /// Indicator of whether empty asynchronous messages (pings) are dropped by `receive_message`.
+    skip_empty_async: bool,
+    /// Statistics of messages sent and received.
+    stats: ConnectionStats,
+    /// Parameters used to connect. `None` for an accepted stream or a user supplied transport.
+    connect_parameters: Option<ConnectParameters>,
+    /// Timer run while waiting in `receive_message`.
+    timer: Option<Timer>,
+    /// Slot of a `ConnectionLimit` held by an accepted stream until it is dropped.
+    connection_permit: Option<OwnedSemaphorePermit>,
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -220,6 +360,62 @@ impl Query for K {
     }
 }
 
+//%% Request %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Future for Request<'_> {
+    type Output = Result<K>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+//%% ConnectionLimit %%//vvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl ConnectionLimit {
+    /// Allow at most `max_connections` concurrent connections. Accepting beyond the limit waits
+    ///  until an accepted connection is dropped.
+    pub fn new(max_connections: usize) -> Self {
+        ConnectionLimit {
+            semaphore: Arc::new(Semaphore::new(max_connections)),
+            max_connections,
+            reject_when_full: false,
+        }
+    }
+
+    /// Return `Error::TooManyConnections` instead of waiting when the limit is reached.
+    pub fn reject_when_full(mut self, reject: bool) -> Self {
+        self.reject_when_full = reject;
+        self
+    }
+
+    /// Maximum number of concurrent connections.
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    /// Number of connections which can be accepted before the limit is reached.
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    /// Take a slot, waiting for one to be released or failing if `reject_when_full` is set.
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit> {
+        let semaphore = self.semaphore.clone();
+        let permit = match self.reject_when_full {
+            true => semaphore.try_acquire_owned().map_err(|error| match error {
+                TryAcquireError::NoPermits => Error::TooManyConnections(self.max_connections),
+                TryAcquireError::Closed => unreachable!("semaphore is never closed"),
+            })?,
+            false => semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed"),
+        };
+        Ok(permit)
+    }
+}
+
 //%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 #[bon::bon]
@@ -230,6 +426,12 @@ impl QStream {
             stream,
             method,
             listener: is_listener,
+            closed: false,
+            skip_empty_async: false,
+            stats: ConnectionStats::default(),
+            connect_parameters: None,
+            timer: None,
+            connection_permit: None,
         }
     }
 
@@ -249,6 +451,8 @@ impl QStream {
     ///         .credential("user:pass")
     ///         .compression_mode(CompressionMode::Always)
     ///         .validation_mode(ValidationMode::Lenient)
+    ///         .dns_fallback(ResolverConfig::cloudflare())
+    ///         .force_encoding(0)
     ///         .connect()
     ///         .await?;
     ///     
@@ -267,16 +471,36 @@ impl QStream {
         #[builder(default = String::new())] credential: String,
         #[builder(default)] compression_mode: CompressionMode,
         #[builder(default)] validation_mode: ValidationMode,
+        /// DNS resolver configuration used when the system configuration (e.g. `/etc/resolv.conf`)
+        ///  cannot be loaded.
+        dns_fallback: Option<ResolverConfig>,
+        /// Endianness of outgoing messages: `0` for big endian and `1` for little endian. Defaults to
+        ///  the endianness of the host.
+        force_encoding: Option<u8>,
+        /// Drop empty asynchronous messages (pings) in `receive_message`. Defaults to `false`.
+        #[builder(default)]
+        skip_empty_async: bool,
+        /// Treat the connection as local (`true`) or remote (`false`) regardless of the host, which
+        ///  decides compression in `CompressionMode::Auto`. Defaults to detection from the host:
+        ///  `localhost`, loopback and link-local addresses are local.
+        force_local: Option<bool>,
     ) -> Result<Self> {
-        Self::connect_with_options(
+        let mut stream = Self::connect_impl(
             method,
             &host,
             port,
             &credential,
             compression_mode,
             validation_mode,
+            dns_fallback.as_ref(),
         )
-        .await
+        .await?;
+        stream.set_force_encoding(force_encoding);
+        stream.skip_empty_async = skip_empty_async;
+        if let Some(is_local) = force_local {
+            stream.codec_mut().set_is_local(is_local);
+        }
+        Ok(stream)
     }
 
     /// Connect to q/kdb+ specifying a connection method, destination host, destination port and access credential.
@@ -383,51 +607,73 @@ impl QStream {
         compression_mode: CompressionMode,
         validation_mode: ValidationMode,
     ) -> Result<Self> {
-        match method {
+        Self::connect_impl(
+            method,
+            host,
+            port,
+            credential,
+            compression_mode,
+            validation_mode,
+            None,
+        )
+        .await
+    }
+
+    /// Inner function of `connect_with_options` and the builder taking a fallback DNS resolver
+    ///  configuration.
+    async fn connect_impl(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        credential: &str,
+        compression_mode: CompressionMode,
+        validation_mode: ValidationMode,
+        dns_fallback: Option<&ResolverConfig>,
+    ) -> Result<Self> {
+        let mut stream = match method {
             ConnectionMethod::TCP => {
-                let stream = connect_tcp(host, port, credential).await?;
-                let is_local = matches!(host, "localhost" | "127.0.0.1");
+                let (stream, capacity) = connect_tcp(host, port, credential, dns_fallback).await?;
+                let is_local = is_local_host(host);
                 let codec = KdbCodec::builder()
                     .is_local(is_local)
                     .compression_mode(compression_mode)
                     .validation_mode(validation_mode)
+                    .peer_capacity(capacity)
                     .build();
                 let framed = Framed::new(stream, codec);
-                Ok(QStream::new(
-                    FramedStream::Tcp(framed),
-                    ConnectionMethod::TCP,
-                    false,
-                ))
+                QStream::new(FramedStream::Tcp(framed), ConnectionMethod::TCP, false)
             }
             ConnectionMethod::TLS => {
-                let stream = connect_tls(host, port, credential).await?;
+                let (stream, capacity) = connect_tls(host, port, credential, dns_fallback).await?;
                 let codec = KdbCodec::builder()
                     .is_local(false)
                     .compression_mode(compression_mode)
                     .validation_mode(validation_mode)
+                    .peer_capacity(capacity)
                     .build(); // TLS is always remote
                 let framed = Framed::new(stream, codec);
-                Ok(QStream::new(
-                    FramedStream::Tls(framed),
-                    ConnectionMethod::TLS,
-                    false,
-                ))
+                QStream::new(FramedStream::Tls(framed), ConnectionMethod::TLS, false)
             }
             ConnectionMethod::UDS => {
-                let stream = connect_uds(port, credential).await?;
+                let (stream, capacity) = connect_uds(port, credential).await?;
                 let codec = KdbCodec::builder()
                     .is_local(true)
                     .compression_mode(compression_mode)
                     .validation_mode(validation_mode)
+                    .peer_capacity(capacity)
                     .build(); // UDS is always local
                 let framed = Framed::new(stream, codec);
-                Ok(QStream::new(
-                    FramedStream::Uds(framed),
-                    ConnectionMethod::UDS,
-                    false,
-                ))
+                QStream::new(FramedStream::Uds(framed), ConnectionMethod::UDS, false)
             }
-        }
+        };
+        stream.connect_parameters = Some(ConnectParameters {
+            method,
+            host: host.to_string(),
+            port,
+            credential: credential.to_string(),
+            dns_fallback: dns_fallback.cloned(),
+        });
+        Ok(stream)
     }
 
     /// Accept connection and does handshake.
@@ -536,16 +782,20 @@ impl QStream {
                 // Listen to the endpoint.
                 let (mut socket, ip_address) = listener.accept().await?;
                 // Read untill null bytes and send back capacity.
-                while let Err(_) = read_client_input(&mut socket).await {
-                    // Continue to listen in case of error.
-                    socket = listener.accept().await?.0;
-                }
+                let capacity = loop {
+                    match read_client_input(&mut socket).await {
+                        Ok(capacity) => break capacity,
+                        // Continue to listen in case of error.
+                        Err(_) => socket = listener.accept().await?.0,
+                    }
+                };
                 // Check if the connection is local
-                let is_local = ip_address.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+                let is_local = is_local_ip(ip_address.ip());
                 let codec = KdbCodec::builder()
                     .is_local(is_local)
                     .compression_mode(compression_mode)
                     .validation_mode(validation_mode)
+                    .peer_capacity(capacity)
                     .build();
                 let framed = Framed::new(socket, codec);
                 Ok(QStream::new(
@@ -569,19 +819,25 @@ impl QStream {
                     .await
                     .expect("failed to accept TLS connection");
                 // Read untill null bytes and send back a capacity.
-                while let Err(_) = read_client_input(&mut tls_socket).await {
-                    // Continue to listen in case of error.
-                    socket = listener.accept().await?.0;
-                    tls_socket = tls_acceptor
-                        .accept(socket)
-                        .await
-                        .expect("failed to accept TLS connection");
-                }
+                let capacity = loop {
+                    match read_client_input(&mut tls_socket).await {
+                        Ok(capacity) => break capacity,
+                        Err(_) => {
+                            // Continue to listen in case of error.
+                            socket = listener.accept().await?.0;
+                            tls_socket = tls_acceptor
+                                .accept(socket)
+                                .await
+                                .expect("failed to accept TLS connection");
+                        }
+                    }
+                };
                 // TLS is always a remote connection
                 let codec = KdbCodec::builder()
                     .is_local(false)
                     .compression_mode(compression_mode)
                     .validation_mode(validation_mode)
+                    .peer_capacity(capacity)
                     .build();
                 let framed = Framed::new(tls_socket, codec);
                 let mut qstream =
@@ -603,15 +859,19 @@ impl QStream {
                 // Listen to the endpoint
                 let (mut socket, _) = listener.accept().await?;
                 // Read untill null bytes and send back capacity.
-                while let Err(_) = read_client_input(&mut socket).await {
-                    // Continue to listen in case of error.
-                    socket = listener.accept().await?.0;
-                }
+                let capacity = loop {
+                    match read_client_input(&mut socket).await {
+                        Ok(capacity) => break capacity,
+                        // Continue to listen in case of error.
+                        Err(_) => socket = listener.accept().await?.0,
+                    }
+                };
                 // UDS is always a local connection
                 let codec = KdbCodec::builder()
                     .is_local(true)
                     .compression_mode(compression_mode)
                     .validation_mode(validation_mode)
+                    .peer_capacity(capacity)
                     .build();
                 let framed = Framed::new(socket, codec);
                 Ok(QStream::new(
@@ -623,34 +883,210 @@ impl QStream {
         }
     }
 
+    /// Accept a connection like [`accept`](#method.accept) while keeping the number of accepted
+    ///  connections alive at the same time within `limit`. The slot taken by the returned stream is
+    ///  released when it is dropped.
+    /// # Parameters
+    /// - `method`: Connection method (TCP, TLS, or UDS)
+    /// - `host`: Hostname or IP address of this listener. Empty `str` for Unix domain socket.
+    /// - `port`: Listening port.
+    /// - `limit`: Limit shared by the acceptors.
+    /// # Errors
+    /// `Error::TooManyConnections` if the limit is reached and `limit` rejects instead of waiting.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let limit = ConnectionLimit::new(16);
+    ///     loop {
+    ///         let mut socket =
+    ///             QStream::accept_with_limit(ConnectionMethod::TCP, "127.0.0.1", 7000, &limit)
+    ///                 .await?;
+    ///         tokio::spawn(async move {
+    ///             while let Ok((_, message)) = socket.receive_message().await {
+    ///                 println!("{}", message);
+    ///             }
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    pub async fn accept_with_limit(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        limit: &ConnectionLimit,
+    ) -> Result<Self> {
+        let permit = limit.acquire().await?;
+        let mut qstream = Self::accept(method, host, port).await?;
+        qstream.connection_permit = Some(permit);
+        Ok(qstream)
+    }
+
+    /// Build a `QStream` over an arbitrary read/write pair, e.g. the channel halves of an SSH
+    ///  tunnel. Messages are decoded as bytes arrive from `reader` and a slow consumer leaves them
+    ///  unread in the transport, so the usual backpressure of the transport applies.
+    /// # Note
+    /// No handshake is performed; the transport must already be connected to a q process or client
+    ///  which completed the handshake. Shutting down the stream shuts down `writer`.
+    /// # Parameters
+    /// - `reader`: Read half of the transport.
+    /// - `writer`: Write half of the transport.
+    /// - `method`: Connection method reported by
+    ///   [`get_connection_type`](#method.get_connection_type).
+    /// - `codec`: Codec carrying the options negotiated during the handshake.
+    /// - `is_listener`: `true` if this side accepted the connection.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (client, _server) = tokio::io::duplex(4096);
+    ///     let (reader, writer) = tokio::io::split(client);
+    ///     let codec = KdbCodec::new(false);
+    ///     let mut socket =
+    ///         QStream::from_async_read_write(reader, writer, ConnectionMethod::TCP, codec, false);
+    ///     let result = socket.send_sync_message(&"1+1").await?;
+    ///     println!("{}", result);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_async_read_write<R, W>(
+        reader: R,
+        writer: W,
+        method: ConnectionMethod,
+        codec: KdbCodec,
+        is_listener: bool,
+    ) -> Self
+    where
+        R: AsyncRead + Send + Sync + Unpin + 'static,
+        W: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let reader: BoxedReader = Box::new(reader);
+        let writer: BoxedWriter = Box::new(writer);
+        let framed = Framed::new(tokio::io::join(reader, writer), codec);
+        QStream::new(FramedStream::Io(framed), method, is_listener)
+    }
+
     /// Shutdown the socket for a q process.
     /// # Example
     /// See the example of [`connect`](#method.connect).
     pub async fn shutdown(mut self) -> Result<()> {
+        self.close().await
+    }
+
+    /// Shutdown the socket for a q process without consuming the stream. Unlike [`shutdown`](#method.shutdown)
+    ///  this can be called from a wrapper holding the stream by reference. Calling it on an already closed
+    ///  stream is a no-op returning `Ok(())`.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     socket.close().await?;
+    ///     // Second close does nothing
+    ///     socket.close().await?;
+    ///     assert!(socket.is_closed());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+
         // For TLS listener, send the close command
         if self.listener && matches!(self.method, ConnectionMethod::TLS) {
             self.send_async_message(&".kdbplus.close_tls_connection_[]")
                 .await?;
         }
 
+        // Mark as closed before touching the socket so that a failed shutdown is not retried on a dead fd
+        self.closed = true;
+
         // Close the underlying stream
-        match self.stream {
+        match &mut self.stream {
             FramedStream::Tcp(framed) => {
-                AsyncWriteExt::shutdown(&mut framed.into_inner()).await?;
+                AsyncWriteExt::shutdown(framed.get_mut()).await?;
             }
             FramedStream::Tls(framed) => {
                 if !self.listener {
-                    framed.into_inner().get_mut().shutdown()?;
+                    framed.get_mut().get_mut().shutdown()?;
                 }
             }
             #[cfg(unix)]
             FramedStream::Uds(framed) => {
-                AsyncWriteExt::shutdown(&mut framed.into_inner()).await?;
+                AsyncWriteExt::shutdown(framed.get_mut()).await?;
+            }
+            FramedStream::Io(framed) => {
+                AsyncWriteExt::shutdown(framed.get_mut()).await?;
             }
         }
         Ok(())
     }
 
+    /// Check if the socket has been shut down via [`close`](#method.close).
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Connect again to the q process this stream was connected to, replacing the underlying
+    ///  socket in place. Settings of the stream such as the compression mode, the deserialization
+    ///  limits and the forced endianness are kept, and so are the statistics.
+    /// # Note
+    /// - Error is returned for a stream built by [`accept`](#method.accept) or
+    ///  [`from_async_read_write`](#method.from_async_read_write).
+    /// - The credential used to connect is kept in memory for the lifetime of the stream.
+    /// - Messages buffered but not yet received on the old socket are discarded.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     loop {
+    ///         match socket.send_sync_message(&"heartbeat[]").await {
+    ///             Ok(result) => println!("{}", result),
+    ///             Err(Error::ConnectionClosed) => socket.reconnect().await?,
+    ///             Err(error) => return Err(error),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let parameters = self.connect_parameters.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "reconnect is only available for streams built by connect",
+            )
+        })?;
+        let mut codec = self.codec().clone();
+        let mut fresh = Self::connect_impl(
+            parameters.method,
+            &parameters.host,
+            parameters.port,
+            &parameters.credential,
+            codec.compression_mode(),
+            codec.validation_mode(),
+            parameters.dns_fallback.as_ref(),
+        )
+        .await?;
+        // Keep the settings of the old codec but take the capacity agreed in the new handshake
+        codec.set_peer_capacity(fresh.codec().peer_capacity());
+        // The compression state of the old socket must not leak into the new byte stream
+        #[cfg(feature = "transport-compression")]
+        codec.reset_transport_state();
+        *fresh.codec_mut() = codec;
+        std::mem::swap(&mut self.stream, &mut fresh.stream);
+        self.closed = false;
+        Ok(())
+    }
+
     /// Send a message with a specified message type without waiting for a response even for a synchronous message.
     ///  If you need to receive a response you need to use [`receive_message`](#method.receive_message).
     /// # Note
@@ -664,7 +1100,30 @@ impl QStream {
     /// # Example
     /// See the example of [`connect`](#method.connect).
     pub async fn send_message(&mut self, message: &dyn Query, message_type: u8) -> Result<()> {
-        let kdb_message = message.to_kdb_message(message_type);
+        self.send_kdb_message(message.to_kdb_message(message_type)).await
+    }
+
+    /// Send a message built by hand, e.g. with
+    ///  [`KdbMessage::with_encoding`](struct.KdbMessage.html#method.with_encoding) to serialize it
+    ///  in an endianness other than the one of the connection. Compression and hooks of the codec
+    ///  still apply.
+    /// # Parameters
+    /// - `kdb_message`: Message type and payload to send.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     let query = K::new_string(String::from("a:1"), qattribute::NONE);
+    ///     let message = KdbMessage::new(qmsg_type::asynchronous, query).with_encoding(0);
+    ///     socket.send_kdb_message(message).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn send_kdb_message(&mut self, kdb_message: KdbMessage) -> Result<()> {
+        let encoded = self.codec().encoded_bytes();
         match &mut self.stream {
             FramedStream::Tcp(framed) => {
                 framed.send(kdb_message).await?;
@@ -676,7 +1135,74 @@ impl QStream {
             FramedStream::Uds(framed) => {
                 framed.send(kdb_message).await?;
             }
+            FramedStream::Io(framed) => {
+                framed.send(kdb_message).await?;
+            }
         }
+        let bytes = self.codec().encoded_bytes() - encoded;
+        self.stats.messages_sent += 1;
+        self.stats.bytes_sent += bytes;
+        self.stats.last_activity = Some(Utc::now());
+        Ok(())
+    }
+
+    /// Write a complete pre-serialized IPC frame (header and body) to the socket as is, bypassing the codec.
+    ///  This avoids serializing the same message again and again, e.g. a constant heartbeat.
+    /// # Note
+    /// The frame is not compressed nor checked in release builds. In debug builds it panics if the bytes
+    ///  do not start with a valid header whose length matches the frame.
+    /// # Parameters
+    /// - `frame`: Bytes built by [`K::ipc_msg_encode`](struct.K.html#method.ipc_msg_encode) or equivalent.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     let heartbeat = K::new_string(String::from("heartbeat[]"), qattribute::NONE)
+    ///         .ipc_msg_encode(qmsg_type::asynchronous, false);
+    ///     socket.send_raw_frame(&heartbeat).await?;
+    ///     socket.send_raw_frame(&heartbeat).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn send_raw_frame(&mut self, frame: &[u8]) -> Result<()> {
+        debug_assert!(
+            is_valid_frame(frame),
+            "send_raw_frame: bytes do not form a complete IPC frame"
+        );
+        // Flush messages already queued in the codec so that the frame is not interleaved with them
+        match &mut self.stream {
+            FramedStream::Tcp(framed) => {
+                SinkExt::<KdbMessage>::flush(framed).await?;
+                let socket = framed.get_mut();
+                socket.write_all(frame).await?;
+                socket.flush().await?;
+            }
+            FramedStream::Tls(framed) => {
+                SinkExt::<KdbMessage>::flush(framed).await?;
+                let socket = framed.get_mut();
+                socket.write_all(frame).await?;
+                socket.flush().await?;
+            }
+            #[cfg(unix)]
+            FramedStream::Uds(framed) => {
+                SinkExt::<KdbMessage>::flush(framed).await?;
+                let socket = framed.get_mut();
+                socket.write_all(frame).await?;
+                socket.flush().await?;
+            }
+            FramedStream::Io(framed) => {
+                SinkExt::<KdbMessage>::flush(framed).await?;
+                let socket = framed.get_mut();
+                socket.write_all(frame).await?;
+                socket.flush().await?;
+            }
+        }
+        self.stats.messages_sent += 1;
+        self.stats.bytes_sent += frame.len() as u64;
+        self.stats.last_activity = Some(Utc::now());
         Ok(())
     }
 
@@ -705,7 +1231,7 @@ impl QStream {
         self.send_message(message, qmsg_type::synchronous).await?;
 
         // Receive the response
-        match self.receive_message().await? {
+        match self.receive_next(false).await? {
             (qmsg_type::response, response) => Ok(response),
             (_, message) => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -715,49 +1241,261 @@ impl QStream {
         }
     }
 
+    /// Send a message synchronously like [`send_sync_message`](#method.send_sync_message) but give
+    ///  up if the response does not arrive within `timeout`.
+    /// # Note
+    /// The connection stays usable after a timeout, but the remote q process may still answer the
+    ///  abandoned message. Its response then arrives before the response of the next synchronous
+    ///  message and is returned in place of it. Unless the remote process is known to have dropped
+    ///  the message, read the late response with [`receive_message`](#method.receive_message) or
+    ///  [`reconnect`](#method.reconnect) before retrying. A message whose write was cut short is
+    ///  kept in the write buffer and finished by the next send, so framing is never broken.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// - `timeout`: Maximum time to wait for sending the message and receiving the response.
+    /// # Errors
+    /// `Error::Timeout` if the response did not arrive in time.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     match socket.send_sync_message_timeout(&"til 3", Duration::from_secs(1)).await {
+    ///         Ok(response) => println!("{}", response),
+    ///         Err(Error::Timeout(_)) => println!("timed out"),
+    ///         Err(error) => return Err(error),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn send_sync_message_timeout(
+        &mut self,
+        message: &dyn Query,
+        timeout: Duration,
+    ) -> Result<K> {
+        tokio::time::timeout(timeout, self.send_sync_message(message))
+            .await
+            .map_err(|_| Error::Timeout(timeout))?
+    }
+
+    /// Send a message synchronously and return a [`Request`] future resolving to the response.
+    ///  This is equivalent to [`send_sync_message`](#method.send_sync_message) but the message is
+    ///  converted up front, so that the future only borrows the stream and can be named, stored or
+    ///  composed with `tokio::select!` and `tokio::time::timeout`.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     tokio::select! {
+    ///         response = socket.request(&"til 3") => println!("{}", response?),
+    ///         _ = tokio::time::sleep(Duration::from_secs(1)) => println!("timed out"),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn request(&mut self, message: &dyn Query) -> Request<'_> {
+        let payload = message.to_kdb_message(qmsg_type::synchronous).payload;
+        Request {
+            inner: Box::pin(async move { self.send_sync_message(&payload).await }),
+        }
+    }
+
+    /// Send a trivial synchronous query (`1b`) and wait for its response. q processes the
+    ///  messages of a handle in order, so once this returns every asynchronous message sent
+    ///  before it has been processed by the remote q process.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     socket.send_async_message(&"upd[`trade; data]").await?;
+    ///     socket.sync_barrier().await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn sync_barrier(&mut self) -> Result<()> {
+        self.send_sync_message(&"1b").await.map(|_| ())
+    }
+
     /// Receive a message from a remote q process. The received message is parsed as `K` and message type is
     ///  stored in the first returned value.
+    /// # Note
+    /// Empty asynchronous messages (generic null or an empty list) sent as pings by some gateways
+    ///  are delivered by default. See [`set_skip_empty_async`](#method.set_skip_empty_async) to
+    ///  drop them.
+    /// # Errors
+    /// - `Error::ConnectionClosed` if the peer closed the connection between messages.
+    /// - `Error::Protocol` if the received bytes do not form a valid message, e.g. a corrupt
+    ///  header or a connection closed in the middle of a message.
+    /// - `Error::NetworkError` if reading from the socket failed.
     /// # Example
     /// See the example of [`accept`](#method.accept).
     pub async fn receive_message(&mut self) -> Result<(u8, K)> {
-        match &mut self.stream {
-            FramedStream::Tcp(framed) => match framed.next().await {
-                Some(Ok(response)) => Ok((response.message_type, response.payload)),
-                Some(Err(e)) => Err(io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    format!("Connection dropped: {}", e),
-                )
-                .into()),
-                None => Err(
-                    io::Error::new(io::ErrorKind::ConnectionAborted, "Connection closed").into(),
-                ),
-            },
-            FramedStream::Tls(framed) => match framed.next().await {
-                Some(Ok(response)) => Ok((response.message_type, response.payload)),
-                Some(Err(e)) => Err(io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    format!("Connection dropped: {}", e),
-                )
-                .into()),
-                None => Err(
-                    io::Error::new(io::ErrorKind::ConnectionAborted, "Connection closed").into(),
-                ),
-            },
-            #[cfg(unix)]
-            FramedStream::Uds(framed) => match framed.next().await {
-                Some(Ok(response)) => Ok((response.message_type, response.payload)),
-                Some(Err(e)) => Err(io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    format!("Connection dropped: {}", e),
-                )
-                .into()),
-                None => Err(
-                    io::Error::new(io::ErrorKind::ConnectionAborted, "Connection closed").into(),
-                ),
-            },
+        self.receive_next(true).await
+    }
+
+    /// Run `callback` every `interval` while [`receive_message`](#method.receive_message) waits
+    ///  for a message, like `.z.ts` of q runs while the process is idle. The first call happens
+    ///  one interval after registration and ticks missed during a long callback are not made up.
+    ///  An error returned by the callback is returned by `receive_message`. Registering another
+    ///  timer replaces the current one.
+    /// # Note
+    /// The callback does not run while [`send_sync_message`](#method.send_sync_message) waits for a
+    ///  response.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::accept(ConnectionMethod::TCP, "127.0.0.1", 5000).await?;
+    ///     // Publish the time every second
+    ///     socket.on_timer(Duration::from_secs(1), |socket| {
+    ///         Box::pin(async move {
+    ///             let now = K::new_timestamp(chrono::Utc::now());
+    ///             socket.send_async_message(&k!([k!(sym: "upd"), now])).await
+    ///         })
+    ///     });
+    ///     loop {
+    ///         let (_, message) = socket.receive_message().await?;
+    ///         println!("{}", message);
+    ///     }
+    /// }
+    /// ```
+    pub fn on_timer<F>(&mut self, interval: Duration, callback: F)
+    where
+        F: for<'a> FnMut(&'a mut QStream) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let mut interval = tokio::time::interval_at(Instant::now() + interval, interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        self.timer = Some(Timer {
+            interval,
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Receive the next message which is not a skipped ping, running the timer while waiting if
+    ///  `run_timer` is `true`.
+    async fn receive_next(&mut self, run_timer: bool) -> Result<(u8, K)> {
+        loop {
+            let received = match self.timer.take() {
+                Some(timer) if run_timer => self.receive_frame_or_tick(timer).await?,
+                timer => {
+                    self.timer = timer;
+                    Some(self.receive_frame().await?)
+                }
+            };
+            match received {
+                Some((message_type, message))
+                    if !(self.skip_empty_async
+                        && message_type == qmsg_type::asynchronous
+                        && is_empty_message(&message)) =>
+                {
+                    return Ok((message_type, message))
+                }
+                _ => continue,
+            }
         }
     }
 
+    /// Receive a message or run the timer, whichever comes first. `None` is returned after the
+    ///  timer ran.
+    async fn receive_frame_or_tick(&mut self, mut timer: Timer) -> Result<Option<(u8, K)>> {
+        let received = tokio::select! {
+            received = self.receive_frame() => Some(received),
+            _ = timer.interval.tick() => None,
+        };
+        let result = match received {
+            Some(received) => received.map(Some),
+            None => (timer.callback)(self).await.map(|_| None),
+        };
+        // The callback may have registered another timer
+        if self.timer.is_none() {
+            self.timer = Some(timer);
+        }
+        result
+    }
+
+    /// Set whether empty asynchronous messages (generic null or an empty list) are dropped by
+    ///  [`receive_message`](#method.receive_message) instead of being delivered. Some kdb+ gateways
+    ///  send such messages periodically as pings. Defaults to `false`.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     socket.set_skip_empty_async(true);
+    ///     // Pings from the gateway never wake us up
+    ///     let (_, message) = socket.receive_message().await?;
+    ///     println!("{}", message);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_skip_empty_async(&mut self, skip: bool) {
+        self.skip_empty_async = skip;
+    }
+
+    /// Check if empty asynchronous messages are dropped by `receive_message`.
+    pub fn skip_empty_async(&self) -> bool {
+        self.skip_empty_async
+    }
+
+    /// Get statistics of messages sent and received over this connection so far.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     socket.send_sync_message(&"1+1").await?;
+    ///     let stats = socket.stats();
+    ///     println!("sent {} bytes, received {} bytes", stats.bytes_sent, stats.bytes_received);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stats(&self) -> ConnectionStats {
+        self.stats.clone()
+    }
+
+    /// Receive a single message from the underlying framed stream.
+    async fn receive_frame(&mut self) -> Result<(u8, K)> {
+        let decoded = self.codec().decoded_bytes();
+        let received: Result<(u8, K)> = match &mut self.stream {
+            FramedStream::Tcp(framed) => received_frame(framed.next().await),
+            FramedStream::Tls(framed) => received_frame(framed.next().await),
+            #[cfg(unix)]
+            FramedStream::Uds(framed) => received_frame(framed.next().await),
+            FramedStream::Io(framed) => received_frame(framed.next().await),
+        };
+        let (message_type, message) = received?;
+        self.stats.messages_received += 1;
+        self.stats.bytes_received += self.codec().decoded_bytes() - decoded;
+        self.stats.last_activity = Some(Utc::now());
+        Ok((message_type, message))
+    }
+
     /// Return underlying connection type. One of `TCP`, `TLS` or `UDS`.
     /// # Example
     /// See the example of [`connect`](#method.connect).
@@ -768,12 +1506,223 @@ impl QStream {
             ConnectionMethod::UDS => "UDS",
         }
     }
+
+    /// Return the compression mode applied to outgoing messages.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let socket = QStream::connect_with_options(
+    ///         ConnectionMethod::TCP,
+    ///         "localhost",
+    ///         5000,
+    ///         "user:pass",
+    ///         CompressionMode::Auto,
+    ///         ValidationMode::Strict,
+    ///     )
+    ///     .await?;
+    ///     assert_eq!(socket.compression_mode(), CompressionMode::Auto);
+    ///     // `Auto` never compresses on a local connection
+    ///     assert!(socket.is_local());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn compression_mode(&self) -> CompressionMode {
+        self.codec().compression_mode()
+    }
+
+    /// Get the validation mode of incoming messages set by
+    ///  [`connect_with_options`](#method.connect_with_options).
+    pub fn validation_mode(&self) -> ValidationMode {
+        self.codec().validation_mode()
+    }
+
+    /// Check if the connection was detected as local. In `CompressionMode::Auto` messages are
+    ///  compressed only on a remote connection.
+    /// # Example
+    /// See the example of [`compression_mode`](#method.compression_mode).
+    pub fn is_local(&self) -> bool {
+        self.codec().is_local()
+    }
+
+    /// Set the endianness of outgoing messages: `0` for big endian and `1` for little endian.
+    ///  `None` restores the endianness of the host.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     // Emit big endian messages even on a little endian host
+    ///     socket.set_force_encoding(Some(0));
+    ///     assert_eq!(socket.force_encoding(), Some(0));
+    ///     socket.send_sync_message(&"2+2").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_force_encoding(&mut self, encoding: Option<u8>) {
+        self.codec_mut().set_force_encoding(encoding);
+    }
+
+    /// Get the forced endianness of outgoing messages.
+    /// # Example
+    /// See the example of [`set_force_encoding`](#method.set_force_encoding).
+    pub fn force_encoding(&self) -> Option<u8> {
+        self.codec().force_encoding()
+    }
+
+    /// Return features supported by the peer according to the capacity agreed in the handshake.
+    ///  Messages are not compressed for a peer without compression support.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     if let Some(capability) = socket.peer_capability() {
+    ///         println!("GUID supported: {}", capability.supports_guid());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn peer_capability(&self) -> Option<Capability> {
+        self.codec().peer_capacity().map(ipc_capability)
+    }
+
+    /// Return the OS-level credentials of the peer process of a UDS connection. An acceptor can use
+    ///  them to authorize a client beyond the credential sent in the handshake.
+    /// # Note
+    /// Error is returned for TCP and TLS connections.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::accept(ConnectionMethod::UDS, "", 5000).await?;
+    ///     let credential = socket.peer_cred()?;
+    ///     if credential.uid != 0 {
+    ///         socket.shutdown().await?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn peer_cred(&self) -> Result<PeerCred> {
+        match &self.stream {
+            FramedStream::Uds(framed) => {
+                let credential = framed.get_ref().peer_cred()?;
+                Ok(PeerCred {
+                    uid: credential.uid(),
+                    gid: credential.gid(),
+                    pid: credential.pid(),
+                })
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "peer credentials are only available for UDS connections",
+            )
+            .into()),
+        }
+    }
+
+    /// Return the number of bytes already read from the socket but not yet forming a complete message.
+    ///  A consumer applying backpressure can use this to tell whether a message is partially received.
+    /// # Example
+    /// ```no_run
+    /// use kdb_codec::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "user:pass").await?;
+    ///     let (_, message) = socket.receive_message().await?;
+    ///     if socket.buffered_read_bytes() > 0 {
+    ///         println!("next message is on its way");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn buffered_read_bytes(&self) -> usize {
+        match &self.stream {
+            FramedStream::Tcp(framed) => framed.read_buffer().len(),
+            FramedStream::Tls(framed) => framed.read_buffer().len(),
+            #[cfg(unix)]
+            FramedStream::Uds(framed) => framed.read_buffer().len(),
+            FramedStream::Io(framed) => framed.read_buffer().len(),
+        }
+    }
+
+    /// Return the codec of the underlying framed stream.
+    fn codec(&self) -> &KdbCodec {
+        match &self.stream {
+            FramedStream::Tcp(framed) => framed.codec(),
+            FramedStream::Tls(framed) => framed.codec(),
+            #[cfg(unix)]
+            FramedStream::Uds(framed) => framed.codec(),
+            FramedStream::Io(framed) => framed.codec(),
+        }
+    }
+
+    /// Return the mutable codec of the underlying framed stream.
+    fn codec_mut(&mut self) -> &mut KdbCodec {
+        match &mut self.stream {
+            FramedStream::Tcp(framed) => framed.codec_mut(),
+            FramedStream::Tls(framed) => framed.codec_mut(),
+            #[cfg(unix)]
+            FramedStream::Uds(framed) => framed.codec_mut(),
+            FramedStream::Io(framed) => framed.codec_mut(),
+        }
+    }
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Private Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+//%% QStream Sender %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Check if bytes start with a valid message header whose length covers exactly the bytes.
+fn is_valid_frame(frame: &[u8]) -> bool {
+    match MessageHeader::from_bytes(frame) {
+        Ok(header) => {
+            header.encoding <= 1
+                && header.message_type <= qmsg_type::response
+                && header.compressed <= 1
+                && header.length as usize == frame.len()
+        }
+        Err(_) => false,
+    }
+}
+
+//%% QStream Receiver %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Classify the next item of a framed stream. The end of the stream is a clean close, an error of
+///  kind `InvalidData` raised by the codec is a protocol error and any other error comes from the
+///  socket.
+fn received_frame(item: Option<io::Result<KdbMessage>>) -> Result<(u8, K)> {
+    match item {
+        Some(Ok(response)) => Ok((response.message_type, response.payload)),
+        Some(Err(error)) if error.kind() == io::ErrorKind::InvalidData => {
+            Err(Error::Protocol(error.to_string()))
+        }
+        Some(Err(error)) => Err(Error::NetworkError(error)),
+        None => Err(Error::ConnectionClosed),
+    }
+}
+
+/// Check if a message is empty, i.e., a generic null or a list without elements.
+fn is_empty_message(message: &K) -> bool {
+    match message.get_type() {
+        qtype::NULL => matches!(message.0.value, k0_inner::null(_)),
+        qtype::COMPOUND_LIST..=qtype::TIME_LIST => message.len() == 0,
+        _ => false,
+    }
+}
+
 //%% QStream Connector %%//vvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Inner function of `connect_tcp` and `connect_tls` to establish a TCP connection with the sepcified
@@ -785,27 +1734,26 @@ impl QStream {
 /// # Parameters
 /// - `host`: Hostname or IP address of the target q/kdb+ process.
 /// - `port`: Port of the target q process
-async fn connect_tcp_impl(host: &str, port: u16) -> Result<TcpStream> {
-    // DNS system resolver (should not fail)
-    let resolver =
-        TokioAsyncResolver::tokio_from_system_conf().expect("failed to create DNS resolver");
-
+/// - `dns_fallback`: DNS resolver configuration used if the system one cannot be loaded.
+async fn connect_tcp_impl(
+    host: &str,
+    port: u16,
+    dns_fallback: Option<&ResolverConfig>,
+) -> Result<TcpStream> {
     // Check if we were given an IP address
-    let ips;
-    if let Ok(ip) = host.parse::<IpAddr>() {
-        ips = vec![ip.to_string()]
+    let addresses;
+    if let Some(address) = parse_socket_address(host, port) {
+        addresses = vec![address]
     } else {
         // Resolve hostname to IP addresses
-        let response = resolver
-            .lookup_ip(host)
-            .await
-            .expect(&format!("failed to resolve host: {}", host));
-        ips = response.iter().map(|ip| ip.to_string()).collect();
+        let system = read_system_conf().map_err(ResolveError::from);
+        let resolver = build_resolver(system, dns_fallback)?;
+        addresses = resolve_host(&resolver, host, port).await?;
     }
 
-    // Try each resolved IP
-    for answer in ips {
-        match TcpStream::connect(format!("{}:{}", answer, port)).await {
+    // Try each resolved IP. `SocketAddr` takes care of bracketing IPv6 addresses.
+    for address in addresses {
+        match TcpStream::connect(address).await {
             Ok(socket) => return Ok(socket),
             Err(_) => continue,
         }
@@ -814,8 +1762,101 @@ async fn connect_tcp_impl(host: &str, port: u16) -> Result<TcpStream> {
     Err(io::Error::new(io::ErrorKind::ConnectionRefused, "failed to connect").into())
 }
 
+/// Look up the IP addresses of a hostname. A failed lookup, e.g. during a DNS outage, is returned
+///  as `Error::DnsResolution` so that the caller can retry.
+async fn resolve_host(
+    resolver: &TokioAsyncResolver,
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>> {
+    let response = resolver
+        .lookup_ip(host)
+        .await
+        .map_err(|error| Error::DnsResolution(format!("{}: {}", host, error)))?;
+    Ok(response
+        .iter()
+        .map(|ip| SocketAddr::new(ip, port))
+        .collect())
+}
+
+/// Build the DNS resolver from the system configuration, or from `fallback` if the system
+///  configuration could not be loaded (e.g. `/etc/resolv.conf` is missing in a container).
+///  Error is returned if neither is available.
+fn build_resolver(
+    system: std::result::Result<(ResolverConfig, ResolverOpts), ResolveError>,
+    fallback: Option<&ResolverConfig>,
+) -> Result<TokioAsyncResolver> {
+    let (config, options) = match (system, fallback) {
+        (Ok(system), _) => system,
+        (Err(_), Some(config)) => (config.clone(), ResolverOpts::default()),
+        (Err(error), None) => return Err(io::Error::from(error).into()),
+    };
+    TokioAsyncResolver::tokio(config, lookup_options(options))
+        .map_err(|error| io::Error::from(error).into())
+}
+
+/// Query both A and AAAA records so that every address of a host is tried in order, IPv4 first.
+///  By default AAAA records are only queried if a host has no A record.
+fn lookup_options(mut options: ResolverOpts) -> ResolverOpts {
+    options.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+    options
+}
+
+/// Check if a host given to connect is on this machine or its link: `localhost` or a loopback or
+///  link-local address.
+fn is_local_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || parse_socket_address(host, 0).is_some_and(|address| is_local_ip(address.ip()))
+}
+
+/// Check if an IP address is a loopback or link-local address.
+fn is_local_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_link_local(),
+        // fe80::/10
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || (ip.segments()[0] & 0xffc0) == 0xfe80
+                || ip.to_ipv4_mapped().is_some_and(|ip| ip.is_loopback())
+        }
+    }
+}
+
+/// Parse a host given as an IP address into a socket address. An IPv6 address may be enclosed in brackets
+///  and may carry a numeric scope ID, e.g. `[fe80::1%2]`. `None` is returned for a hostname.
+fn parse_socket_address(host: &str, port: u16) -> Option<SocketAddr> {
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        Some(SocketAddr::new(ip, port))
+    } else {
+        // Scoped IPv6 address
+        format!("[{}]:{}", host, port)
+            .parse::<SocketAddrV6>()
+            .ok()
+            .map(SocketAddr::V6)
+    }
+}
+
 /// Send a credential and receive a common capacity.
 pub async fn handshake<S>(socket: &mut S, credential_: &str, method_bytes: &str) -> Result<()>
+where
+    S: Unpin + AsyncWriteExt + AsyncReadExt,
+{
+    handshake_with_capacity(socket, credential_, method_bytes)
+        .await
+        .map(|_| ())
+}
+
+/// Send a credential and receive a common capacity. Returns the capacity byte answered by the
+///  peer (see [`ipc_capability`]).
+pub async fn handshake_with_capacity<S>(
+    socket: &mut S,
+    credential_: &str,
+    method_bytes: &str,
+) -> Result<u8>
 where
     S: Unpin + AsyncWriteExt + AsyncReadExt,
 {
@@ -826,18 +1867,25 @@ where
     // Read a single byte
     let mut capacity = [0u8; 1];
     socket.read_exact(&mut capacity).await?;
-    Ok(())
+    Ok(capacity[0])
 }
 
 /// Connect to q process running on a specified `host` and `port` via TCP with a credential `username:password`.
+///  Returns the socket and the capacity agreed in the handshake.
 /// # Parameters
 /// - `host`: Hostname or IP address of the target q process.
 /// - `port`: Port of the target q process.
 /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
-async fn connect_tcp(host: &str, port: u16, credential: &str) -> Result<TcpStream> {
-    let mut socket = connect_tcp_impl(host, port).await?;
-    handshake(&mut socket, credential, "\x03\x00").await?;
-    Ok(socket)
+/// - `dns_fallback`: DNS resolver configuration used if the system one cannot be loaded.
+async fn connect_tcp(
+    host: &str,
+    port: u16,
+    credential: &str,
+    dns_fallback: Option<&ResolverConfig>,
+) -> Result<(TcpStream, u8)> {
+    let mut socket = connect_tcp_impl(host, port, dns_fallback).await?;
+    let capacity = handshake_with_capacity(&mut socket, credential, "\x03\x00").await?;
+    Ok((socket, capacity))
 }
 
 /// TLS version of `connect_tcp`.
@@ -845,9 +1893,15 @@ async fn connect_tcp(host: &str, port: u16, credential: &str) -> Result<TcpStrea
 /// - `host`: Hostname or IP address of the target q process.
 /// - `port`: Port of the target q process.
 /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
-async fn connect_tls(host: &str, port: u16, credential: &str) -> Result<TlsStream<TcpStream>> {
+/// - `dns_fallback`: DNS resolver configuration used if the system one cannot be loaded.
+async fn connect_tls(
+    host: &str,
+    port: u16,
+    credential: &str,
+    dns_fallback: Option<&ResolverConfig>,
+) -> Result<(TlsStream<TcpStream>, u8)> {
     // Connect via TCP
-    let socket_ = connect_tcp_impl(host, port).await?;
+    let socket_ = connect_tcp_impl(host, port, dns_fallback).await?;
     // Use TLS
     let connector = TlsConnector::from(TlsConnectorInner::new().unwrap());
     let mut socket = connector
@@ -855,8 +1909,8 @@ async fn connect_tls(host: &str, port: u16, credential: &str) -> Result<TlsStrea
         .await
         .expect("failed to create TLS session");
     // Handshake
-    handshake(&mut socket, credential, "\x03\x00").await?;
-    Ok(socket)
+    let capacity = handshake_with_capacity(&mut socket, credential, "\x03\x00").await?;
+    Ok((socket, capacity))
 }
 
 /// Build a path of a socket file.
@@ -871,11 +1925,12 @@ fn create_sockfile_path(port: u16) -> Result<String> {
 }
 
 /// Connect to q process running on the specified `port` via Unix domain socket with a credential `username:password`.
+///  Returns the socket and the capacity agreed in the handshake.
 /// # Parameters
 /// - `port`: Port of the target q process.
 /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
 #[cfg(unix)]
-async fn connect_uds(port: u16, credential: &str) -> Result<UnixStream> {
+async fn connect_uds(port: u16, credential: &str) -> Result<(UnixStream, u8)> {
     // Create a file path.
     let uds_path = create_sockfile_path(port)?;
     let abstract_sockfile_ = format!("\x00{}", uds_path);
@@ -883,110 +1938,125 @@ async fn connect_uds(port: u16, credential: &str) -> Result<UnixStream> {
     // Connect to kdb+.
     let mut socket = UnixStream::connect(&abstract_sockfile).await?;
     // Handshake
-    handshake(&mut socket, credential, "\x06\x00").await?;
+    let capacity = handshake_with_capacity(&mut socket, credential, "\x06\x00").await?;
 
-    Ok(socket)
+    Ok((socket, capacity))
 }
 
 //%% QStream Acceptor %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Read username, password, capacity and null byte from q client at the connection and does authentication.
-///  Close the handle if the authentication fails.
-async fn read_client_input<S>(socket: &mut S) -> Result<()>
+///  Close the handle if the authentication fails. Returns the capacity sent back to the client.
+async fn read_client_input<S>(socket: &mut S) -> Result<u8>
 where
     S: Unpin + AsyncWriteExt + AsyncReadExt,
 {
-    let debug_auth = matches!(std::env::var("KDBPLUS_DEBUG_AUTH").ok().as_deref(), Some("1"));
+    let debug_auth = matches!(
+        std::env::var("KDBPLUS_DEBUG_AUTH").ok().as_deref(),
+        Some("1")
+    );
+    let handshake = match read_handshake(socket).await {
+        Ok(handshake) => handshake,
+        Err(error) => {
+            // Client closed the connection or sent a malformed handshake.
+            socket.shutdown().await?;
+            return Err(error);
+        }
+    };
+    let (user, password, capacity) = match parse_handshake(&handshake) {
+        Ok(credential) => credential,
+        Err(error) => {
+            if debug_auth {
+                eprintln!("[acceptor auth] invalid credential format");
+            }
+            // Authentication failure.
+            socket.shutdown().await?;
+            return Err(error);
+        }
+    };
+    if debug_auth {
+        eprintln!(
+            "[acceptor auth] user='{}' capacity=0x{:02x}",
+            user, capacity
+        );
+    }
+    if let Some(encoded) = ACCOUNTS.get(user) {
+        // User exists
+        let mut hasher = Sha1::new();
+        hasher.update(password.as_bytes());
+        let encoded_password = hasher.digest().to_string();
+        if encoded == &encoded_password {
+            // Client passed correct credential
+            if debug_auth {
+                eprintln!("[acceptor auth] success");
+            }
+            socket.write_all(&[capacity; 1]).await?;
+            return Ok(capacity);
+        } else if debug_auth {
+            eprintln!("[acceptor auth] password mismatch");
+        }
+    } else if debug_auth {
+        eprintln!("[acceptor auth] unknown user");
+    }
+    // Authentication failure.
+    // Close connection.
+    socket.shutdown().await?;
+    Err(io::Error::new(io::ErrorKind::InvalidData, "authentication failed").into())
+}
+
+/// Read a client handshake up to (excluding) the terminating null byte. The handshake may arrive
+///  in any number of fragments.
+async fn read_handshake<S>(socket: &mut S) -> Result<Vec<u8>>
+where
+    S: Unpin + AsyncReadExt,
+{
     // Buffer to read inputs.
     let mut client_input = [0u8; 32];
-    // credential will be built from small fractions of bytes.
-    let mut passed_credential = String::new();
+    // handshake will be built from small fractions of bytes.
+    let mut handshake = Vec::new();
     loop {
-        // Read a client credential input.
-        match socket.read(&mut client_input).await {
-            Ok(0) => {
-                // Client closed the connection.
-                socket.shutdown().await?;
-                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "client disconnected").into());
+        match socket.read(&mut client_input).await? {
+            0 => {
+                return Err(
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "client disconnected").into(),
+                );
             }
-            Ok(n) => {
+            n => {
                 let chunk = &client_input[..n];
-                // Locate a byte denoting a capacity.
-                if let Some(index) = chunk.iter().position(|byte| *byte == 0x03 || *byte == 0x06)
-                {
-                    let capacity = chunk[index];
-                    passed_credential
-                        .push_str(str::from_utf8(&chunk[0..index]).expect("invalid bytes"));
-                    let credential = passed_credential.as_str().split(':').collect::<Vec<&str>>();
-                    if credential.len() < 2 {
-                        if debug_auth {
-                            eprintln!("[acceptor auth] invalid credential format");
-                        }
-                        // Authentication failure.
-                        socket.shutdown().await?;
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "authentication failed",
-                        )
-                        .into());
-                    }
-                    if debug_auth {
-                        eprintln!(
-                            "[acceptor auth] user='{}' capacity=0x{:02x}",
-                            credential[0], capacity
-                        );
-                    }
-                    if let Some(encoded) = ACCOUNTS.get(&credential[0].to_string()) {
-                        // User exists
-                        let mut hasher = Sha1::new();
-                        hasher.update(credential[1].as_bytes());
-                        let encoded_password = hasher.digest().to_string();
-                        if encoded == &encoded_password {
-                            // Client passed correct credential
-                            if debug_auth {
-                                eprintln!("[acceptor auth] success");
-                            }
-                            socket.write_all(&[capacity; 1]).await?;
-                            return Ok(());
-                        } else {
-                            if debug_auth {
-                                eprintln!("[acceptor auth] password mismatch");
-                            }
-                            // Authentication failure.
-                            // Close connection.
-                            socket.shutdown().await?;
-                            return Err(io::Error::new(
-                                io::ErrorKind::InvalidData,
-                                "authentication failed",
-                            )
-                            .into());
-                        }
-                    } else {
-                        if debug_auth {
-                            eprintln!("[acceptor auth] unknown user");
-                        }
-                        // Authentication failure.
-                        // Close connection.
-                        socket.shutdown().await?;
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "authentication failed",
-                        )
-                        .into());
-                    }
-                } else {
-                    // Append a fraction of credential
-                    passed_credential
-                        .push_str(str::from_utf8(chunk).expect("invalid bytes"));
+                // Only the null byte terminates the handshake. Capacity byte is located by position later.
+                if let Some(index) = chunk.iter().position(|byte| *byte == 0x00) {
+                    handshake.extend_from_slice(&chunk[..index]);
+                    return Ok(handshake);
+                }
+                handshake.extend_from_slice(chunk);
+                if handshake.len() > MAX_HANDSHAKE_LENGTH {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "client handshake is too long",
+                    )
+                    .into());
                 }
-            }
-            Err(error) => {
-                return Err(error.into());
             }
         }
     }
 }
 
+/// Split a client handshake (without the terminating null byte) into username, password and capacity.
+///  The capacity is the last byte only when its value is a valid capacity; a client without capacity
+///  byte is treated as capacity 0. Password may contain `:` or bytes equal to a capacity.
+fn parse_handshake(handshake: &[u8]) -> Result<(&str, &str, u8)> {
+    let (credential, capacity) = match handshake.split_last() {
+        Some((&capacity, credential)) if capacity <= MAX_CAPACITY => (credential, capacity),
+        _ => (handshake, 0),
+    };
+    let credential = str::from_utf8(credential)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "authentication failed"))?;
+    match credential.split_once(':') {
+        Some((user, password)) => Ok((user, password, capacity)),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, "authentication failed").into()),
+    }
+}
+
 /// Check if server key exists and return teh contents.
 async fn build_identity_from_cert() -> Result<Identity> {
     // Check if server key exists.
@@ -1018,3 +2088,168 @@ async fn build_identity_from_cert() -> Result<Identity> {
         );
     }
 }
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Tests
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_handshake_long_credential() {
+        let credential = format!("{}:{}", "u".repeat(15), "p".repeat(24));
+        assert_eq!(credential.len(), 40);
+        let mut handshake = credential.into_bytes();
+        handshake.push(0x03);
+
+        let (user, password, capacity) = parse_handshake(&handshake).unwrap();
+        assert_eq!(user, "u".repeat(15));
+        assert_eq!(password, "p".repeat(24));
+        assert_eq!(capacity, 3);
+    }
+
+    #[test]
+    fn test_parse_handshake_capacity_byte_in_password() {
+        let (user, password, capacity) = parse_handshake(b"user:pa\x03ss:word\x06").unwrap();
+        assert_eq!(user, "user");
+        assert_eq!(password, "pa\x03ss:word");
+        assert_eq!(capacity, 6);
+
+        // Client without capacity byte
+        let (user, password, capacity) = parse_handshake(b"user:pass").unwrap();
+        assert_eq!((user, password, capacity), ("user", "pass", 0));
+
+        assert!(parse_handshake(b"userpass\x03").is_err());
+        assert!(parse_handshake(b"user:\xffpass\x03").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_handshake_fragmented() {
+        let credential = format!("{}:{}\x03\x00", "u".repeat(15), "p\x03".repeat(12));
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let writer = tokio::spawn(async move {
+            // Send in small fragments splitting the capacity byte from the null byte
+            for fragment in credential.as_bytes().chunks(7) {
+                client.write_all(fragment).await.unwrap();
+                client.flush().await.unwrap();
+                tokio::task::yield_now().await;
+            }
+            client
+        });
+
+        let handshake = read_handshake(&mut server).await.unwrap();
+        let (user, password, capacity) = parse_handshake(&handshake).unwrap();
+        assert_eq!(user, "u".repeat(15));
+        assert_eq!(password, "p\x03".repeat(12));
+        assert_eq!(capacity, 3);
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_handshake_rejects_oversized_input() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let writer = tokio::spawn(async move {
+            let _ = client.write_all(&[b'a'; MAX_HANDSHAKE_LENGTH + 64]).await;
+        });
+
+        assert!(read_handshake(&mut server).await.is_err());
+        drop(server);
+        writer.await.unwrap();
+    }
+
+    #[test]
+    fn test_is_valid_frame() {
+        let frame = K::new_long(1).ipc_msg_encode(qmsg_type::synchronous, false);
+        assert!(is_valid_frame(&frame));
+        // Truncated
+        assert!(!is_valid_frame(&frame[..frame.len() - 1]));
+        assert!(!is_valid_frame(&frame[..4]));
+        // Unknown message type
+        let mut invalid = frame.clone();
+        invalid[1] = 3;
+        assert!(!is_valid_frame(&invalid));
+    }
+
+    #[test]
+    fn test_parse_socket_address() {
+        let address = parse_socket_address("127.0.0.1", 5000).unwrap();
+        assert_eq!(address.to_string(), "127.0.0.1:5000");
+
+        // IPv6 addresses are bracketed
+        let address = parse_socket_address("::1", 5000).unwrap();
+        assert_eq!(address.to_string(), "[::1]:5000");
+        let address = parse_socket_address("[::1]", 5000).unwrap();
+        assert_eq!(address.to_string(), "[::1]:5000");
+
+        // Scope ID is kept
+        let address = parse_socket_address("fe80::1%2", 5000).unwrap();
+        assert_eq!(address.to_string(), "[fe80::1%2]:5000");
+
+        // Hostname needs to be resolved
+        assert!(parse_socket_address("localhost", 5000).is_none());
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        for host in ["localhost", "LOCALHOST", "127.0.0.1", "127.1.2.3", "::1", "[::1]"] {
+            assert!(is_local_host(host), "{}", host);
+        }
+        for host in ["169.254.10.1", "fe80::1", "[fe80::1%2]", "::ffff:127.0.0.1"] {
+            assert!(is_local_host(host), "{}", host);
+        }
+        for host in ["0.0.0.0", "10.0.0.1", "2001:db8::1", "::ffff:10.0.0.1", "kdb.example.com"] {
+            assert!(!is_local_host(host), "{}", host);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_resolver_falls_back_without_system_config() {
+        // Simulate missing `/etc/resolv.conf`
+        let missing = || Err(ResolveError::from("no system DNS configuration"));
+
+        let resolver = build_resolver(missing(), Some(&ResolverConfig::cloudflare())).unwrap();
+        // An IP literal is answered without querying the name servers
+        let response = resolver.lookup_ip("127.0.0.1").await.unwrap();
+        assert_eq!(response.iter().collect::<Vec<_>>(), vec![IpAddr::from([127, 0, 0, 1])]);
+
+        // Error instead of panic without a fallback
+        assert!(build_resolver(missing(), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_host_failure_is_error() {
+        use trust_dns_resolver::config::NameServerConfigGroup;
+
+        // Name server which refuses every query
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = ResolverConfig::from_parts(
+            None,
+            Vec::new(),
+            NameServerConfigGroup::from_ips_clear(&["127.0.0.1".parse().unwrap()], port, true),
+        );
+        let mut options = ResolverOpts::default();
+        options.timeout = Duration::from_millis(200);
+        options.attempts = 0;
+        let resolver = TokioAsyncResolver::tokio(config, options).unwrap();
+
+        let result = resolve_host(&resolver, "kdb.invalid", 5000).await;
+        match result {
+            Err(Error::DnsResolution(description)) => {
+                assert!(description.starts_with("kdb.invalid: "), "{}", description)
+            }
+            other => panic!("expected DnsResolution, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lookup_options_query_ipv4_and_ipv6() {
+        let options = lookup_options(ResolverOpts::default());
+        assert_eq!(options.ip_strategy, LookupIpStrategy::Ipv4AndIpv6);
+    }
+}
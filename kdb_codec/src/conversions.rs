@@ -1,7 +1,7 @@
 //! Conversion functions between q types and Rust types.
 
 use crate::error::Error;
-use crate::qconsts::qnull_base;
+use crate::qconsts::{qinf_base, qninf_base, qnull_base};
 use crate::qnull_inf::{qinf, qninf, qnull};
 use crate::types::Result;
 use chrono::prelude::*;
@@ -41,7 +41,13 @@ pub fn q_month_to_date(months: i32) -> NaiveDate {
         //  with 1461 as 4 years, 36525 as 100 years and 146097 as 400 years
         *qinf::MONTH
     } else {
-        NaiveDate::from_ymd_opt(2000 + months / 12, 1 + (months % 12) as u32, 1).unwrap()
+        // Euclidean division so that months before 2000 fall in the right year
+        NaiveDate::from_ymd_opt(
+            2000 + months.div_euclid(12),
+            1 + months.rem_euclid(12) as u32,
+            1,
+        )
+        .unwrap()
     }
 }
 
@@ -88,8 +94,34 @@ pub fn q_datetime_to_datetime(days: f64) -> DateTime<Utc> {
         // DateTime::signed_duration_since(chrono::MAX_DATETIME, Utc.ymd(2000,1,1).and_hms_nano(0, 0, 0, 0)).num_days())
         *qinf::DATETIME
     } else {
-        Utc.timestamp_millis_opt((ONE_DAY_MILLIS as f64 * (days + KDB_DAY_OFFSET as f64)) as i64)
-            .unwrap()
+        // Round rather than truncate so that a value just below a whole millisecond is not shifted back
+        Utc.timestamp_millis_opt(
+            (ONE_DAY_MILLIS as f64 * (days + KDB_DAY_OFFSET as f64)).round() as i64,
+        )
+        .unwrap()
+    }
+}
+
+/// Convert days of q datetime into nanoseconds of q timestamp keeping null and infinity.
+pub(crate) fn q_datetime_to_q_timestamp(days: f64) -> i64 {
+    if days.is_nan() {
+        qnull_base::J
+    } else if days == qinf_base::F {
+        qinf_base::J
+    } else if days == qninf_base::F {
+        qninf_base::J
+    } else {
+        (days * ONE_DAY_NANOS as f64).round() as i64
+    }
+}
+
+/// Convert nanoseconds of q timestamp into days of q datetime keeping null and infinity.
+pub(crate) fn q_timestamp_to_q_datetime(nanos: i64) -> f64 {
+    match nanos {
+        qnull_base::J => qnull_base::F,
+        qinf_base::J => qinf_base::F,
+        qninf_base::J => qninf_base::F,
+        _ => nanos as f64 / ONE_DAY_NANOS as f64,
     }
 }
 
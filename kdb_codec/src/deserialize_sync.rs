@@ -8,8 +8,34 @@
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 use super::*;
+use std::cell::Cell;
 use std::convert::TryInto;
 
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variable
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Upper bound of the capacity reserved for a list of symbols, GUIDs or objects before its
+///  elements are decoded. Larger lists grow as their elements arrive so that a header claiming a
+///  huge size cannot trigger a huge allocation by itself.
+const MAX_INITIAL_CAPACITY: usize = 1 << 16;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Limits and running state shared by all nested calls decoding one object.
+struct DecodeContext<'a> {
+    /// Limits configured by the user.
+    limits: &'a DeserializeLimits,
+    /// Number of list elements decoded so far, checked against `max_total_elements`.
+    elements: Cell<usize>,
+    /// Whether an element of a compound list failing to decode is replaced by an error object.
+    element_error_recovery: bool,
+    /// Decoder of types unknown to this crate.
+    unknown_type_handler: Option<&'a UnknownTypeHandler>,
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Macros
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -349,14 +375,34 @@ macro_rules! build_list {
 impl K {
     /// Synchronously decode q object from bytes in a manner of q function `-8!`.
     /// Returns Result to handle errors gracefully instead of panicking.
-    /// Uses default security limits for list size and recursion depth.
+    /// Uses default security limits for list size, recursion depth and symbol length.
     pub fn q_ipc_decode(bytes: &[u8], encode: u8) -> Result<K> {
-        q_ipc_decode_sync(
-            bytes,
-            encode,
-            crate::MAX_LIST_SIZE,
-            crate::MAX_RECURSION_DEPTH,
-        )
+        q_ipc_decode_sync(bytes, encode, &DeserializeLimits::default(), false, None)
+    }
+
+    /// Decode q object from bytes in a manner of q function `-8!` under the given limits. Use this
+    ///  to decode bytes from an untrusted source, e.g. to refuse functions.
+    /// # Parameters
+    /// - `bytes`: Serialized q object without the message header.
+    /// - `encode`: Endianness of `bytes`. `0` for big endian and `1` for little endian.
+    /// - `limits`: Limits applied during deserialization.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// let bytes = k!(long: vec![1, 2, 3]).q_ipc_encode();
+    /// let limits = DeserializeLimits {
+    ///     max_total_elements: Some(2),
+    ///     ..Default::default()
+    /// };
+    /// assert!(K::q_ipc_decode_with_limits(&bytes, 1, &limits).is_err());
+    /// ```
+    pub fn q_ipc_decode_with_limits(
+        bytes: &[u8],
+        encode: u8,
+        limits: &DeserializeLimits,
+    ) -> Result<K> {
+        q_ipc_decode_sync(bytes, encode, limits, false, None)
     }
 
     /// Decode a complete IPC message including the 8-byte header.
@@ -425,10 +471,28 @@ impl K {
 pub(crate) fn q_ipc_decode_sync(
     bytes: &[u8],
     encode: u8,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    limits: &DeserializeLimits,
+    element_error_recovery: bool,
+    unknown_type_handler: Option<&UnknownTypeHandler>,
 ) -> Result<K> {
-    deserialize_bytes_sync(bytes, 0, encode, 0, max_list_size, max_recursion_depth).map(|(k, _)| k)
+    let context = DecodeContext {
+        limits,
+        elements: Cell::new(0),
+        element_error_recovery,
+        unknown_type_handler,
+    };
+    deserialize_bytes_sync(bytes, 0, encode, 0, &context).map(|(k, _)| k)
+}
+
+/// Decode the first q object in bytes and return it with the number of bytes it occupies.
+pub(crate) fn q_ipc_decode_prefix(bytes: &[u8], encode: u8) -> Result<(K, usize)> {
+    let context = DecodeContext {
+        limits: &DeserializeLimits::default(),
+        elements: Cell::new(0),
+        element_error_recovery: false,
+        unknown_type_handler: None,
+    };
+    deserialize_bytes_sync(bytes, 0, encode, 0, &context)
 }
 
 fn deserialize_bytes_sync(
@@ -436,14 +500,13 @@ fn deserialize_bytes_sync(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
     // Check recursion depth
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
@@ -456,6 +519,7 @@ fn deserialize_bytes_sync(
     }
 
     let qtype = bytes[cursor] as i8;
+    check_type_limits(bytes, cursor, encode, context)?;
 
     match qtype {
         qtype::BOOL_ATOM => deserialize_bool(bytes, cursor + 1, encode),
@@ -467,7 +531,9 @@ fn deserialize_bytes_sync(
         qtype::REAL_ATOM => build_element!(bytes, cursor + 1, encode, qtype::REAL_ATOM, f32),
         qtype::FLOAT_ATOM => build_element!(bytes, cursor + 1, encode, qtype::FLOAT_ATOM, f64),
         qtype::CHAR => deserialize_char(bytes, cursor + 1, encode),
-        qtype::SYMBOL_ATOM => deserialize_symbol(bytes, cursor + 1, encode),
+        qtype::SYMBOL_ATOM => {
+            deserialize_symbol(bytes, cursor + 1, encode, context.limits.max_symbol_len)
+        }
         qtype::TIMESTAMP_ATOM => {
             build_element!(bytes, cursor + 1, encode, qtype::TIMESTAMP_ATOM, i64)
         }
@@ -482,24 +548,25 @@ fn deserialize_bytes_sync(
         qtype::MINUTE_ATOM => build_element!(bytes, cursor + 1, encode, qtype::MINUTE_ATOM, i32),
         qtype::SECOND_ATOM => build_element!(bytes, cursor + 1, encode, qtype::SECOND_ATOM, i32),
         qtype::TIME_ATOM => build_element!(bytes, cursor + 1, encode, qtype::TIME_ATOM, i32),
-        qtype::COMPOUND_LIST => deserialize_compound_list_sync(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
-        qtype::BOOL_LIST => deserialize_bool_list(bytes, cursor + 1, encode, max_list_size),
-        qtype::GUID_LIST => deserialize_guid_list_sync(bytes, cursor + 1, encode, max_list_size),
-        qtype::BYTE_LIST => deserialize_byte_list(bytes, cursor + 1, encode, max_list_size),
+        qtype::COMPOUND_LIST => {
+            deserialize_compound_list_sync(bytes, cursor + 1, encode, depth, context)
+        }
+        qtype::BOOL_LIST => {
+            deserialize_bool_list(bytes, cursor + 1, encode, context.limits.max_list_size)
+        }
+        qtype::GUID_LIST => {
+            deserialize_guid_list_sync(bytes, cursor + 1, encode, context.limits.max_list_size)
+        }
+        qtype::BYTE_LIST => {
+            deserialize_byte_list(bytes, cursor + 1, encode, context.limits.max_list_size)
+        }
         qtype::SHORT_LIST => build_list!(
             bytes,
             cursor + 1,
             encode,
             qtype::SHORT_LIST,
             i16,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::INT_LIST => build_list!(
             bytes,
@@ -507,7 +574,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::INT_LIST,
             i32,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::LONG_LIST => build_list!(
             bytes,
@@ -515,7 +582,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::LONG_LIST,
             i64,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::REAL_LIST => build_list!(
             bytes,
@@ -523,7 +590,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::REAL_LIST,
             f32,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::FLOAT_LIST => build_list!(
             bytes,
@@ -531,19 +598,25 @@ fn deserialize_bytes_sync(
             encode,
             qtype::FLOAT_LIST,
             f64,
-            max_list_size
+            context.limits.max_list_size
         ),
-        qtype::STRING => deserialize_string(bytes, cursor + 1, encode, max_list_size),
-        qtype::SYMBOL_LIST => {
-            deserialize_symbol_list_sync(bytes, cursor + 1, encode, max_list_size)
+        qtype::STRING => {
+            deserialize_string(bytes, cursor + 1, encode, context.limits.max_list_size)
         }
+        qtype::SYMBOL_LIST => deserialize_symbol_list_sync(
+            bytes,
+            cursor + 1,
+            encode,
+            context.limits.max_list_size,
+            context.limits.max_symbol_len,
+        ),
         qtype::TIMESTAMP_LIST => build_list!(
             bytes,
             cursor + 1,
             encode,
             qtype::TIMESTAMP_LIST,
             i64,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::MONTH_LIST => build_list!(
             bytes,
@@ -551,7 +624,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::MONTH_LIST,
             i32,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::DATE_LIST => build_list!(
             bytes,
@@ -559,7 +632,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::DATE_LIST,
             i32,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::DATETIME_LIST => build_list!(
             bytes,
@@ -567,7 +640,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::DATETIME_LIST,
             f64,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::TIMESPAN_LIST => build_list!(
             bytes,
@@ -575,7 +648,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::TIMESPAN_LIST,
             i64,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::MINUTE_LIST => build_list!(
             bytes,
@@ -583,7 +656,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::MINUTE_LIST,
             i32,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::SECOND_LIST => build_list!(
             bytes,
@@ -591,7 +664,7 @@ fn deserialize_bytes_sync(
             encode,
             qtype::SECOND_LIST,
             i32,
-            max_list_size
+            context.limits.max_list_size
         ),
         qtype::TIME_LIST => build_list!(
             bytes,
@@ -599,32 +672,20 @@ fn deserialize_bytes_sync(
             encode,
             qtype::TIME_LIST,
             i32,
-            max_list_size
-        ),
-        qtype::TABLE => deserialize_table_sync(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
-        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => deserialize_dictionary_sync(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
-        qtype::LAMBDA => deserialize_lambda_sync(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
+            context.limits.max_list_size
         ),
+        qtype::TABLE => deserialize_table_sync(bytes, cursor + 1, encode, depth, context),
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+            deserialize_dictionary_sync(bytes, cursor + 1, encode, depth, context).map(
+                |(mut dictionary, cursor)| {
+                    // Keep the type on the wire. A sorted keyed table is a sorted dictionary even
+                    // though the attribute is set on the key columns rather than on the key table.
+                    dictionary.0.qtype = qtype;
+                    (dictionary, cursor)
+                },
+            )
+        }
+        qtype::LAMBDA => deserialize_lambda_sync(bytes, cursor + 1, encode, depth, context),
         qtype::UNARY_PRIMITIVE => deserialize_unary_primitive_or_null(bytes, cursor + 1, encode),
         qtype::BINARY_PRIMITIVE => deserialize_fixed_payload_opaque(
             bytes,
@@ -633,56 +694,29 @@ fn deserialize_bytes_sync(
             qtype::BINARY_PRIMITIVE,
             1,
         ),
-        qtype::PROJECTION => deserialize_projection_opaque(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
+        qtype::PROJECTION => {
+            deserialize_projection_opaque(bytes, cursor + 1, encode, depth, context)
+        }
         qtype::COMPOSITION => deserialize_counted_or_fixed_arity_opaque(
             bytes,
             cursor + 1,
             encode,
             depth,
-            max_list_size,
-            max_recursion_depth,
+            context,
             qtype::COMPOSITION,
             2,
         ),
-        qtype::EACH => deserialize_single_inner_opaque(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-            qtype::EACH,
-        ),
-        qtype::OVER => deserialize_over_opaque(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
-        qtype::SCAN => deserialize_scan_opaque(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
+        qtype::EACH => {
+            deserialize_single_inner_opaque(bytes, cursor + 1, encode, depth, context, qtype::EACH)
+        }
+        qtype::OVER => deserialize_over_opaque(bytes, cursor + 1, encode, depth, context),
+        qtype::SCAN => deserialize_scan_opaque(bytes, cursor + 1, encode, depth, context),
         qtype::EACH_PRIOR => deserialize_single_inner_opaque(
             bytes,
             cursor + 1,
             encode,
             depth,
-            max_list_size,
-            max_recursion_depth,
+            context,
             qtype::EACH_PRIOR,
         ),
         qtype::EACH_LEFT => deserialize_single_inner_opaque(
@@ -690,31 +724,69 @@ fn deserialize_bytes_sync(
             cursor + 1,
             encode,
             depth,
-            max_list_size,
-            max_recursion_depth,
+            context,
             qtype::EACH_LEFT,
         ),
-        qtype::EACH_RIGHT => deserialize_each_right_opaque(
-            bytes,
-            cursor + 1,
-            encode,
-            depth,
-            max_list_size,
-            max_recursion_depth,
-        ),
+        qtype::EACH_RIGHT => {
+            deserialize_each_right_opaque(bytes, cursor + 1, encode, depth, context)
+        }
         qtype::FOREIGN => deserialize_counted_or_fixed_arity_opaque(
             bytes,
             cursor + 1,
             encode,
             depth,
-            max_list_size,
-            max_recursion_depth,
+            context,
             qtype::FOREIGN,
             3,
         ),
-        qtype::ERROR => deserialize_error(bytes, cursor + 1, encode),
-        _ => Err(Error::InvalidType(qtype)),
+        qtype::ERROR => {
+            deserialize_error(bytes, cursor + 1, encode, context.limits.max_symbol_len)
+        }
+        _ => match context.unknown_type_handler {
+            Some(handler) => handler(qtype, bytes, cursor + 1),
+            None => Err(Error::InvalidType(qtype)),
+        },
+    }
+}
+
+/// Reject a type disallowed by the limits and count the elements of a list against
+///  `max_total_elements`.
+fn check_type_limits(
+    bytes: &[u8],
+    cursor: usize,
+    encode: u8,
+    context: &DecodeContext,
+) -> Result<()> {
+    let qtype = bytes[cursor] as i8;
+    let limits = context.limits;
+    match qtype {
+        qtype::TABLE if !limits.allow_tables => return Err(Error::DisallowedType(qtype)),
+        // (::) shares the type with unary primitives
+        qtype::UNARY_PRIMITIVE if bytes.get(cursor + 1) == Some(&0x00) => {}
+        qtype::LAMBDA..=qtype::FOREIGN if !limits.allow_functions => {
+            return Err(Error::DisallowedType(qtype))
+        }
+        _ => {}
     }
+
+    if let Some(max) = limits.max_total_elements {
+        // Attribute is followed by the length; a truncated header is reported by the list decoder
+        if (qtype::COMPOUND_LIST..=qtype::TIME_LIST).contains(&qtype) && cursor + 6 <= bytes.len() {
+            let size_bytes: [u8; 4] = bytes[cursor + 2..cursor + 6]
+                .try_into()
+                .map_err(|_| Error::DeserializationError("invalid list size bytes".to_string()))?;
+            let size = match encode {
+                0 => u32::from_be_bytes(size_bytes),
+                _ => u32::from_le_bytes(size_bytes),
+            } as usize;
+            let total = context.elements.get().saturating_add(size);
+            if total > max {
+                return Err(Error::TooManyElements { total, max });
+            }
+            context.elements.set(total);
+        }
+    }
+    Ok(())
 }
 
 fn deserialize_fixed_payload_opaque(
@@ -766,8 +838,7 @@ fn deserialize_projection_opaque(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
     // Projection format (as observed from q -8!):
     //   byte 104, then i32 count N, then N serialized q objects.
@@ -778,10 +849,10 @@ fn deserialize_projection_opaque(
         });
     }
 
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
@@ -802,14 +873,7 @@ fn deserialize_projection_opaque(
     let start_payload = cursor;
     let mut next = cursor + 4;
     for _ in 0..n {
-        let (_k, new_cursor) = deserialize_bytes_sync(
-            bytes,
-            next,
-            encode,
-            depth + 1,
-            max_list_size,
-            max_recursion_depth,
-        )?;
+        let (_k, new_cursor) = deserialize_bytes_sync(bytes, next, encode, depth + 1, context)?;
         next = new_cursor;
     }
 
@@ -825,8 +889,7 @@ fn deserialize_counted_or_fixed_arity_opaque(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
     outer_qtype: i8,
     fallback_arity: usize,
 ) -> Result<(K, usize)> {
@@ -838,10 +901,10 @@ fn deserialize_counted_or_fixed_arity_opaque(
     // Other objects appear to encode as a fixed number of serialized q objects without the count.
     // We attempt the counted form first (if the count looks plausible), otherwise fall back to
     // reading `fallback_arity` serialized q objects.
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
@@ -857,19 +920,12 @@ fn deserialize_counted_or_fixed_arity_opaque(
 
         if n >= 0 {
             let n_usize = n as usize;
-            if n_usize <= max_list_size {
+            if n_usize <= context.limits.max_list_size {
                 let start_payload = cursor;
                 let mut next = cursor + 4;
                 let mut ok = true;
                 for _ in 0..n_usize {
-                    match deserialize_bytes_sync(
-                        bytes,
-                        next,
-                        encode,
-                        depth + 1,
-                        max_list_size,
-                        max_recursion_depth,
-                    ) {
+                    match deserialize_bytes_sync(bytes, next, encode, depth + 1, context) {
                         Ok((_k, new_cursor)) => next = new_cursor,
                         Err(_) => {
                             ok = false;
@@ -893,14 +949,7 @@ fn deserialize_counted_or_fixed_arity_opaque(
     let start_payload = cursor;
     let mut next = cursor;
     for _ in 0..fallback_arity {
-        let (_k, new_cursor) = deserialize_bytes_sync(
-            bytes,
-            next,
-            encode,
-            depth + 1,
-            max_list_size,
-            max_recursion_depth,
-        )?;
+        let (_k, new_cursor) = deserialize_bytes_sync(bytes, next, encode, depth + 1, context)?;
         next = new_cursor;
     }
     let payload = bytes[start_payload..next].to_vec();
@@ -915,27 +964,19 @@ fn deserialize_over_opaque(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
     // Observed from q `-8!`:
     //   107 (0x6b) followed by exactly one serialized q object (typically a function).
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
     let start_payload = cursor;
-    let (_inner, next) = deserialize_bytes_sync(
-        bytes,
-        cursor,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (_inner, next) = deserialize_bytes_sync(bytes, cursor, encode, depth + 1, context)?;
 
     let payload = bytes[start_payload..next].to_vec();
     Ok((K::new(qtype::OVER, qattribute::NONE, k0_inner::opaque(payload)), next))
@@ -946,8 +987,7 @@ fn deserialize_scan_opaque(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
     // Observed from q `-8!`:
     //   108 (0x6c) then a 1-byte adverb indicator (often also 0x6c), then one serialized q object.
@@ -957,23 +997,16 @@ fn deserialize_scan_opaque(
             available: 0,
         });
     }
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
     let start_payload = cursor;
     let after_adverb = cursor + 1;
-    let (_inner, next) = deserialize_bytes_sync(
-        bytes,
-        after_adverb,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (_inner, next) = deserialize_bytes_sync(bytes, after_adverb, encode, depth + 1, context)?;
 
     let payload = bytes[start_payload..next].to_vec();
     Ok((K::new(qtype::SCAN, qattribute::NONE, k0_inner::opaque(payload)), next))
@@ -984,28 +1017,20 @@ fn deserialize_single_inner_opaque(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
     outer_qtype: i8,
 ) -> Result<(K, usize)> {
     // Derived-function format (as observed from q `-8!` for EACH/EACH_PRIOR/EACH_LEFT/OVER):
     //   outer type byte, then exactly one serialized q object (typically a function).
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
     let start_payload = cursor;
-    let (_inner, next) = deserialize_bytes_sync(
-        bytes,
-        cursor,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (_inner, next) = deserialize_bytes_sync(bytes, cursor, encode, depth + 1, context)?;
 
     let payload = bytes[start_payload..next].to_vec();
     Ok((
@@ -1019,8 +1044,7 @@ fn deserialize_each_right_opaque(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
     // Observed from q `-8!` for `+\\:`:
     //   111 (0x6f) then a 1-byte marker (observed 0x6c), then one serialized q object.
@@ -1030,23 +1054,16 @@ fn deserialize_each_right_opaque(
             available: 0,
         });
     }
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
     let start_payload = cursor;
     let after_marker = cursor + 1;
-    let (_inner, next) = deserialize_bytes_sync(
-        bytes,
-        after_marker,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (_inner, next) = deserialize_bytes_sync(bytes, after_marker, encode, depth + 1, context)?;
 
     let payload = bytes[start_payload..next].to_vec();
     Ok((
@@ -1060,8 +1077,7 @@ fn deserialize_lambda_sync(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
     // Context: null-terminated string
     if cursor >= bytes.len() {
@@ -1073,6 +1089,11 @@ fn deserialize_lambda_sync(
 
     let mut idx = cursor;
     while idx < bytes.len() && bytes[idx] != 0x00 {
+        if idx - cursor >= context.limits.max_symbol_len {
+            return Err(Error::SymbolTooLong {
+                max: context.limits.max_symbol_len,
+            });
+        }
         idx += 1;
     }
     if idx >= bytes.len() {
@@ -1082,18 +1103,11 @@ fn deserialize_lambda_sync(
         });
     }
 
-    let context = String::from_utf8_lossy(&bytes[cursor..idx]).to_string();
+    let lambda_context = String::from_utf8_lossy(&bytes[cursor..idx]).to_string();
     idx += 1; // skip null terminator
 
     // Body: a char vector (type 10)
-    let (body_k, next_cursor) = deserialize_bytes_sync(
-        bytes,
-        idx,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (body_k, next_cursor) = deserialize_bytes_sync(bytes, idx, encode, depth + 1, context)?;
 
     if body_k.get_type() != qtype::STRING {
         return Err(Error::DeserializationError(
@@ -1102,7 +1116,7 @@ fn deserialize_lambda_sync(
     }
     let body = body_k.as_string()?.to_string();
 
-    Ok((K::new_lambda(context, body), next_cursor))
+    Ok((K::new_lambda(lambda_context, body), next_cursor))
 }
 
 fn deserialize_bool(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)> {
@@ -1151,7 +1165,12 @@ fn deserialize_char(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)> {
     ))
 }
 
-fn deserialize_symbol(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)> {
+fn deserialize_symbol(
+    bytes: &[u8],
+    cursor: usize,
+    _: u8,
+    max_symbol_len: usize,
+) -> Result<(K, usize)> {
     if cursor >= bytes.len() {
         return Err(Error::InsufficientData {
             needed: 1,
@@ -1159,12 +1178,7 @@ fn deserialize_symbol(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)>
         });
     }
 
-    let null_location = bytes
-        .split_at(cursor)
-        .1
-        .iter()
-        .position(|b| *b == 0x00)
-        .ok_or(Error::MissingNullTerminator)?;
+    let null_location = find_null_terminator(bytes, cursor, max_symbol_len)?;
 
     let symbol_str = String::from_utf8(bytes[cursor..cursor + null_location].to_vec())
         .map_err(|_| Error::InvalidUtf8)?;
@@ -1172,6 +1186,20 @@ fn deserialize_symbol(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)>
     Ok((k, cursor + null_location + 1))
 }
 
+/// Locate the null terminator of a symbol starting at `cursor` and return its offset from `cursor`.
+///  At most `max_symbol_len` bytes are scanned before the terminator.
+fn find_null_terminator(bytes: &[u8], cursor: usize, max_symbol_len: usize) -> Result<usize> {
+    let end = bytes.len().min(cursor.saturating_add(max_symbol_len).saturating_add(1));
+    let window = &bytes[cursor..end];
+    match window.iter().position(|b| *b == 0x00) {
+        Some(null_location) => Ok(null_location),
+        None if window.len() > max_symbol_len => Err(Error::SymbolTooLong {
+            max: max_symbol_len,
+        }),
+        None => Err(Error::MissingNullTerminator),
+    }
+}
+
 /// Extract attribute and list length and then proceed the cursor.
 fn get_attribute_and_size(
     bytes: &[u8],
@@ -1247,7 +1275,7 @@ fn deserialize_guid_list_sync(
         });
     }
     let slice = &bytes[cursor..cursor + byte_count];
-    let mut list: Vec<U> = Vec::with_capacity(size);
+    let mut list: Vec<U> = Vec::with_capacity(size.min(MAX_INITIAL_CAPACITY));
     let mut iter = slice.chunks_exact(16);
     for guid_bytes in &mut iter {
         let guid: [u8; 16] = guid_bytes
@@ -1313,6 +1341,7 @@ fn deserialize_symbol_list_sync(
     cursor: usize,
     encode: u8,
     max_list_size: usize,
+    max_symbol_len: usize,
 ) -> Result<(K, usize)> {
     let (attribute, size, mut cursor) =
         get_attribute_and_size(bytes, cursor, encode, max_list_size)?;
@@ -1325,7 +1354,7 @@ fn deserialize_symbol_list_sync(
             available: remaining,
         });
     }
-    let mut list = Vec::with_capacity(size);
+    let mut list = Vec::with_capacity(size.min(MAX_INITIAL_CAPACITY));
     for _ in 0..size {
         if cursor >= bytes.len() {
             return Err(Error::InsufficientData {
@@ -1333,12 +1362,7 @@ fn deserialize_symbol_list_sync(
                 available: 0,
             });
         }
-        let null_location = bytes
-            .split_at(cursor)
-            .1
-            .iter()
-            .position(|b| *b == 0x00)
-            .ok_or(Error::MissingNullTerminator)?;
+        let null_location = find_null_terminator(bytes, cursor, max_symbol_len)?;
         let symbol = String::from_utf8(bytes[cursor..cursor + null_location].to_vec())
             .map_err(|_| Error::InvalidUtf8)?;
         list.push(symbol);
@@ -1352,18 +1376,17 @@ fn deserialize_compound_list_sync(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
     let (attribute, size, mut cursor) =
-        get_attribute_and_size(bytes, cursor, encode, max_list_size)?;
+        get_attribute_and_size(bytes, cursor, encode, context.limits.max_list_size)?;
     // Each nested element requires at least 1 byte (its qtype). If the input can't possibly
     // contain `size` elements, fail early before attempting large allocations.
     let remaining = bytes.len().saturating_sub(cursor);
@@ -1373,36 +1396,88 @@ fn deserialize_compound_list_sync(
             available: remaining,
         });
     }
-    let mut list = Vec::with_capacity(size);
-    for _ in 0..size {
-        let (k, new_cursor) = deserialize_bytes_sync(
-            bytes,
-            cursor,
-            encode,
-            depth + 1,
-            max_list_size,
-            max_recursion_depth,
-        )?;
-        list.push(k);
-        cursor = new_cursor;
+    let mut list = Vec::with_capacity(size.min(MAX_INITIAL_CAPACITY));
+    while list.len() < size {
+        match deserialize_bytes_sync(bytes, cursor, encode, depth + 1, context) {
+            Ok((k, new_cursor)) => {
+                list.push(k);
+                cursor = new_cursor;
+            }
+            Err(error) if context.element_error_recovery && is_recoverable(&error) => {
+                match failed_element_end(bytes, cursor, encode, context) {
+                    Some(new_cursor) => {
+                        list.push(K::new_error(error.to_string()));
+                        cursor = new_cursor;
+                    }
+                    None => return Err(error),
+                }
+            }
+            Err(error) => return Err(error),
+        }
     }
     let mut k = K::new_compound_list(list);
     k.0.attribute = attribute;
     Ok((k, cursor))
 }
 
+/// Check if an element failing with `error` may be skipped. Errors raised by the limits are
+///  never recovered from.
+fn is_recoverable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::InvalidType(_)
+            | Error::InsufficientData { .. }
+            | Error::MissingNullTerminator
+            | Error::InvalidUtf8
+            | Error::DeserializationError(_)
+    )
+}
+
+/// Find the end of an element of a compound list which failed to decode at `cursor`. The end is
+///  only known for a typed list of fixed-width elements, e.g. a string holding invalid UTF-8,
+///  whose extent follows from its type and length header. Any other element, including one with
+///  an unknown type byte, cannot be skipped without guessing.
+fn failed_element_end(
+    bytes: &[u8],
+    cursor: usize,
+    encode: u8,
+    context: &DecodeContext,
+) -> Option<usize> {
+    let width = match *bytes.get(cursor)? as i8 {
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => 1,
+        qtype::SHORT_LIST => 2,
+        qtype::INT_LIST
+        | qtype::REAL_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => 4,
+        qtype::LONG_LIST
+        | qtype::FLOAT_LIST
+        | qtype::TIMESTAMP_LIST
+        | qtype::DATETIME_LIST
+        | qtype::TIMESPAN_LIST => 8,
+        qtype::GUID_LIST => 16,
+        _ => return None,
+    };
+    let (_, size, start) =
+        get_attribute_and_size(bytes, cursor + 1, encode, context.limits.max_list_size).ok()?;
+    let end = size.checked_mul(width)?.checked_add(start)?;
+    (end <= bytes.len()).then_some(end)
+}
+
 fn deserialize_table_sync(
     bytes: &[u8],
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
@@ -1422,14 +1497,8 @@ fn deserialize_table_sync(
     let cursor = cursor + 2;
 
     // Deserialize the dictionary (keys and values)
-    let (dictionary, cursor) = deserialize_dictionary_sync(
-        bytes,
-        cursor,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (dictionary, cursor) =
+        deserialize_dictionary_sync(bytes, cursor, encode, depth + 1, context)?;
     Ok((
         K::new(qtype::TABLE, attribute, k0_inner::table(dictionary)),
         cursor,
@@ -1441,34 +1510,19 @@ fn deserialize_dictionary_sync(
     cursor: usize,
     encode: u8,
     depth: usize,
-    max_list_size: usize,
-    max_recursion_depth: usize,
+    context: &DecodeContext,
 ) -> Result<(K, usize)> {
-    if depth > max_recursion_depth {
+    if depth > context.limits.max_recursion_depth {
         return Err(Error::MaxDepthExceeded {
             depth,
-            max: max_recursion_depth,
+            max: context.limits.max_recursion_depth,
         });
     }
 
     // Deserialize keys
-    let (keys, cursor) = deserialize_bytes_sync(
-        bytes,
-        cursor,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (keys, cursor) = deserialize_bytes_sync(bytes, cursor, encode, depth + 1, context)?;
     // Deserialize values
-    let (values, cursor) = deserialize_bytes_sync(
-        bytes,
-        cursor,
-        encode,
-        depth + 1,
-        max_list_size,
-        max_recursion_depth,
-    )?;
+    let (values, cursor) = deserialize_bytes_sync(bytes, cursor, encode, depth + 1, context)?;
     // Build dictionary - new_dictionary handles sorted and keyed tables internally
     let dictionary = K::new_dictionary(keys, values)
         .map_err(|e| Error::DeserializationError(format!("Failed to build dictionary: {}", e)))?;
@@ -1481,7 +1535,12 @@ fn deserialize_null(bytes: &[u8], cursor: usize, encode: u8) -> Result<(K, usize
     deserialize_unary_primitive_or_null(bytes, cursor, encode)
 }
 
-fn deserialize_error(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)> {
+fn deserialize_error(
+    bytes: &[u8],
+    cursor: usize,
+    _: u8,
+    max_symbol_len: usize,
+) -> Result<(K, usize)> {
     if cursor >= bytes.len() {
         return Err(Error::InsufficientData {
             needed: 1,
@@ -1489,12 +1548,7 @@ fn deserialize_error(bytes: &[u8], cursor: usize, _: u8) -> Result<(K, usize)> {
         });
     }
 
-    let null_location = bytes
-        .split_at(cursor)
-        .1
-        .iter()
-        .position(|b| *b == 0x00)
-        .ok_or(Error::MissingNullTerminator)?;
+    let null_location = find_null_terminator(bytes, cursor, max_symbol_len)?;
 
     let error_msg = String::from_utf8(bytes[cursor..cursor + null_location].to_vec())
         .map_err(|_| Error::InvalidUtf8)?;
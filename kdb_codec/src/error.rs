@@ -9,6 +9,7 @@ use crate::qtype;
 use std::error::Error as StdError;
 use std::fmt;
 use std::io::Error as IOError;
+use std::time::Duration;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >>  Structs
@@ -20,8 +21,9 @@ pub enum Error {
     InvalidDateTime,
     /// Network error.
     IO(IOError),
-    /// Network error with custom message.
-    NetworkError(String),
+    /// Network error raised outside of `QStream`, e.g. by a `Framed` stream using `KdbCodec`. The
+    ///  original IO error is available via `source()`.
+    NetworkError(IOError),
     /// Invalid message size.
     InvalidMessageSize,
     /// Tried to cast to wrong type.
@@ -46,6 +48,12 @@ pub enum Error {
     },
     /// Tried to get non-existing column.
     NoSuchColumn(String),
+    /// Tried to create a column whose name already exists.
+    DuplicateColumn(String),
+    /// Operand of an operation which requires ascending order is not sorted.
+    NotAscending(&'static str),
+    /// Object is not structurally consistent, e.g. a table with columns of different lengths.
+    InvalidStructure(String),
     /// Tried to insert or push wrong element.
     InsertWrongElement {
         is_insert: bool,
@@ -70,8 +78,28 @@ pub enum Error {
     MaxDepthExceeded { depth: usize, max: usize },
     /// List size exceeds maximum allowed size.
     ListTooLarge { size: usize, max: usize },
+    /// Symbol has no null terminator within the maximum allowed length.
+    SymbolTooLong { max: usize },
     /// Integer overflow in size calculation.
     SizeOverflow,
+    /// Type refused by the deserialization limits, e.g. a lambda when functions are not allowed.
+    DisallowedType(i8),
+    /// Total number of list elements of an object exceeds the maximum allowed number.
+    TooManyElements { total: usize, max: usize },
+    /// Journal ends in the middle of a message starting at the given byte offset.
+    TruncatedJournal { at_offset: usize },
+    /// Text is not a valid q literal.
+    ParseError(String),
+    /// Peer closed the connection cleanly between messages.
+    ConnectionClosed,
+    /// Bytes received from the peer do not form a valid message.
+    Protocol(String),
+    /// The maximum number of concurrent connections of a `ConnectionLimit` was reached.
+    TooManyConnections(usize),
+    /// No response arrived within the given time.
+    Timeout(Duration),
+    /// Hostname could not be resolved to IP addresses.
+    DnsResolution(String),
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -123,6 +151,21 @@ impl Error {
         Self::NoSuchColumn(column)
     }
 
+    /// Construct `DuplicateColumn` error.
+    pub(crate) fn duplicate_column(column: String) -> Self {
+        Self::DuplicateColumn(column)
+    }
+
+    /// Construct `NotAscending` error.
+    pub(crate) fn not_ascending(operator: &'static str) -> Self {
+        Self::NotAscending(operator)
+    }
+
+    /// Construct `InvalidStructure` error.
+    pub(crate) fn invalid_structure(description: String) -> Self {
+        Self::InvalidStructure(description)
+    }
+
     /// Construct `InsertWrongElement` error.
     pub(crate) fn insert_wrong_element(
         is_insert: bool,
@@ -181,7 +224,9 @@ impl PartialEq<Self> for Error {
         match (self, other) {
             (Self::IO(left), Self::IO(right)) => left.to_string() == right.to_string(),
             (Self::IO(_), _) => false,
-            (Self::NetworkError(left), Self::NetworkError(right)) => left == right,
+            (Self::NetworkError(left), Self::NetworkError(right)) => {
+                left.kind() == right.kind() && left.to_string() == right.to_string()
+            }
             (Self::InvalidMessageSize, Self::InvalidMessageSize) => true,
             (Self::InvalidCast { from: f, to: t }, Self::InvalidCast { from: f2, to: t2 }) => {
                 f == f2 && t == t2
@@ -220,6 +265,9 @@ impl PartialEq<Self> for Error {
                 },
             ) => k == k2 && l == l2,
             (Self::NoSuchColumn(left), Self::NoSuchColumn(right)) => left == right,
+            (Self::DuplicateColumn(left), Self::DuplicateColumn(right)) => left == right,
+            (Self::NotAscending(left), Self::NotAscending(right)) => left == right,
+            (Self::InvalidStructure(left), Self::InvalidStructure(right)) => left == right,
             (
                 Self::InsertWrongElement {
                     is_insert: i,
@@ -236,6 +284,15 @@ impl PartialEq<Self> for Error {
                 left.0.qtype == right.0.qtype && left.0.attribute == right.0.attribute
             }
             (Self::PopFromEmptyList, Self::PopFromEmptyList) => true,
+            (Self::TruncatedJournal { at_offset: o }, Self::TruncatedJournal { at_offset: o2 }) => {
+                o == o2
+            }
+            (Self::ParseError(left), Self::ParseError(right)) => left == right,
+            (Self::ConnectionClosed, Self::ConnectionClosed) => true,
+            (Self::Protocol(left), Self::Protocol(right)) => left == right,
+            (Self::TooManyConnections(left), Self::TooManyConnections(right)) => left == right,
+            (Self::Timeout(left), Self::Timeout(right)) => left == right,
+            (Self::DnsResolution(left), Self::DnsResolution(right)) => left == right,
             _ => false,
         }
     }
@@ -246,7 +303,7 @@ impl fmt::Display for Error {
         match self {
             Self::InvalidDateTime => write!(f, "invalid datetime"),
             Self::IO(error) => write!(f, "IO error: {}", error),
-            Self::NetworkError(msg) => write!(f, "Network error: {}", msg),
+            Self::NetworkError(error) => write!(f, "Network error: {}", error),
             Self::InvalidMessageSize => write!(f, "Invalid message size"),
             Self::InvalidCast { from, to } => write!(f, "invalid cast from {} to {}", from, to),
             Self::InvalidCastList(from) => {
@@ -278,6 +335,9 @@ impl fmt::Display for Error {
                 key_length, value_length
             ),
             Self::NoSuchColumn(column) => write!(f, "no such column: {}", column),
+            Self::DuplicateColumn(column) => write!(f, "duplicate column: {}", column),
+            Self::NotAscending(operator) => write!(f, "{}: operand is not ascending", operator),
+            Self::InvalidStructure(description) => write!(f, "invalid structure: {}", description),
             Self::InsertWrongElement {
                 is_insert,
                 destination,
@@ -314,7 +374,31 @@ impl fmt::Display for Error {
             Self::ListTooLarge { size, max } => {
                 write!(f, "list size {} exceeds maximum allowed size {}", size, max)
             }
+            Self::SymbolTooLong { max } => {
+                write!(f, "symbol exceeds maximum allowed length {}", max)
+            }
             Self::SizeOverflow => write!(f, "integer overflow in size calculation"),
+            Self::DisallowedType(qtype) => write!(f, "q type {} is not allowed", qtype),
+            Self::TooManyElements { total, max } => write!(
+                f,
+                "total number of elements {} exceeds maximum allowed number {}",
+                total, max
+            ),
+            Self::TruncatedJournal { at_offset } => {
+                write!(f, "journal truncated in message at offset {}", at_offset)
+            }
+            Self::ParseError(description) => write!(f, "parse error: {}", description),
+            Self::ConnectionClosed => write!(f, "connection closed by peer"),
+            Self::Protocol(description) => write!(f, "protocol error: {}", description),
+            Self::TooManyConnections(max_connections) => {
+                write!(
+                    f,
+                    "too many connections: limit of {} reached",
+                    max_connections
+                )
+            }
+            Self::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+            Self::DnsResolution(description) => write!(f, "DNS resolution failed: {}", description),
         }
     }
 }
@@ -324,7 +408,7 @@ impl fmt::Debug for Error {
         match self {
             Self::InvalidDateTime => write!(f, "invalid datetime"),
             Self::IO(error) => write!(f, "IO error: {:?}", error),
-            Self::NetworkError(msg) => write!(f, "Network error: {}", msg),
+            Self::NetworkError(error) => write!(f, "Network error: {}", error),
             Self::InvalidMessageSize => write!(f, "Invalid message size"),
             Self::InvalidCast { from, to } => write!(f, "invalid cast from {} to {}", from, to),
             Self::InvalidCastList(from) => {
@@ -356,6 +440,9 @@ impl fmt::Debug for Error {
                 key_length, value_length
             ),
             Self::NoSuchColumn(column) => write!(f, "no such column: {}", column),
+            Self::DuplicateColumn(column) => write!(f, "duplicate column: {}", column),
+            Self::NotAscending(operator) => write!(f, "{}: operand is not ascending", operator),
+            Self::InvalidStructure(description) => write!(f, "invalid structure: {}", description),
             Self::InsertWrongElement {
                 is_insert,
                 destination,
@@ -392,19 +479,50 @@ impl fmt::Debug for Error {
             Self::ListTooLarge { size, max } => {
                 write!(f, "list size {} exceeds maximum allowed size {}", size, max)
             }
+            Self::SymbolTooLong { max } => {
+                write!(f, "symbol exceeds maximum allowed length {}", max)
+            }
             Self::SizeOverflow => write!(f, "integer overflow in size calculation"),
+            Self::DisallowedType(qtype) => write!(f, "q type {} is not allowed", qtype),
+            Self::TooManyElements { total, max } => write!(
+                f,
+                "total number of elements {} exceeds maximum allowed number {}",
+                total, max
+            ),
+            Self::TruncatedJournal { at_offset } => {
+                write!(f, "journal truncated in message at offset {}", at_offset)
+            }
+            Self::ParseError(description) => write!(f, "parse error: {}", description),
+            Self::ConnectionClosed => write!(f, "connection closed by peer"),
+            Self::Protocol(description) => write!(f, "protocol error: {}", description),
+            Self::TooManyConnections(max_connections) => {
+                write!(
+                    f,
+                    "too many connections: limit of {} reached",
+                    max_connections
+                )
+            }
+            Self::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+            Self::DnsResolution(description) => write!(f, "DNS resolution failed: {}", description),
         }
     }
 }
 
-impl StdError for Error {}
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::IO(error) | Self::NetworkError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Private Function
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 /// Return a corresponding type name of a given type indicator.
-fn type_to_string(qtype: i8) -> &'static str {
+pub(crate) fn type_to_string(qtype: i8) -> &'static str {
     match qtype {
         qtype::BOOL_ATOM => "bool",
         qtype::GUID_ATOM => "guid",
@@ -447,6 +565,7 @@ fn type_to_string(qtype: i8) -> &'static str {
         qtype::DICTIONARY => "dictionary",
         qtype::NULL => "null",
         qtype::SORTED_DICTIONARY => "sorted dictionary",
+        qtype::BINARY_PRIMITIVE => "binary primitive",
         qtype::ERROR => "error",
         _ => "not supported",
     }
@@ -29,6 +29,35 @@ impl fmt::Display for K {
     }
 }
 
+//%% Debug Layout %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl K {
+    /// Describe the internal representation of the object, one line per nested object. Each line shows
+    ///  the type indicator, the attribute and the variant holding the value with its length. Unlike
+    ///  `Display` this reveals how a value is stored, e.g. which list a nested object belongs to.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_compound = k!([k!(long: vec![1, 2]), k!(sym: "a")]);
+    ///     assert_eq!(
+    ///         q_compound.debug_layout(),
+    ///         String::from(
+    ///             "qtype: 0 (compound list), attribute: 0, inner: list(len: 2)
+    ///   qtype: 7 (long list), attribute: 0, inner: list(len: 2)
+    ///   qtype: -11 (symbol), attribute: 0, inner: symbol(len: 1)"
+    ///         )
+    ///     );
+    /// }
+    /// ```
+    pub fn debug_layout(&self) -> String {
+        let mut stream = String::new();
+        put_layout(self, 0, &mut stream);
+        stream
+    }
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Private Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -165,16 +194,9 @@ fn put_month(months: I, stream: &mut String) {
     } else if months == qninf_base::I {
         stream.push_str("-0W")
     } else {
-        match months.signum() {
-            -1 => {
-                let year = 2000 + months.signum() * (1 + months.abs() / 12);
-                let month = 12 + (1 + months % 12);
-                stream.push_str(format!("{}.{:02}", year, month).as_str())
-            }
-            _ => {
-                stream.push_str(format!("{}.{:02}", 2000 + months / 12, 1 + (months % 12)).as_str())
-            }
-        }
+        let year = 2000 + months.div_euclid(12);
+        let month = 1 + months.rem_euclid(12);
+        stream.push_str(format!("{}.{:02}", year, month).as_str())
     }
 }
 
@@ -933,3 +955,52 @@ fn put_q(object: &K, stream: &mut String, precision: usize) {
         }
     }
 }
+
+/// Write a line describing the layout of an object and then the lines of its children indented.
+fn put_layout(object: &K, depth: usize, stream: &mut String) {
+    if depth != 0 {
+        stream.push('\n');
+    }
+    let inner = match &object.0.value {
+        k0_inner::byte(_) => String::from("byte"),
+        k0_inner::guid(_) => String::from("guid"),
+        k0_inner::short(_) => String::from("short"),
+        k0_inner::int(_) => String::from("int"),
+        k0_inner::long(_) => String::from("long"),
+        k0_inner::real(_) => String::from("real"),
+        k0_inner::float(_) => String::from("float"),
+        k0_inner::symbol(symbol) => format!("symbol(len: {})", symbol.len()),
+        k0_inner::table(_) => String::from("table"),
+        k0_inner::list(list) => format!("list(len: {})", list.len()),
+        k0_inner::null(_) => String::from("null"),
+        k0_inner::lambda { context, body } => {
+            format!("lambda(context: {}, len: {})", context, body.len())
+        }
+        k0_inner::opaque(bytes) => format!("opaque(len: {})", bytes.len()),
+    };
+    stream.push_str(&format!(
+        "{}qtype: {} ({}), attribute: {}, inner: {}",
+        "  ".repeat(depth),
+        object.0.qtype,
+        error::type_to_string(object.0.qtype),
+        object.0.attribute,
+        inner
+    ));
+    match &object.0.value {
+        k0_inner::table(dictionary) => put_layout(dictionary, depth + 1, stream),
+        k0_inner::list(_)
+            if matches!(
+                object.0.qtype,
+                qtype::COMPOUND_LIST | qtype::DICTIONARY | qtype::SORTED_DICTIONARY
+            ) =>
+        {
+            // A malformed list holding something other than objects shows no children
+            if let Ok(children) = object.as_vec::<K>() {
+                for child in children {
+                    put_layout(child, depth + 1, stream);
+                }
+            }
+        }
+        _ => {}
+    }
+}
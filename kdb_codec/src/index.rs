@@ -45,7 +45,7 @@
 //! ```
 
 use crate::error::Error;
-use crate::qconsts::qtype;
+use crate::qconsts::{qattribute, qtype};
 use crate::types::K;
 use std::ops::{Index, IndexMut};
 
@@ -444,6 +444,18 @@ impl K {
                     .ok_or_else(|| Error::index_out_of_bounds(vec.len(), index))?;
                 Ok(K::new_float(value))
             }
+            qtype::DATETIME_LIST => {
+                // Stored as float but must stay a datetime
+                let vec = list.as_vec::<F>()?;
+                let value = *vec
+                    .get(index)
+                    .ok_or_else(|| Error::index_out_of_bounds(vec.len(), index))?;
+                Ok(K::new(
+                    qtype::DATETIME_ATOM,
+                    qattribute::NONE,
+                    k0_inner::float(value),
+                ))
+            }
             qtype::REAL_LIST => {
                 let vec = list.as_vec::<E>()?;
                 let value = *vec
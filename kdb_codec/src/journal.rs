@@ -0,0 +1,173 @@
+//! # Journal Reader
+//!
+//! This module reads kdb+ log files such as a tickerplant journal replayed by `-11!`. A journal
+//! starts with an 8-byte header (`0xff 0x01`, the type and attribute of a compound list and the
+//! message count) followed by serialized q objects without message headers.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use crate::deserialize_sync::q_ipc_decode_prefix;
+use crate::error::Error;
+use crate::types::*;
+use std::path::Path;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variable
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Size of the journal header in bytes.
+const HEADER_SIZE: usize = 8;
+
+/// Magic bytes at the start of a journal.
+const MAGIC: [u8; 2] = [0xff, 0x01];
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Sequential reader of messages stored in a kdb+ journal. Messages are expected in little endian
+///  as written by q on x86 and ARM hosts.
+/// # Example
+/// ```no_run
+/// use kdb_codec::*;
+///
+/// let mut reader = JournalReader::open("/tmp/sym2024.01.01").unwrap();
+/// while let Some(message) = reader.read_next().unwrap() {
+///     println!("{}", message);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JournalReader {
+    /// Whole content of the journal.
+    bytes: Vec<u8>,
+    /// Offset of the next message.
+    offset: usize,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl JournalReader {
+    /// Create a reader over the content of a journal.
+    /// # Errors
+    /// Returns `Error::DeserializationError` if `bytes` does not start with a journal header.
+    pub fn new(bytes: Vec<u8>) -> Result<Self> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(Error::DeserializationError(
+                "missing journal header".to_string(),
+            ));
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::TruncatedJournal { at_offset: 0 });
+        }
+        Ok(Self {
+            bytes,
+            offset: HEADER_SIZE,
+        })
+    }
+
+    /// Read the journal file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(std::fs::read(path)?)
+    }
+
+    /// Decode the next message. Returns `Ok(None)` once all messages have been read.
+    /// # Errors
+    /// Returns `Error::TruncatedJournal` if the journal ends in the middle of a message, e.g. when
+    ///  the writer died while appending. The reader stays at the start of the incomplete message
+    ///  so that [`offset`](Self::offset) gives the length of the valid part.
+    pub fn read_next(&mut self) -> Result<Option<K>> {
+        if self.offset == self.bytes.len() {
+            return Ok(None);
+        }
+        match q_ipc_decode_prefix(&self.bytes[self.offset..], 1) {
+            Ok((message, size)) => {
+                self.offset += size;
+                Ok(Some(message))
+            }
+            Err(Error::InsufficientData { .. }) | Err(Error::MissingNullTerminator) => {
+                Err(Error::TruncatedJournal {
+                    at_offset: self.offset,
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Byte offset of the next message to read.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Tests
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k;
+
+    fn journal(messages: &[K]) -> Vec<u8> {
+        let mut bytes = vec![0xff, 0x01, 0x00, 0x00];
+        bytes.extend_from_slice(&(messages.len() as i32).to_le_bytes());
+        for message in messages {
+            bytes.extend(message.q_ipc_encode());
+        }
+        bytes
+    }
+
+    fn upd(price: f64) -> K {
+        k!([
+            k!(sym: "upd"),
+            k!(sym: "trade"),
+            k!([k!(sym: vec!["AAPL"]), k!(float: vec![price])])
+        ])
+    }
+
+    #[test]
+    fn test_read_journal() {
+        let bytes = journal(&[upd(1.5), upd(2.5)]);
+        let mut reader = JournalReader::new(bytes.clone()).unwrap();
+        assert_eq!(
+            format!("{}", reader.read_next().unwrap().unwrap()),
+            format!("{}", upd(1.5))
+        );
+        assert_eq!(
+            format!("{}", reader.read_next().unwrap().unwrap()),
+            format!("{}", upd(2.5))
+        );
+        assert!(reader.read_next().unwrap().is_none());
+        assert_eq!(reader.offset(), bytes.len());
+    }
+
+    #[test]
+    fn test_read_truncated_journal() {
+        let mut bytes = journal(&[upd(1.5), upd(2.5)]);
+        let second = HEADER_SIZE + upd(1.5).q_ipc_encode().len();
+        bytes.truncate(second + 10);
+        let mut reader = JournalReader::new(bytes).unwrap();
+        assert_eq!(
+            format!("{}", reader.read_next().unwrap().unwrap()),
+            format!("{}", upd(1.5))
+        );
+        assert_eq!(
+            reader.read_next().unwrap_err(),
+            Error::TruncatedJournal { at_offset: second }
+        );
+        assert_eq!(reader.offset(), second);
+    }
+
+    #[test]
+    fn test_missing_journal_header() {
+        let bytes = upd(1.5).q_ipc_encode();
+        assert!(matches!(
+            JournalReader::new(bytes),
+            Err(Error::DeserializationError(_))
+        ));
+    }
+}
@@ -20,6 +20,7 @@
 //! - `MAX_RECURSION_DEPTH`: 100 levels (default for max_recursion_depth)
 //! - `MAX_MESSAGE_SIZE`: 256 MB (default for max_message_size)
 //! - `MAX_DECOMPRESSED_SIZE`: 512 MB (default for max_decompressed_size)
+//! - `MAX_SYMBOL_LENGTH`: 64 KB (default for max_symbol_len)
 //!
 //! These defaults are based on kdb+ database limits documented at:
 //! https://www.timestored.com/kdb-guides/kdb-database-limits
@@ -197,6 +198,15 @@ pub const MAX_MESSAGE_SIZE: usize = 256 * 1024 * 1024; // 256 MB
 /// This serves as the default value for `KdbCodec::max_decompressed_size`.
 pub const MAX_DECOMPRESSED_SIZE: usize = 512 * 1024 * 1024; // 512 MB
 
+/// Maximum allowed symbol length in bytes during deserialization (64 KB)
+///
+/// This limit bounds the scan for the null terminator of a symbol, so that a malformed
+/// symbol without a terminator fails fast instead of scanning the entire remaining buffer.
+/// The same limit applies to error messages and lambda contexts.
+///
+/// This serves as the default value for `KdbCodec::max_symbol_len`.
+pub const MAX_SYMBOL_LENGTH: usize = 64 * 1024; // 64 KB
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Module Declarations
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -206,23 +216,29 @@ mod conversions;
 pub mod error;
 mod index;
 mod macros;
+mod ops;
+mod parse;
 mod qconsts;
 mod qnull_inf;
 mod types;
+mod validate;
 
 // IPC modules
 pub mod codec;
 mod connection;
 mod deserialize_sync;
 mod format;
+mod journal;
 mod serialize;
+#[cfg(feature = "transport-compression")]
+mod transport;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Re-exports
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 // Re-export qconsts modules at root level
-pub use qconsts::{qattribute, qinf_base, qninf_base, qnull_base, qtype};
+pub use qconsts::{qattribute, qinf_base, qninf_base, qnull_base, qprimitive, qtype};
 
 // Re-export qnull_inf modules at root level
 pub use qnull_inf::{qinf, qninf, qnull};
@@ -236,8 +252,18 @@ pub(crate) use types::{k0, k0_inner, k0_list, AsAny, Klone};
 // Re-export conversions
 pub use conversions::*;
 
+// Re-export from validate
+pub use validate::MessageSchema;
+
 // Re-export from codec
 pub use codec::*;
 
 // Re-export from connection
 pub use connection::*;
+
+// Re-export from journal
+pub use journal::JournalReader;
+
+// Re-export from transport
+#[cfg(feature = "transport-compression")]
+pub use transport::TransportCompression;
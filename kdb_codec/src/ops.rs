@@ -0,0 +1,3930 @@
+//! List and table operations mirroring q built-in functions.
+//!
+//! This module provides a small set of q verbs (`wavg`, `distinct`, `group`, `cols`, ...) as methods on `K` so that
+//! common calculations on received columns can be done without converting to Rust collections first.
+//!
+//! # Example
+//! ```
+//! use kdb_codec::*;
+//!
+//! let price = k!(float: vec![10.0, 11.0, 12.0]);
+//! let size = k!(long: vec![100, 200, 100]);
+//!
+//! // VWAP
+//! assert_eq!(price.wavg(&size).unwrap(), 11.0);
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use crate::conversions::{q_datetime_to_q_timestamp, q_timestamp_to_q_datetime};
+use crate::error::Error;
+use crate::qconsts::{qattribute, qnull_base, qtype};
+use crate::types::*;
+use std::cmp::Ordering;
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl K {
+    // Aggregation //----------------------------/
+
+    /// Weighted sum of a numeric list against an equal-length numeric weight list (q `wsum`).
+    ///  Pairs where either element is null are ignored.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let price = k!(float: vec![10.0, 11.0, 12.0]);
+    ///     let size = k!(long: vec![100, 200, 100]);
+    ///     assert_eq!(price.wsum(&size).unwrap(), 4400.0);
+    /// }
+    /// ```
+    pub fn wsum(&self, weights: &K) -> Result<f64> {
+        Ok(numeric_pairs("wsum", self, weights)?
+            .into_iter()
+            .map(|(value, weight)| value * weight)
+            .sum())
+    }
+
+    /// Weighted average of a numeric list against an equal-length numeric weight list (q `wavg`).
+    ///  Pairs where either element is null are ignored. If the total weight is zero, `NaN` (`0n`) is returned.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let price = k!(float: vec![10.0, 11.0, 12.0]);
+    ///     let size = k!(long: vec![100, 200, 100]);
+    ///     assert_eq!(price.wavg(&size).unwrap(), 11.0);
+    /// }
+    /// ```
+    pub fn wavg(&self, weights: &K) -> Result<f64> {
+        let (sum, total_weight) = numeric_pairs("wavg", self, weights)?
+            .into_iter()
+            .fold((0.0, 0.0), |(sum, total), (value, weight)| {
+                (sum + value * weight, total + weight)
+            });
+        if total_weight == 0.0 {
+            Ok(f64::NAN)
+        } else {
+            Ok(sum / total_weight)
+        }
+    }
+
+    /// Dot product of two equal-length numeric lists (q `mmu` of two vectors). Nulls are treated as
+    ///  zero, i.e., pairs where either element is null are ignored as in [`wsum`](#method.wsum).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let x = k!(float: vec![1.0, 2.0, 3.0]);
+    ///     let y = k!(long: vec![4, 5, 6]);
+    ///     assert_eq!(x.dot(&y).unwrap(), 32.0);
+    /// }
+    /// ```
+    pub fn dot(&self, other: &K) -> Result<f64> {
+        Ok(numeric_pairs("dot", self, other)?
+            .into_iter()
+            .map(|(x, y)| x * y)
+            .sum())
+    }
+
+    /// Matrix product (q `mmu`). A matrix is a compound list of equal-length numeric lists, one per
+    ///  row, and the result is a compound list of float lists. Nulls are treated as zero.
+    /// # Note
+    /// The number of columns of `self` must match the number of rows of `other`; otherwise
+    ///  `Error::LengthMismatch` is returned.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let x = k!([k!(float: vec![1.0, 2.0]), k!(float: vec![3.0, 4.0])]);
+    ///     let y = k!([k!(float: vec![5.0, 6.0]), k!(float: vec![7.0, 8.0])]);
+    ///     assert_eq!(format!("{}", x.mmu(&y).unwrap()), String::from("(19 22;43 50)"));
+    /// }
+    /// ```
+    pub fn mmu(&self, other: &K) -> Result<K> {
+        let left = matrix_rows("mmu", self)?;
+        let right = matrix_rows("mmu", other)?;
+        let inner = left.first().map_or(0, |row| row.len());
+        if inner != right.len() {
+            return Err(Error::length_mismatch(inner, right.len()));
+        }
+        let columns = right.first().map_or(0, |row| row.len());
+        let rows = left
+            .iter()
+            .map(|row| {
+                let product = (0..columns)
+                    .map(|column| {
+                        row.iter()
+                            .zip(&right)
+                            .map(|(x, right_row)| x * right_row[column])
+                            .sum()
+                    })
+                    .collect::<Vec<F>>();
+                K::new_float_list(product, qattribute::NONE)
+            })
+            .collect::<Vec<K>>();
+        Ok(K::new_compound_list(rows))
+    }
+
+    /// Moving average over windows of `window` elements (q `mavg`). The first `window - 1` results
+    ///  average the shorter leading windows and nulls are ignored. A window with only nulls gives
+    ///  `NaN` (`0n`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let price = k!(float: vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    ///     let averages = price.mavg(3).unwrap();
+    ///     assert_eq!(averages.as_vec::<F>().unwrap(), &vec![1.0, 1.5, 2.0, 3.0, 4.0]);
+    /// }
+    /// ```
+    pub fn mavg(&self, window: usize) -> Result<K> {
+        self.sliding(window, |values| match values.len() {
+            0 => f64::NAN,
+            length => values.iter().sum::<f64>() / length as f64,
+        })
+    }
+
+    /// Apply `f` to each sliding window of `window` elements of a numeric list and return the
+    ///  results as a float list. As in q moving functions, the window ending at each element is
+    ///  used, so the first `window - 1` windows are shorter. `f` receives the non-null values of
+    ///  the window as floats.
+    /// # Note
+    /// Error is returned if `window` is zero.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let size = k!(long: vec![3, 1, 4, 1, 5]);
+    ///     let moving_max = size
+    ///         .sliding(2, |values| values.iter().cloned().fold(f64::NAN, f64::max))
+    ///         .unwrap();
+    ///     assert_eq!(moving_max.as_vec::<F>().unwrap(), &vec![3.0, 3.0, 4.0, 4.0, 5.0]);
+    /// }
+    /// ```
+    pub fn sliding<F>(&self, window: usize, f: F) -> Result<K>
+    where
+        F: Fn(&[f64]) -> f64,
+    {
+        if window == 0 {
+            return Err(Error::invalid_operation("sliding", qtype::LONG_ATOM, None));
+        }
+        let values = numeric_values("sliding", self)?;
+        let mut buffer = Vec::with_capacity(window);
+        let results = (0..values.len())
+            .map(|end| {
+                buffer.clear();
+                buffer.extend(
+                    values[end.saturating_sub(window - 1)..=end]
+                        .iter()
+                        .flatten(),
+                );
+                f(&buffer)
+            })
+            .collect::<Vec<f64>>();
+        Ok(K::new_float_list(results, qattribute::NONE))
+    }
+
+    // List //-----------------------------------/
+
+    /// Build the long list `0 1 ... n-1` (q `til`). A non-positive `n` gives an empty list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     assert_eq!(format!("{}", K::til(5)), String::from("0 1 2 3 4"));
+    /// }
+    /// ```
+    pub fn til(n: i64) -> K {
+        K::new_long_list((0..n.max(0)).collect(), qattribute::NONE)
+    }
+
+    /// Build the long list running from `start` towards `stop` (exclusive) by `step`. A negative
+    ///  step counts down.
+    /// # Note
+    /// Error is returned if `step` is zero.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     assert_eq!(format!("{}", K::range(1, 10, 3).unwrap()), String::from("1 4 7"));
+    ///     assert_eq!(format!("{}", K::range(5, 0, -2).unwrap()), String::from("5 3 1"));
+    /// }
+    /// ```
+    pub fn range(start: i64, stop: i64, step: i64) -> Result<K> {
+        if step == 0 {
+            return Err(Error::invalid_operation("range", qtype::LONG_ATOM, None));
+        }
+        let list = std::iter::successors(Some(start), |value| value.checked_add(step))
+            .take_while(|&value| if step > 0 { value < stop } else { value > stop })
+            .collect::<Vec<J>>();
+        Ok(K::new_long_list(list, qattribute::NONE))
+    }
+
+    /// Concatenate the lists held by a compound list into one list (q `raze`). If all lists share a type
+    ///  the result is a list of that type, otherwise a compound list of their elements.
+    /// # Note
+    /// Error is returned if `self` is not a compound list or if any of its elements is not a list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_compound = k!([k!(long: vec![1, 2]), k!(long: vec![3])]);
+    ///     assert_eq!(
+    ///         format!("{}", q_compound.raze().unwrap()),
+    ///         String::from("1 2 3")
+    ///     );
+    ///
+    ///     let q_mixed = k!([k!(long: vec![1, 2]), k!(sym: vec!["a"])]);
+    ///     assert_eq!(
+    ///         format!("{}", q_mixed.raze().unwrap()),
+    ///         String::from("(1;2;`a)")
+    ///     );
+    /// }
+    /// ```
+    pub fn raze(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::COMPOUND_LIST => raze_lists("raze", self.as_vec::<K>()?),
+            _ => Err(Error::invalid_operation(
+                "raze",
+                self.get_type(),
+                Some(qtype::COMPOUND_LIST),
+            )),
+        }
+    }
+
+    /// Count each element of a compound list (q `count each`). An atom counts as 1, a table as its
+    ///  number of rows and a dictionary as its number of keys.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_ragged = k!([k!(long: vec![1, 2, 3]), k!(sym: "a"), k!(string: "hello")]);
+    ///     assert_eq!(q_ragged.count_each().unwrap().as_vec::<J>().unwrap(), &vec![3, 1, 5]);
+    /// }
+    /// ```
+    pub fn count_each(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::COMPOUND_LIST => Ok(K::new_long_list(
+                self.as_vec::<K>()?
+                    .iter()
+                    .map(|element| match element.get_type() {
+                        qtype if qtype < 0 => 1,
+                        _ => element.len() as J,
+                    })
+                    .collect(),
+                qattribute::NONE,
+            )),
+            qtype => Err(Error::invalid_operation(
+                "count_each",
+                qtype,
+                Some(qtype::COMPOUND_LIST),
+            )),
+        }
+    }
+
+    /// Convert a typed list into a compound list holding each element as an atom, e.g. to append
+    ///  elements of another type. A compound list is returned as is.
+    /// # Note
+    /// Error is returned if `self` is not a list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_compound = k!(long: vec![1, 2]).to_compound().unwrap();
+    ///     assert_eq!(q_compound.get_type(), qtype::COMPOUND_LIST);
+    ///     assert_eq!(format!("{}", q_compound), String::from("(1;2)"));
+    /// }
+    /// ```
+    pub fn to_compound(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::COMPOUND_LIST => Ok(self.clone()),
+            qtype::STRING => Ok(K::new_compound_list(
+                self.as_string()?.chars().map(K::new_char).collect(),
+            )),
+            _ if is_list(self) => {
+                K::compound_from_iter_result((0..self.len()).map(|index| element_at(self, index)))
+            }
+            qtype => Err(Error::invalid_operation("to_compound", qtype, None)),
+        }
+    }
+
+    /// Split a list at the given cut points (q `_` with an int list on the left). Each sublist runs from
+    ///  a cut point up to the next one; elements before the first cut point are dropped.
+    /// # Parameters
+    /// - `indices`: Ascending int or long list of cut points.
+    /// # Note
+    /// Error is returned if `indices` is not ascending or if a cut point is negative or beyond the
+    ///  end of `self`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![1, 2, 3, 4, 5]);
+    ///     assert_eq!(
+    ///         format!("{}", q_long_list.cut(&k!(long: vec![0, 2])).unwrap()),
+    ///         String::from("(1 2;3 4 5)")
+    ///     );
+    /// }
+    /// ```
+    pub fn cut(&self, indices: &K) -> Result<K> {
+        if !is_list(self) {
+            return Err(Error::invalid_operation("cut", self.get_type(), None));
+        }
+        let points = index_values("cut", indices)?;
+        if points.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(Error::not_ascending("cut"));
+        }
+        let length = self.len();
+        let points = points
+            .into_iter()
+            .map(|point| match usize::try_from(point) {
+                Ok(point) if point <= length => Ok(point),
+                Ok(point) => Err(Error::index_out_of_bounds(length, point)),
+                Err(_) => Err(Error::invalid_structure(format!(
+                    "cut point {} is negative",
+                    point
+                ))),
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        let ends = points
+            .iter()
+            .skip(1)
+            .copied()
+            .chain(std::iter::once(length));
+        let sublists = points
+            .iter()
+            .zip(ends)
+            .map(|(&start, end)| take(self, &(start..end).collect::<Vec<usize>>()))
+            .collect::<Result<Vec<K>>>()?;
+        Ok(K::new_compound_list(sublists))
+    }
+
+    /// Reshape a flat list into `rows` lists of `cols` elements each (q `#` with a pair of counts on
+    ///  the left, e.g. `2 3#til 6`). Each row keeps the type of `self`.
+    /// # Note
+    /// Error is returned if the length of `self` is not `rows * cols` or if the product overflows.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![0, 1, 2, 3, 4, 5]);
+    ///     assert_eq!(
+    ///         format!("{}", q_long_list.reshape(2, 3).unwrap()),
+    ///         String::from("(0 1 2;3 4 5)")
+    ///     );
+    /// }
+    /// ```
+    pub fn reshape(&self, rows: usize, cols: usize) -> Result<K> {
+        if !is_list(self) {
+            return Err(Error::invalid_operation("reshape", self.get_type(), None));
+        }
+        let size = rows.checked_mul(cols).ok_or(Error::SizeOverflow)?;
+        if size != self.len() {
+            return Err(Error::length_mismatch(size, self.len()));
+        }
+        let rows = (0..rows)
+            .map(|row| {
+                take(
+                    self,
+                    &(row * cols..(row + 1) * cols).collect::<Vec<usize>>(),
+                )
+            })
+            .collect::<Result<Vec<K>>>()?;
+        Ok(K::new_compound_list(rows))
+    }
+
+    /// Replace each null with the last preceding non-null element (q `fills`). Leading nulls stay null.
+    /// # Note
+    /// Supported for bool, byte, short, int, long, real, float, symbol and temporal lists.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_float_list = k!(float: vec![qnull::FLOAT, 1.5, qnull::FLOAT, 3.0]);
+    ///     assert_eq!(
+    ///         format!("{}", q_float_list.fills().unwrap()),
+    ///         String::from("0n 1.5 1.5 3")
+    ///     );
+    /// }
+    /// ```
+    pub fn fills(&self) -> Result<K> {
+        let qtype = self.get_type();
+        let inner = match qtype {
+            qtype::BOOL_LIST | qtype::BYTE_LIST => {
+                k0_inner::list(k0_list::new(self.as_vec::<G>()?.clone()))
+            }
+            qtype::SHORT_LIST => {
+                k0_inner::list(k0_list::new(fill_forward(self.as_vec::<H>()?, |&value| {
+                    value == qnull_base::H
+                })))
+            }
+            qtype::INT_LIST
+            | qtype::MONTH_LIST
+            | qtype::DATE_LIST
+            | qtype::MINUTE_LIST
+            | qtype::SECOND_LIST
+            | qtype::TIME_LIST => {
+                k0_inner::list(k0_list::new(fill_forward(self.as_vec::<I>()?, |&value| {
+                    value == qnull_base::I
+                })))
+            }
+            qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+                k0_inner::list(k0_list::new(fill_forward(self.as_vec::<J>()?, |&value| {
+                    value == qnull_base::J
+                })))
+            }
+            qtype::REAL_LIST => {
+                k0_inner::list(k0_list::new(fill_forward(self.as_vec::<E>()?, |value| {
+                    value.is_nan()
+                })))
+            }
+            qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+                k0_inner::list(k0_list::new(fill_forward(self.as_vec::<F>()?, |value| {
+                    value.is_nan()
+                })))
+            }
+            qtype::SYMBOL_LIST => {
+                k0_inner::list(k0_list::new(fill_forward(self.as_vec::<S>()?, |value| {
+                    value.is_empty()
+                })))
+            }
+            _ => return Err(Error::invalid_operation("fills", qtype, None)),
+        };
+        Ok(K::new(qtype, qattribute::NONE, inner))
+    }
+
+    /// Ratio of each element of a numeric list to the previous one as a float list (q `ratios`). The
+    ///  first element is kept as it is. Nulls give null and division by zero gives infinity, or null
+    ///  for `0 % 0`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_float_list = k!(float: vec![100.0, 110.0, 99.0]);
+    ///     assert_eq!(
+    ///         format!("{}", q_float_list.ratios().unwrap()),
+    ///         String::from("100 1.1 0.9")
+    ///     );
+    /// }
+    /// ```
+    pub fn ratios(&self) -> Result<K> {
+        let values = numeric_values("ratios", self)?
+            .into_iter()
+            .map(|value| value.unwrap_or(qnull_base::F))
+            .collect::<Vec<F>>();
+        let ratios = values
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| match index {
+                0 => value,
+                _ => value / values[index - 1],
+            })
+            .collect();
+        Ok(K::new_float_list(ratios, qattribute::NONE))
+    }
+
+    /// Apply a binary function to corresponding elements of two lists of the same length (q each-both `'`).
+    ///  Elements of typed lists are passed as atoms and the results are collected into a compound list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let price = k!(float: vec![100.0, 200.0]);
+    ///     let adjustment = k!(float: vec![0.5, -1.5]);
+    ///     let adjusted = price
+    ///         .zip_with(&adjustment, |p, a| Ok(k!(float: p.get_float()? + a.get_float()?)))
+    ///         .unwrap();
+    ///     assert_eq!(format!("{}", adjusted), String::from("(100.5;198.5)"));
+    /// }
+    /// ```
+    pub fn zip_with<F>(&self, other: &K, f: F) -> Result<K>
+    where
+        F: Fn(K, K) -> Result<K>,
+    {
+        for list in [self, other] {
+            if !is_list(list) {
+                return Err(Error::invalid_operation("zip_with", list.get_type(), None));
+            }
+        }
+        if self.len() != other.len() {
+            return Err(Error::length_mismatch(self.len(), other.len()));
+        }
+        let results = (0..self.len())
+            .map(|index| f(element_at(self, index)?, element_at(other, index)?))
+            .collect::<Result<Vec<K>>>()?;
+        Ok(K::new_compound_list(results))
+    }
+
+    /// Apply a binary function to each element of a list and its predecessor (q each-prior `':`).
+    ///  The first element is kept as it is and the function is called as `f(current, previous)` for
+    ///  the others. Elements of typed lists are passed as atoms and the results are collected into a
+    ///  compound list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![1, 4, 9]);
+    ///     let deltas = q_long_list
+    ///         .each_prior(|x, y| Ok(k!(long: x.get_long()? - y.get_long()?)))
+    ///         .unwrap();
+    ///     assert_eq!(format!("{}", deltas), String::from("(1;3;5)"));
+    /// }
+    /// ```
+    pub fn each_prior<F>(&self, f: F) -> Result<K>
+    where
+        F: Fn(K, K) -> Result<K>,
+    {
+        if !is_list(self) {
+            return Err(Error::invalid_operation(
+                "each_prior",
+                self.get_type(),
+                None,
+            ));
+        }
+        let results = (0..self.len())
+            .map(|index| match index {
+                0 => element_at(self, index),
+                _ => f(element_at(self, index)?, element_at(self, index - 1)?),
+            })
+            .collect::<Result<Vec<K>>>()?;
+        Ok(K::new_compound_list(results))
+    }
+
+    /// Take elements of a list at the given indices (q `@` with an index list).
+    /// # Parameters
+    /// - `indices`: Int or long list of indices.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "c"]);
+    ///     assert_eq!(
+    ///         format!("{}", q_symbol_list.gather(&k!(long: vec![2, 0, 2])).unwrap()),
+    ///         String::from("`c`a`c")
+    ///     );
+    /// }
+    /// ```
+    pub fn gather(&self, indices: &K) -> Result<K> {
+        let indices = index_values("gather", indices)?
+            .into_iter()
+            .map(|index| usize::try_from(index).unwrap_or(usize::MAX))
+            .collect::<Vec<usize>>();
+        take(self, &indices)
+    }
+
+    /// Indices of elements for which a predicate holds (q `where` over a boolean mask). Each element
+    ///  is passed as an atom, or as is for a compound list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "a"]);
+    ///     let indices = q_symbol_list
+    ///         .which(|symbol| symbol.get_symbol().map_or(false, |symbol| symbol == "a"))
+    ///         .unwrap();
+    ///     assert_eq!(format!("{}", indices), String::from("0 2"));
+    /// }
+    /// ```
+    pub fn which<F>(&self, predicate: F) -> Result<K>
+    where
+        F: Fn(&K) -> bool,
+    {
+        if !is_list(self) {
+            return Err(Error::invalid_operation("which", self.get_type(), None));
+        }
+        let mut indices = Vec::new();
+        for index in 0..self.len() {
+            if predicate(&element_at(self, index)?) {
+                indices.push(index as J);
+            }
+        }
+        Ok(K::new_long_list(indices, qattribute::NONE))
+    }
+
+    /// Indices of elements greater than a threshold. Both `self` and `threshold` must be numeric
+    ///  (short, int, long, real or float); `threshold` must be an atom. Nulls are never greater.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_float_list = k!(float: vec![1.5, 3.0, 0.5]);
+    ///     assert_eq!(
+    ///         format!("{}", q_float_list.which_gt(&k!(long: 1)).unwrap()),
+    ///         String::from("0 1")
+    ///     );
+    /// }
+    /// ```
+    pub fn which_gt(&self, threshold: &K) -> Result<K> {
+        let (_, atom, values) = numbers("which_gt", self)?;
+        if atom {
+            return Err(Error::invalid_operation("which_gt", self.get_type(), None));
+        }
+        let threshold = match numbers("which_gt", threshold)? {
+            (_, true, threshold) => threshold,
+            _ => {
+                return Err(Error::invalid_operation(
+                    "which_gt",
+                    threshold.get_type(),
+                    Some(qtype::FLOAT_ATOM),
+                ))
+            }
+        };
+        let indices = (0..self.len())
+            .filter(|&index| match (&values, &threshold) {
+                (Numbers::Integer(values), Numbers::Integer(threshold)) => {
+                    matches!((values[index], threshold[0]), (Some(x), Some(y)) if x > y)
+                }
+                _ => values.float(index) > threshold.float(0),
+            })
+            .map(|index| index as J)
+            .collect::<Vec<J>>();
+        Ok(K::new_long_list(indices, qattribute::NONE))
+    }
+
+    /// Boolean mask flagging null elements of a list (q `null`). Supported types are guid, short, int,
+    ///  long, real, float, char, symbol and their temporal counterparts.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![1, qnull_base::J, 3]);
+    ///     assert_eq!(format!("{}", q_long_list.null_mask().unwrap()), String::from("010b"));
+    /// }
+    /// ```
+    pub fn null_mask(&self) -> Result<K> {
+        let flags = null_flags("null_mask", self)?;
+        Ok(K::new_bool_list(flags, qattribute::NONE))
+    }
+
+    /// Cyclically shift elements of a list by `n` positions (q `n rotate list`). Positive `n` moves
+    ///  elements towards the head and negative `n` towards the tail.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![1, 2, 3, 4]);
+    ///     assert_eq!(format!("{}", q_long_list.rotate(1).unwrap()), String::from("2 3 4 1"));
+    ///     assert_eq!(format!("{}", q_long_list.rotate(-1).unwrap()), String::from("4 1 2 3"));
+    /// }
+    /// ```
+    pub fn rotate(&self, n: i64) -> Result<K> {
+        if !is_list(self) {
+            return Err(Error::invalid_operation("rotate", self.get_type(), None));
+        }
+        let length = self.len() as i64;
+        // Reduce `n` first so that the sum cannot overflow
+        let shift = if length == 0 { 0 } else { n.rem_euclid(length) };
+        let indices = (0..length)
+            .map(|index| ((index + shift) % length) as usize)
+            .collect::<Vec<usize>>();
+        take(self, &indices)
+    }
+
+    /// Sort a list in ascending order (q `asc`). The result has the sorted attribute. Nulls sort
+    ///  first and equal elements keep their order.
+    /// # Note
+    /// Supported for bool, byte, short, int, long, real, float, symbol and temporal lists.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![3, 1, 2]);
+    ///     assert_eq!(format!("{}", q_long_list.asc().unwrap()), String::from("`s#1 2 3"));
+    /// }
+    /// ```
+    pub fn asc(&self) -> Result<K> {
+        let mut sorted = take(self, &sort_order("asc", self, false)?)?;
+        sorted.set_attribute(qattribute::SORTED);
+        Ok(sorted)
+    }
+
+    /// Sort a list in descending order (q `desc`). Nulls sort last and equal elements keep their
+    ///  order.
+    /// # Note
+    /// Supported for bool, byte, short, int, long, real, float, symbol and temporal lists.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["b", "c", "a"]);
+    ///     assert_eq!(format!("{}", q_symbol_list.desc().unwrap()), String::from("`c`b`a"));
+    /// }
+    /// ```
+    pub fn desc(&self) -> Result<K> {
+        take(self, &sort_order("desc", self, true)?)
+    }
+
+    /// Assign each element of a list to one of `buckets` rank buckets of equal size by its
+    ///  position in ascending order (q `xrank`), e.g. `10` for deciles. The bucket of the element
+    ///  at sorted position `i` of `n` elements is `buckets * i / n`. Nulls sort first and equal
+    ///  elements keep their order.
+    /// # Note
+    /// Supported for bool, byte, short, int, long, real, float, symbol and temporal lists.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_float_list = k!(float: vec![3.5, 1.0, 4.0, 2.0]);
+    ///     assert_eq!(format!("{}", q_float_list.xrank(2).unwrap()), String::from("1 0 1 0"));
+    /// }
+    /// ```
+    pub fn xrank(&self, buckets: usize) -> Result<K> {
+        let order = sort_order("xrank", self, false)?;
+        let length = order.len() as u128;
+        let mut ranks = vec![0; order.len()];
+        for (position, index) in order.into_iter().enumerate() {
+            ranks[index] = (buckets as u128 * position as u128 / length) as J;
+        }
+        Ok(K::new_long_list(ranks, qattribute::NONE))
+    }
+
+    /// Index of the greatest element less than or equal to each value in a sorted list (q `bin`),
+    ///  or -1 if every element is greater. The basis of as-of lookups. A long atom is returned for an
+    ///  atom and a long list for a list.
+    /// # Parameters
+    /// - `values`: Atom or list of the same type as this list.
+    /// # Note
+    /// - This list must be numeric (short, int, long, real or float) or temporal.
+    /// - Ascending order is verified unless the list has the sorted attribute. Nulls sort first.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![10, 20, 30]; @sorted);
+    ///     assert_eq!(
+    ///         format!("{}", q_long_list.bin(&k!(long: vec![5, 10, 25, 99])).unwrap()),
+    ///         String::from("-1 0 1 2")
+    ///     );
+    ///     assert_eq!(q_long_list.bin(&k!(long: 20)).unwrap().get_long().unwrap(), 1);
+    /// }
+    /// ```
+    pub fn bin(&self, values: &K) -> Result<K> {
+        let (qtype, atom, basis) = ordinals("bin", self)?;
+        if atom {
+            return Err(Error::invalid_operation("bin", self.get_type(), None));
+        }
+        let (value_atom, values) = match ordinals("bin", values) {
+            Ok((value_type, value_atom, numbers)) if value_type == qtype => (value_atom, numbers),
+            _ => {
+                return Err(Error::invalid_operation(
+                    "bin",
+                    values.get_type(),
+                    Some(qtype),
+                ))
+            }
+        };
+
+        let indices = match (&basis, &values) {
+            (Numbers::Integer(basis), Numbers::Integer(values)) => {
+                if self.get_attribute() != qattribute::SORTED
+                    && !basis.windows(2).all(|pair| pair[0] <= pair[1])
+                {
+                    return Err(Error::not_ascending("bin"));
+                }
+                values
+                    .iter()
+                    .map(|value| basis.partition_point(|element| element <= value) as J - 1)
+                    .collect::<Vec<J>>()
+            }
+            (Numbers::Float(basis), Numbers::Float(values)) => {
+                if self.get_attribute() != qattribute::SORTED
+                    && !basis.windows(2).all(|pair| float_le(pair[0], pair[1]))
+                {
+                    return Err(Error::not_ascending("bin"));
+                }
+                values
+                    .iter()
+                    .map(|&value| {
+                        basis.partition_point(|&element| float_le(element, value)) as J - 1
+                    })
+                    .collect::<Vec<J>>()
+            }
+            _ => unreachable!("operands of the same type"),
+        };
+        if value_atom {
+            Ok(K::new_long(indices[0]))
+        } else {
+            Ok(K::new_long_list(indices, qattribute::NONE))
+        }
+    }
+
+    /// Index of the first occurrence of `value` in a list (q `?` with a list on the left). As in q,
+    ///  the length of the list is returned if `value` is not found.
+    /// # Parameters
+    /// - `value`: Atom of the type of the elements of this list, or any object for a compound list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_long_list = k!(long: vec![10, 20, 30, 20]);
+    ///     assert_eq!(q_long_list.find_index(&k!(long: 20)).unwrap(), 1);
+    ///     assert_eq!(q_long_list.find_index(&k!(long: 40)).unwrap(), 4);
+    /// }
+    /// ```
+    pub fn find_index(&self, value: &K) -> Result<i64> {
+        let key = match self.get_type() {
+            qtype::COMPOUND_LIST => ElementKey::Object(value.q_ipc_encode()),
+            // Only list types are negated; the type of an error object is `i8::MIN`
+            qtype if qtype > 0 && value.get_type() == -qtype => {
+                element_keys("find_index", &enlist(value))?.remove(0)
+            }
+            qtype if qtype > 0 => {
+                return Err(Error::invalid_operation(
+                    "find_index",
+                    value.get_type(),
+                    Some(-qtype),
+                ))
+            }
+            qtype => return Err(Error::invalid_operation("find_index", qtype, None)),
+        };
+        let keys = element_keys("find_index", self)?;
+        Ok(keys
+            .iter()
+            .position(|element| *element == key)
+            .unwrap_or(keys.len()) as i64)
+    }
+
+    // Grouping //-------------------------------/
+
+    /// Unique elements of a list in order of first occurrence (q `distinct`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "a", "c", "b"]);
+    ///     assert_eq!(
+    ///         format!("{}", q_symbol_list.distinct().unwrap()),
+    ///         String::from("`a`b`c")
+    ///     );
+    /// }
+    /// ```
+    pub fn distinct(&self) -> Result<K> {
+        let firsts = group_indices("distinct", self)?
+            .iter()
+            .map(|indices| indices[0])
+            .collect::<Vec<usize>>();
+        take(self, &firsts)
+    }
+
+    /// Dictionary mapping each distinct element of a list to the long list of its indices (q `group`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "a", "c", "b"]);
+    ///     assert_eq!(
+    ///         format!("{}", q_symbol_list.group().unwrap()),
+    ///         String::from("`a`b`c!(0 2;1 4;,3)")
+    ///     );
+    /// }
+    /// ```
+    pub fn group(&self) -> Result<K> {
+        let groups = group_indices("group", self)?;
+        let firsts = groups
+            .iter()
+            .map(|indices| indices[0])
+            .collect::<Vec<usize>>();
+        let indices = groups
+            .into_iter()
+            .map(|indices| {
+                K::new_long_list(
+                    indices.into_iter().map(|index| index as J).collect(),
+                    qattribute::NONE,
+                )
+            })
+            .collect();
+        K::new_dictionary(take(self, &firsts)?, K::new_compound_list(indices))
+    }
+
+    /// Number of distinct elements of a list (q `count distinct`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "a", "c", "b"]);
+    ///     assert_eq!(q_symbol_list.count_distinct().unwrap(), 3);
+    /// }
+    /// ```
+    pub fn count_distinct(&self) -> Result<usize> {
+        Ok(group_indices("count_distinct", self)?.len())
+    }
+
+    /// Dictionary mapping each distinct element of a list to the number of its occurrences
+    ///  (q `count each group`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "a", "c", "b", "a"]);
+    ///     assert_eq!(
+    ///         format!("{}", q_symbol_list.frequencies().unwrap()),
+    ///         String::from("`a`b`c!3 2 1")
+    ///     );
+    /// }
+    /// ```
+    pub fn frequencies(&self) -> Result<K> {
+        let groups = group_indices("frequencies", self)?;
+        let firsts = groups
+            .iter()
+            .map(|indices| indices[0])
+            .collect::<Vec<usize>>();
+        let counts = groups
+            .iter()
+            .map(|indices| indices.len() as J)
+            .collect::<Vec<J>>();
+        K::new_dictionary(
+            take(self, &firsts)?,
+            K::new_long_list(counts, qattribute::NONE),
+        )
+    }
+
+    // Set //------------------------------------/
+
+    /// Elements of a list which are not in another list of the same type, in their original order
+    ///  (q `except`). Duplicates are kept.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "c", "b"]);
+    ///     let other = k!(sym: vec!["c"]);
+    ///     assert_eq!(format!("{}", q_symbol_list.except(&other).unwrap()), String::from("`a`b`b"));
+    /// }
+    /// ```
+    pub fn except(&self, other: &K) -> Result<K> {
+        let found = membership("except", self, other)?;
+        let indices = (0..found.len())
+            .filter(|&index| !found[index])
+            .collect::<Vec<usize>>();
+        take(self, &indices)
+    }
+
+    /// Elements of a list which are also in another list of the same type, in their original order
+    ///  (q `inter`). Duplicates are kept.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "c", "b"]);
+    ///     let other = k!(sym: vec!["b", "c", "d"]);
+    ///     assert_eq!(format!("{}", q_symbol_list.inter(&other).unwrap()), String::from("`b`c`b"));
+    /// }
+    /// ```
+    pub fn inter(&self, other: &K) -> Result<K> {
+        let found = membership("inter", self, other)?;
+        let indices = (0..found.len())
+            .filter(|&index| found[index])
+            .collect::<Vec<usize>>();
+        take(self, &indices)
+    }
+
+    /// Distinct elements of two lists of the same type in order of first occurrence (q `union`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "b", "a"]);
+    ///     let other = k!(sym: vec!["c", "b"]);
+    ///     assert_eq!(format!("{}", q_symbol_list.union(&other).unwrap()), String::from("`a`b`c"));
+    /// }
+    /// ```
+    pub fn union(&self, other: &K) -> Result<K> {
+        membership("union", self, other)?;
+        let joined = raze_lists("union", &[self.clone(), other.clone()])?;
+        let firsts = group_indices("union", &joined)?
+            .iter()
+            .map(|indices| indices[0])
+            .collect::<Vec<usize>>();
+        take(&joined, &firsts)
+    }
+
+    // String //---------------------------------/
+
+    /// Convert a symbol atom, a symbol list or a string to lower case (q `lower`). Only ASCII letters
+    ///  are converted; other characters including non-ASCII letters are kept as they are.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["AB", "Cd"]);
+    ///     assert_eq!(format!("{}", q_symbol_list.to_lower().unwrap()), String::from("`ab`cd"));
+    /// }
+    /// ```
+    pub fn to_lower(&self) -> Result<K> {
+        map_text("to_lower", self, |text| text.to_ascii_lowercase())
+    }
+
+    /// Convert a symbol atom, a symbol list or a string to upper case (q `upper`). Only ASCII letters
+    ///  are converted; other characters including non-ASCII letters are kept as they are.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_string = k!(string: "Hello");
+    ///     assert_eq!(format!("{}", q_string.to_upper().unwrap()), String::from("\"HELLO\""));
+    /// }
+    /// ```
+    pub fn to_upper(&self) -> Result<K> {
+        map_text("to_upper", self, |text| text.to_ascii_uppercase())
+    }
+
+    /// Remove leading and trailing whitespace from a symbol atom, each symbol of a symbol list or a
+    ///  string (q `trim`). Only ASCII whitespace is removed.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_string = k!(string: "  price ");
+    ///     assert_eq!(format!("{}", q_string.trim().unwrap()), String::from("\"price\""));
+    /// }
+    /// ```
+    pub fn trim(&self) -> Result<K> {
+        map_text("trim", self, |text| {
+            text.trim_matches(|c: char| c.is_ascii_whitespace())
+                .to_string()
+        })
+    }
+
+    /// Replace every occurrence of `pattern` in a string with `replacement` (q `ssr`) and return
+    ///  the result as a new string. Occurrences are found from left to right without overlapping,
+    ///  and a string without any occurrence is returned unchanged.
+    /// # Note
+    /// - `pattern` is matched literally. Unlike q, `?` and `[]` are not treated as wildcards.
+    /// - Error is returned if `self` is not a string or if `pattern` is empty.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_string = k!(string: "a-b-c");
+    ///     let replaced = q_string.ssr("-", ", ").unwrap();
+    ///     assert_eq!(format!("{}", replaced), String::from("\"a, b, c\""));
+    /// }
+    /// ```
+    pub fn ssr(&self, pattern: &str, replacement: &str) -> Result<K> {
+        match self.get_type() {
+            qtype::STRING if pattern.is_empty() => {
+                Err(Error::invalid_operation("ssr", qtype::STRING, None))
+            }
+            qtype::STRING => Ok(K::new_string(
+                self.as_string()?.replace(pattern, replacement),
+                qattribute::NONE,
+            )),
+            qtype => Err(Error::invalid_operation("ssr", qtype, Some(qtype::STRING))),
+        }
+    }
+
+    /// Convert a symbol atom to a string and a symbol list to a list of strings (q `string`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["ab", "c"]);
+    ///     assert_eq!(
+    ///         format!("{}", q_symbol_list.symbol_to_string().unwrap()),
+    ///         String::from("(\"ab\";,\"c\")")
+    ///     );
+    /// }
+    /// ```
+    pub fn symbol_to_string(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::SYMBOL_ATOM => Ok(K::new_string(
+                self.get_symbol()?.to_string(),
+                qattribute::NONE,
+            )),
+            qtype::SYMBOL_LIST => Ok(K::new_compound_list(
+                self.as_vec::<S>()?
+                    .iter()
+                    .map(|symbol| K::new_string(symbol.clone(), qattribute::NONE))
+                    .collect(),
+            )),
+            qtype => Err(Error::invalid_operation(
+                "symbol_to_string",
+                qtype,
+                Some(qtype::SYMBOL_LIST),
+            )),
+        }
+    }
+
+    /// Convert a string to a symbol atom and a list of strings to a symbol list (q `` `$ ``).
+    /// # Note
+    /// Error is returned if an element of a compound list is not a string.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_strings = k!([k!(string: "ab"), k!(string: "c")]);
+    ///     assert_eq!(format!("{}", q_strings.string_to_symbol().unwrap()), String::from("`ab`c"));
+    /// }
+    /// ```
+    pub fn string_to_symbol(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::STRING => Ok(K::new_symbol(self.as_string()?.to_string())),
+            qtype::COMPOUND_LIST => Ok(K::new_symbol_list(
+                self.as_vec::<K>()?
+                    .iter()
+                    .map(|element| match element.get_type() {
+                        qtype::STRING => Ok(element.as_string()?.to_string()),
+                        qtype => Err(Error::invalid_operation(
+                            "string_to_symbol",
+                            qtype,
+                            Some(qtype::STRING),
+                        )),
+                    })
+                    .collect::<Result<Vec<S>>>()?,
+                qattribute::NONE,
+            )),
+            qtype => Err(Error::invalid_operation(
+                "string_to_symbol",
+                qtype,
+                Some(qtype::STRING),
+            )),
+        }
+    }
+
+    // Temporal //-------------------------------/
+
+    /// Convert a datetime atom or list to a timestamp atom or list (q `` `timestamp$ ``). Nulls and
+    ///  infinities are kept.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::prelude::*;
+    ///
+    /// fn main() {
+    ///     let instant = Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 0).unwrap();
+    ///     let q_timestamp = K::new_datetime(instant).datetime_to_timestamp().unwrap();
+    ///     assert_eq!(q_timestamp.get_timestamp(), Ok(instant));
+    /// }
+    /// ```
+    pub fn datetime_to_timestamp(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::DATETIME_ATOM => Ok(K::new(
+                qtype::TIMESTAMP_ATOM,
+                qattribute::NONE,
+                k0_inner::long(q_datetime_to_q_timestamp(self.get_float()?)),
+            )),
+            qtype::DATETIME_LIST => Ok(K::new(
+                qtype::TIMESTAMP_LIST,
+                self.get_attribute(),
+                k0_inner::list(k0_list::new(
+                    self.as_vec::<F>()?
+                        .iter()
+                        .map(|&days| q_datetime_to_q_timestamp(days))
+                        .collect::<Vec<J>>(),
+                )),
+            )),
+            qtype => Err(Error::invalid_operation(
+                "datetime_to_timestamp",
+                qtype,
+                Some(qtype::DATETIME_LIST),
+            )),
+        }
+    }
+
+    /// Convert a timestamp atom or list to a datetime atom or list (q `` `datetime$ ``). Nulls and
+    ///  infinities are kept.
+    /// # Note
+    /// Datetime is a float of days and holds about millisecond precision for recent dates, so
+    ///  nanoseconds of the timestamp are lost and converting back does not restore them.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::prelude::*;
+    ///
+    /// fn main() {
+    ///     let instant = Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 0).unwrap();
+    ///     let q_datetime = K::new_timestamp(instant).timestamp_to_datetime().unwrap();
+    ///     assert_eq!(format!("{}", q_datetime), String::from("2024.03.15T10:30:00.000"));
+    /// }
+    /// ```
+    pub fn timestamp_to_datetime(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::TIMESTAMP_ATOM => Ok(K::new(
+                qtype::DATETIME_ATOM,
+                qattribute::NONE,
+                k0_inner::float(q_timestamp_to_q_datetime(self.get_long()?)),
+            )),
+            qtype::TIMESTAMP_LIST => Ok(K::new(
+                qtype::DATETIME_LIST,
+                self.get_attribute(),
+                k0_inner::list(k0_list::new(
+                    self.as_vec::<J>()?
+                        .iter()
+                        .map(|&nanos| q_timestamp_to_q_datetime(nanos))
+                        .collect::<Vec<F>>(),
+                )),
+            )),
+            qtype => Err(Error::invalid_operation(
+                "timestamp_to_datetime",
+                qtype,
+                Some(qtype::TIMESTAMP_LIST),
+            )),
+        }
+    }
+
+    // Dictionary //-----------------------------/
+
+    /// Construct a dictionary from parallel key and value lists, keeping only the first occurrence of
+    ///  each key together with its value. Keys and values are used as they are if keys are unique.
+    /// # Note
+    /// Error is returned if the lengths of keys and values differ.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let keys = k!(sym: vec!["a", "b", "a"]);
+    ///     let values = k!(long: vec![1, 2, 3]);
+    ///     let q_dictionary = K::dict_dedup(keys, values).unwrap();
+    ///     assert_eq!(format!("{}", q_dictionary), String::from("`a`b!1 2"));
+    /// }
+    /// ```
+    pub fn dict_dedup(keys: K, values: K) -> Result<K> {
+        if keys.len() != values.len() {
+            return Err(Error::length_mismatch(keys.len(), values.len()));
+        }
+        let groups = group_indices("dict_dedup", &keys)?;
+        if groups.len() == keys.len() {
+            return K::new_dictionary(keys, values);
+        }
+        let firsts = groups
+            .iter()
+            .map(|indices| indices[0])
+            .collect::<Vec<usize>>();
+        K::new_dictionary(take(&keys, &firsts)?, take(&values, &firsts)?)
+    }
+
+    /// Values of a dictionary (q `value`). For a keyed table this is the table of the value
+    ///  columns.
+    /// # Note
+    /// Error is returned if `self` is not a dictionary or a keyed table.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_dictionary = k!(dict: k!(sym: vec!["a", "b"]) => k!(long: vec![1, 2]));
+    ///     assert_eq!(format!("{}", q_dictionary.value().unwrap()), String::from("1 2"));
+    /// }
+    /// ```
+    pub fn value(&self) -> Result<K> {
+        match self.get_type() {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => Ok(self.as_vec::<K>()?[1].clone()),
+            _ => Err(Error::invalid_operation(
+                "value",
+                self.get_type(),
+                Some(qtype::DICTIONARY),
+            )),
+        }
+    }
+
+    // Table //----------------------------------/
+
+    /// Build a one-row table from a symbol-keyed dictionary (q `enlist` of a dictionary). Each value
+    ///  becomes a column holding that single value; an atom becomes a one-element list of the same
+    ///  type and any other object a one-element compound list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_dictionary = k!(dict: k!(sym: vec!["a", "b"]) => k!([k!(long: 1), k!(sym: "x")]));
+    ///     assert_eq!(
+    ///         format!("{}", q_dictionary.row_to_table().unwrap()),
+    ///         String::from("+`a`b!(,1;,`x)")
+    ///     );
+    /// }
+    /// ```
+    pub fn row_to_table(&self) -> Result<K> {
+        if self.get_type() != qtype::DICTIONARY {
+            return Err(Error::invalid_operation(
+                "row_to_table",
+                self.get_type(),
+                Some(qtype::DICTIONARY),
+            ));
+        }
+        let keys_values = self.as_vec::<K>()?;
+        let (keys, values) = (&keys_values[0], &keys_values[1]);
+        if keys.get_type() != qtype::SYMBOL_LIST {
+            return Err(Error::invalid_operation(
+                "row_to_table",
+                keys.get_type(),
+                Some(qtype::SYMBOL_LIST),
+            ));
+        }
+        let columns = (0..values.len())
+            .map(|index| element_at(values, index).map(|value| enlist(&value)))
+            .collect::<Result<Vec<K>>>()?;
+        new_table(keys.as_vec::<S>()?.clone(), columns)
+    }
+
+    /// Column names of a table or a keyed table (q `cols`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b"]),
+    ///         "price" => k!(float: vec![1.0, 2.0])
+    ///     });
+    ///     assert_eq!(table.cols().unwrap(), vec!["sym", "price"]);
+    /// }
+    /// ```
+    pub fn cols(&self) -> Result<Vec<S>> {
+        match self.get_type() {
+            qtype::TABLE => Ok(table_parts("cols", self)?.0.clone()),
+            qtype::DICTIONARY => {
+                let key_value = self.as_vec::<K>()?;
+                if key_value[0].get_type() == qtype::TABLE {
+                    // Keyed table
+                    let mut names = key_value[0].cols()?;
+                    names.extend(key_value[1].cols()?);
+                    Ok(names)
+                } else {
+                    Err(Error::invalid_operation("cols", self.get_type(), None))
+                }
+            }
+            _ => Err(Error::invalid_operation(
+                "cols",
+                self.get_type(),
+                Some(qtype::TABLE),
+            )),
+        }
+    }
+
+    /// Permute columns of a table into the specified order (q `xcols` with all columns).
+    /// # Note
+    /// `order` must contain each existing column exactly once. An unknown or missing column is reported
+    ///  as `Error::NoSuchColumn` and a repeated column as `Error::DuplicateColumn`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let mut table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b"]),
+    ///         "price" => k!(float: vec![1.5, 2.5])
+    ///     });
+    ///     table.reorder_columns(&["price", "sym"]).unwrap();
+    ///     assert_eq!(
+    ///         format!("{}", table),
+    ///         String::from("+`price`sym!(1.5 2.5;`a`b)")
+    ///     );
+    /// }
+    /// ```
+    pub fn reorder_columns(&mut self, order: &[&str]) -> Result<()> {
+        let (names, _) = table_parts("reorder_columns", self)?;
+        let mut permutation = Vec::with_capacity(order.len());
+        for name in order {
+            match names.iter().position(|existing| existing == name) {
+                Some(index) if permutation.contains(&index) => {
+                    return Err(Error::duplicate_column(name.to_string()))
+                }
+                Some(index) => permutation.push(index),
+                None => return Err(Error::no_such_column(name.to_string())),
+            }
+        }
+        if let Some(missing) = names.iter().find(|name| !order.contains(&name.as_str())) {
+            return Err(Error::no_such_column(missing.clone()));
+        }
+
+        let dictionary = self.get_mut_dictionary()?.as_mut_vec::<K>()?;
+        let names = std::mem::take(dictionary[0].as_mut_vec::<S>()?);
+        let columns = std::mem::take(dictionary[1].as_mut_vec::<K>()?);
+        let mut names = names.into_iter().map(Some).collect::<Vec<_>>();
+        let mut columns = columns.into_iter().map(Some).collect::<Vec<_>>();
+        *dictionary[0].as_mut_vec::<S>()? = permutation
+            .iter()
+            .filter_map(|&index| names[index].take())
+            .collect();
+        *dictionary[1].as_mut_vec::<K>()? = permutation
+            .iter()
+            .filter_map(|&index| columns[index].take())
+            .collect();
+        Ok(())
+    }
+
+    /// Project a table onto the specified columns in the given order (q `select a,b from table`).
+    /// # Note
+    /// An unknown column is reported as `Error::NoSuchColumn` and a repeated column as
+    ///  `Error::DuplicateColumn`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b"]),
+    ///         "price" => k!(float: vec![1.5, 2.5]),
+    ///         "size" => k!(long: vec![100, 200])
+    ///     });
+    ///     assert_eq!(
+    ///         format!("{}", table.select_columns(&["size", "sym"]).unwrap()),
+    ///         String::from("+`size`sym!(100 200;`a`b)")
+    ///     );
+    /// }
+    /// ```
+    pub fn select_columns(&self, columns: &[&str]) -> Result<K> {
+        let (names, all_columns) = table_parts("select_columns", self)?;
+        let mut selected = Vec::with_capacity(columns.len());
+        for (index, name) in columns.iter().enumerate() {
+            if columns[..index].contains(name) {
+                return Err(Error::duplicate_column(name.to_string()));
+            }
+            match names.iter().position(|existing| existing == name) {
+                Some(position) => selected.push(all_columns[position].clone()),
+                None => return Err(Error::no_such_column(name.to_string())),
+            }
+        }
+        new_table(
+            columns.iter().map(|name| name.to_string()).collect(),
+            selected,
+        )
+    }
+
+    /// Expand a table whose columns hold a list per row into one row per nested element (q `ungroup`).
+    ///  Columns holding an atom per row are repeated. A keyed table is unkeyed first.
+    /// # Note
+    /// Nested columns must have the same length within each row; otherwise `Error::LengthMismatch` is returned.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b"]),
+    ///         "size" => k!([k!(long: vec![1, 2]), k!(long: vec![3])])
+    ///     });
+    ///     assert_eq!(
+    ///         format!("{}", table.ungroup().unwrap()),
+    ///         String::from("+`sym`size!(`a`a`b;1 2 3)")
+    ///     );
+    /// }
+    /// ```
+    pub fn ungroup(&self) -> Result<K> {
+        if self.get_type() == qtype::DICTIONARY {
+            // Keyed table
+            return self.clone().unkey()?.ungroup();
+        }
+        let (names, columns) = table_parts("ungroup", self)?;
+        let nested = columns
+            .iter()
+            .map(|column| {
+                column.get_type() == qtype::COMPOUND_LIST
+                    && column
+                        .as_vec::<K>()
+                        .map(|rows| rows.iter().all(is_list))
+                        .unwrap_or(false)
+            })
+            .collect::<Vec<bool>>();
+
+        // Number of elements of each row
+        let mut counts = vec![None; self.len()];
+        for (column, _) in columns.iter().zip(&nested).filter(|(_, nested)| **nested) {
+            for (count, row) in counts.iter_mut().zip(column.as_vec::<K>()?) {
+                match *count {
+                    Some(length) if length != row.len() => {
+                        return Err(Error::length_mismatch(length, row.len()))
+                    }
+                    _ => *count = Some(row.len()),
+                }
+            }
+        }
+        let repeated = counts
+            .iter()
+            .enumerate()
+            .flat_map(|(row, count)| std::iter::repeat_n(row, count.unwrap_or(1)))
+            .collect::<Vec<usize>>();
+
+        let columns = columns
+            .iter()
+            .zip(&nested)
+            .map(|(column, nested)| match nested {
+                true => raze_lists("ungroup", column.as_vec::<K>()?),
+                false => take(column, &repeated),
+            })
+            .collect::<Result<Vec<K>>>()?;
+        new_table(names.clone(), columns)
+    }
+
+    /// Remove rows of a table where the given column is null (q `select from table where not null column`).
+    /// # Note
+    /// The column must hold a nullable type (see [`null_mask`](#method.null_mask)); otherwise
+    ///  `Error::InvalidOperation` is returned.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b", "c"]),
+    ///         "size" => k!(long: vec![1, qnull_base::J, 3])
+    ///     });
+    ///     assert_eq!(
+    ///         format!("{}", table.drop_null_rows("size").unwrap()),
+    ///         String::from("+`sym`size!(`a`c;1 3)")
+    ///     );
+    /// }
+    /// ```
+    pub fn drop_null_rows(&self, column: &str) -> Result<K> {
+        let (names, columns) = table_parts("drop_null_rows", self)?;
+        let kept = self
+            .get_column(column)?
+            .null_mask()?
+            .which(|null| !null.get_bool().unwrap_or(true))?;
+        let columns = columns
+            .iter()
+            .map(|column| column.gather(&kept))
+            .collect::<Result<Vec<K>>>()?;
+        new_table(names.clone(), columns)
+    }
+
+    /// Remove duplicate rows of a table keeping the first occurrence of each row (q `distinct` of
+    ///  a table). Rows are compared by value across all columns.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b", "a"]),
+    ///         "size" => k!(long: vec![1, 2, 1])
+    ///     });
+    ///     assert_eq!(
+    ///         format!("{}", table.distinct_rows().unwrap()),
+    ///         String::from("+`sym`size!(`a`b;1 2)")
+    ///     );
+    /// }
+    /// ```
+    pub fn distinct_rows(&self) -> Result<K> {
+        let (names, columns) = table_parts("distinct_rows", self)?;
+        let column_keys = columns
+            .iter()
+            .map(|column| element_keys("distinct_rows", column))
+            .collect::<Result<Vec<Vec<ElementKey>>>>()?;
+        let rows = column_keys.first().map_or(0, |keys| keys.len());
+        let mut seen = HashSet::new();
+        let kept = (0..rows)
+            .filter(|&row| {
+                seen.insert(
+                    column_keys
+                        .iter()
+                        .map(|keys| keys[row].clone())
+                        .collect::<Vec<ElementKey>>(),
+                )
+            })
+            .collect::<Vec<usize>>();
+        let columns = columns
+            .iter()
+            .map(|column| take(column, &kept))
+            .collect::<Result<Vec<K>>>()?;
+        new_table(names.clone(), columns)
+    }
+
+    // Apply //----------------------------------/
+
+    /// Evaluate a primitive function received from q on arguments without a round-trip to q. Only a
+    ///  small subset of primitives is supported:
+    /// - binary `+`, `-`, `*` and `%` with two arguments
+    /// - unary `neg` with one argument
+    ///
+    /// Arguments must be short, int, long, real or float atoms or lists. An atom is paired with each
+    ///  element of a list and lists must have the same length. As in q, the result has the wider type
+    ///  of the arguments but at least int (e.g., short plus short gives int), `neg` keeps the type
+    ///  of its argument, `%` always returns float and null in either argument gives null.
+    /// # Note
+    /// Error is returned for any other function, number of arguments or argument type.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     // `+` received from q
+    ///     let plus = K::q_ipc_decode(&[102, 1], 1).unwrap();
+    ///     let sum = plus.apply(&[k!(long: vec![1, 2]), k!(float: 0.5)]).unwrap();
+    ///     assert_eq!(format!("{}", sum), String::from("1.5 2.5"));
+    /// }
+    /// ```
+    pub fn apply(&self, args: &[K]) -> Result<K> {
+        let primitive = match (self.get_type(), args.len()) {
+            (qtype::UNARY_PRIMITIVE, 1) => match self.unary_primitive_id()? {
+                2 => Arithmetic::Neg,
+                _ => return Err(Error::invalid_operation("apply", self.get_type(), None)),
+            },
+            (qtype::BINARY_PRIMITIVE, 2) => match primitive_id(self)? {
+                1 => Arithmetic::Add,
+                2 => Arithmetic::Subtract,
+                3 => Arithmetic::Multiply,
+                4 => Arithmetic::Divide,
+                _ => return Err(Error::invalid_operation("apply", self.get_type(), None)),
+            },
+            _ => return Err(Error::invalid_operation("apply", self.get_type(), None)),
+        };
+        match primitive {
+            Arithmetic::Neg => arithmetic(primitive, &args[0], &K::new_long(0)),
+            _ => arithmetic(primitive, &args[0], &args[1]),
+        }
+    }
+
+    // Join //-----------------------------------/
+
+    /// Inner join of two tables on a shared column. Each row of this table is paired with every row
+    ///  of `other` having an equal value in the column `on`. The result has the columns of this table
+    ///  followed by the columns of `other` except `on`.
+    /// # Note
+    /// - Types of the `on` columns must match.
+    /// - A column other than `on` existing in both tables is rejected with `Error::DuplicateColumn`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let trades = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b", "a"]),
+    ///         "price" => k!(float: vec![1.0, 2.0, 3.0])
+    ///     });
+    ///     let reference = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "c"]),
+    ///         "name" => k!(sym: vec!["alpha", "gamma"])
+    ///     });
+    ///     assert_eq!(
+    ///         format!("{}", trades.inner_join(&reference, "sym").unwrap()),
+    ///         String::from("+`sym`price`name!(`a`a;1 3;`alpha`alpha)")
+    ///     );
+    /// }
+    /// ```
+    pub fn inner_join(&self, other: &K, on: &str) -> Result<K> {
+        let (left_names, left_columns) = table_parts("inner_join", self)?;
+        let (right_names, right_columns) = table_parts("inner_join", other)?;
+        let left_key = self.get_column(on)?;
+        let right_key = other.get_column(on)?;
+        if left_key.get_type() != right_key.get_type() {
+            return Err(Error::invalid_operation(
+                "inner_join",
+                right_key.get_type(),
+                Some(left_key.get_type()),
+            ));
+        }
+        if let Some(duplicate) = right_names
+            .iter()
+            .find(|name| name.as_str() != on && left_names.contains(name))
+        {
+            return Err(Error::duplicate_column(duplicate.clone()));
+        }
+
+        // Index rows of the right table by key
+        let mut right_rows: HashMap<ElementKey, Vec<usize>> = HashMap::new();
+        for (index, key) in element_keys("inner_join", right_key)?
+            .into_iter()
+            .enumerate()
+        {
+            right_rows.entry(key).or_default().push(index);
+        }
+        let mut left_indices = Vec::new();
+        let mut right_indices = Vec::new();
+        for (index, key) in element_keys("inner_join", left_key)?
+            .into_iter()
+            .enumerate()
+        {
+            if let Some(matched) = right_rows.get(&key) {
+                left_indices.extend(std::iter::repeat_n(index, matched.len()));
+                right_indices.extend(matched);
+            }
+        }
+
+        let mut names = left_names.clone();
+        let mut columns = left_columns
+            .iter()
+            .map(|column| take(column, &left_indices))
+            .collect::<Result<Vec<K>>>()?;
+        for (name, column) in right_names.iter().zip(right_columns.iter()) {
+            if name != on {
+                names.push(name.clone());
+                columns.push(take(column, &right_indices)?);
+            }
+        }
+        new_table(names, columns)
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Convert a numeric list into a vector of `f64` where null is mapped to `None`.
+fn numeric_values(operator: &'static str, list: &K) -> Result<Vec<Option<f64>>> {
+    match list.get_type() {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => Ok(list
+            .as_vec::<G>()?
+            .iter()
+            .map(|&value| Some(value as f64))
+            .collect()),
+        qtype::SHORT_LIST => Ok(list
+            .as_vec::<H>()?
+            .iter()
+            .map(|&value| (value != qnull_base::H).then_some(value as f64))
+            .collect()),
+        qtype::INT_LIST => Ok(list
+            .as_vec::<I>()?
+            .iter()
+            .map(|&value| (value != qnull_base::I).then_some(value as f64))
+            .collect()),
+        qtype::LONG_LIST => Ok(list
+            .as_vec::<J>()?
+            .iter()
+            .map(|&value| (value != qnull_base::J).then_some(value as f64))
+            .collect()),
+        qtype::REAL_LIST => Ok(list
+            .as_vec::<E>()?
+            .iter()
+            .map(|&value| (!value.is_nan()).then_some(value as f64))
+            .collect()),
+        qtype::FLOAT_LIST => Ok(list
+            .as_vec::<F>()?
+            .iter()
+            .map(|&value| (!value.is_nan()).then_some(value))
+            .collect()),
+        _ => Err(Error::invalid_operation(
+            operator,
+            list.get_type(),
+            Some(qtype::FLOAT_LIST),
+        )),
+    }
+}
+
+/// Hashable representation of a list element used to compare elements across lists.
+/// # Note
+/// Floats are compared by bit pattern with all `NaN` treated as the same null. Elements of a compound
+///  list are compared by their serialized bytes.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum ElementKey {
+    Integer(i64),
+    Float(u64),
+    Guid([u8; 16]),
+    Char(char),
+    Symbol(String),
+    Object(Vec<u8>),
+}
+
+/// Convert a float into a bit pattern, treating all `NaN` as the same null.
+fn float_bits(value: f64) -> u64 {
+    if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+/// Build comparable keys of all elements of a list.
+fn element_keys(operator: &'static str, list: &K) -> Result<Vec<ElementKey>> {
+    match list.get_type() {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => Ok(list
+            .as_vec::<G>()?
+            .iter()
+            .map(|&v| ElementKey::Integer(v as i64))
+            .collect()),
+        qtype::GUID_LIST => Ok(list
+            .as_vec::<U>()?
+            .iter()
+            .map(|&v| ElementKey::Guid(v))
+            .collect()),
+        qtype::SHORT_LIST => Ok(list
+            .as_vec::<H>()?
+            .iter()
+            .map(|&v| ElementKey::Integer(v as i64))
+            .collect()),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => Ok(list
+            .as_vec::<I>()?
+            .iter()
+            .map(|&v| ElementKey::Integer(v as i64))
+            .collect()),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => Ok(list
+            .as_vec::<J>()?
+            .iter()
+            .map(|&v| ElementKey::Integer(v))
+            .collect()),
+        qtype::REAL_LIST => Ok(list
+            .as_vec::<E>()?
+            .iter()
+            .map(|&v| ElementKey::Float(float_bits(v as f64)))
+            .collect()),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => Ok(list
+            .as_vec::<F>()?
+            .iter()
+            .map(|&v| ElementKey::Float(float_bits(v)))
+            .collect()),
+        qtype::STRING => Ok(list.as_string()?.chars().map(ElementKey::Char).collect()),
+        qtype::SYMBOL_LIST => Ok(list
+            .as_vec::<S>()?
+            .iter()
+            .map(|v| ElementKey::Symbol(v.clone()))
+            .collect()),
+        qtype::COMPOUND_LIST => Ok(list
+            .as_vec::<K>()?
+            .iter()
+            .map(|v| ElementKey::Object(v.q_ipc_encode()))
+            .collect()),
+        _ => Err(Error::invalid_operation(operator, list.get_type(), None)),
+    }
+}
+
+/// Flag elements of a list which are also in another list of the same type.
+fn membership(operator: &'static str, list: &K, other: &K) -> Result<Vec<bool>> {
+    if other.get_type() != list.get_type() {
+        return Err(Error::invalid_operation(
+            operator,
+            other.get_type(),
+            Some(list.get_type()),
+        ));
+    }
+    let others = element_keys(operator, other)?
+        .into_iter()
+        .collect::<HashSet<ElementKey>>();
+    Ok(element_keys(operator, list)?
+        .iter()
+        .map(|key| others.contains(key))
+        .collect())
+}
+
+/// Indices which sort a list, keeping equal elements in their order. Nulls are the least elements.
+fn sort_order(operator: &'static str, list: &K, descending: bool) -> Result<Vec<usize>> {
+    let compare: Box<dyn Fn(usize, usize) -> Ordering + '_> = match list.get_type() {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => {
+            let values = list.as_vec::<G>()?;
+            Box::new(move |left, right| values[left].cmp(&values[right]))
+        }
+        qtype::SHORT_LIST => {
+            let values = list.as_vec::<H>()?;
+            Box::new(move |left, right| values[left].cmp(&values[right]))
+        }
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => {
+            let values = list.as_vec::<I>()?;
+            Box::new(move |left, right| values[left].cmp(&values[right]))
+        }
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+            let values = list.as_vec::<J>()?;
+            Box::new(move |left, right| values[left].cmp(&values[right]))
+        }
+        qtype::REAL_LIST => {
+            let values = list.as_vec::<E>()?;
+            Box::new(move |left, right| float_cmp(values[left] as F, values[right] as F))
+        }
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+            let values = list.as_vec::<F>()?;
+            Box::new(move |left, right| float_cmp(values[left], values[right]))
+        }
+        qtype::SYMBOL_LIST => {
+            let values = list.as_vec::<S>()?;
+            Box::new(move |left, right| values[left].cmp(&values[right]))
+        }
+        _ => return Err(Error::invalid_operation(operator, list.get_type(), None)),
+    };
+    let mut indices = (0..list.len()).collect::<Vec<usize>>();
+    match descending {
+        true => indices.sort_by(|&left, &right| compare(right, left)),
+        false => indices.sort_by(|&left, &right| compare(left, right)),
+    }
+    Ok(indices)
+}
+
+/// Group indices of equal elements of a list in order of first occurrence.
+fn group_indices(operator: &'static str, list: &K) -> Result<Vec<Vec<usize>>> {
+    let mut positions: HashMap<ElementKey, usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (index, key) in element_keys(operator, list)?.into_iter().enumerate() {
+        match positions.entry(key) {
+            Entry::Occupied(position) => groups[*position.get()].push(index),
+            Entry::Vacant(position) => {
+                position.insert(groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Get column names and columns of an unkeyed table.
+fn table_parts<'a>(operator: &'static str, table: &'a K) -> Result<(&'a Vec<S>, &'a Vec<K>)> {
+    match table.get_type() {
+        qtype::TABLE => {
+            let dictionary = table.get_dictionary()?.as_vec::<K>()?;
+            Ok((dictionary[0].as_vec::<S>()?, dictionary[1].as_vec::<K>()?))
+        }
+        _ => Err(Error::invalid_operation(
+            operator,
+            table.get_type(),
+            Some(qtype::TABLE),
+        )),
+    }
+}
+
+/// Build a table from column names and columns.
+fn new_table(names: Vec<S>, columns: Vec<K>) -> Result<K> {
+    K::new_dictionary(
+        K::new_symbol_list(names, qattribute::NONE),
+        K::new_compound_list(columns),
+    )?
+    .flip()
+}
+
+/// Wrap an object into a one-element list (q `enlist`). An atom becomes a typed list and any other
+///  object a compound list.
+fn enlist(object: &K) -> K {
+    let qtype = object.get_type();
+    let inner = match (qtype, &object.0.value) {
+        (qtype::BOOL_ATOM | qtype::BYTE_ATOM, k0_inner::byte(byte)) => {
+            k0_inner::list(k0_list::new(vec![*byte]))
+        }
+        (qtype::CHAR, k0_inner::byte(character)) => {
+            k0_inner::symbol((*character as char).to_string())
+        }
+        (qtype::GUID_ATOM, k0_inner::guid(guid)) => k0_inner::list(k0_list::new(vec![*guid])),
+        (qtype::SHORT_ATOM, k0_inner::short(short)) => k0_inner::list(k0_list::new(vec![*short])),
+        (
+            qtype::INT_ATOM
+            | qtype::MONTH_ATOM
+            | qtype::DATE_ATOM
+            | qtype::MINUTE_ATOM
+            | qtype::SECOND_ATOM
+            | qtype::TIME_ATOM,
+            k0_inner::int(int),
+        ) => k0_inner::list(k0_list::new(vec![*int])),
+        (qtype::LONG_ATOM | qtype::TIMESTAMP_ATOM | qtype::TIMESPAN_ATOM, k0_inner::long(long)) => {
+            k0_inner::list(k0_list::new(vec![*long]))
+        }
+        (qtype::REAL_ATOM, k0_inner::real(real)) => k0_inner::list(k0_list::new(vec![*real])),
+        (qtype::FLOAT_ATOM | qtype::DATETIME_ATOM, k0_inner::float(float)) => {
+            k0_inner::list(k0_list::new(vec![*float]))
+        }
+        (qtype::SYMBOL_ATOM, k0_inner::symbol(symbol)) => {
+            k0_inner::list(k0_list::new(vec![symbol.clone()]))
+        }
+        _ => return K::new_compound_list(vec![object.clone()]),
+    };
+    K::new(-qtype, qattribute::NONE, inner)
+}
+
+/// Build a list of the same type from elements at the given indices (q `@` with an index list).
+///  Attribute is not carried over.
+fn take(list: &K, indices: &[usize]) -> Result<K> {
+    fn pick<T: Clone>(elements: &[T], indices: &[usize]) -> Result<Vec<T>> {
+        indices
+            .iter()
+            .map(|&index| {
+                elements
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| Error::index_out_of_bounds(elements.len(), index))
+            })
+            .collect()
+    }
+
+    let qtype = list.get_type();
+    let inner = match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => {
+            k0_inner::list(k0_list::new(pick(list.as_vec::<G>()?, indices)?))
+        }
+        qtype::GUID_LIST => k0_inner::list(k0_list::new(pick(list.as_vec::<U>()?, indices)?)),
+        qtype::SHORT_LIST => k0_inner::list(k0_list::new(pick(list.as_vec::<H>()?, indices)?)),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => k0_inner::list(k0_list::new(pick(list.as_vec::<I>()?, indices)?)),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+            k0_inner::list(k0_list::new(pick(list.as_vec::<J>()?, indices)?))
+        }
+        qtype::REAL_LIST => k0_inner::list(k0_list::new(pick(list.as_vec::<E>()?, indices)?)),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+            k0_inner::list(k0_list::new(pick(list.as_vec::<F>()?, indices)?))
+        }
+        qtype::STRING => k0_inner::symbol(
+            pick(&list.as_string()?.chars().collect::<Vec<char>>(), indices)?
+                .into_iter()
+                .collect(),
+        ),
+        qtype::SYMBOL_LIST => k0_inner::list(k0_list::new(pick(list.as_vec::<S>()?, indices)?)),
+        qtype::COMPOUND_LIST => k0_inner::list(k0_list::new(pick(list.as_vec::<K>()?, indices)?)),
+        _ => return Err(Error::invalid_operation("take", qtype, None)),
+    };
+    Ok(K::new(qtype, qattribute::NONE, inner))
+}
+
+/// Copy values replacing each null with the last preceding non-null value.
+fn fill_forward<T: Clone>(values: &[T], is_null: impl Fn(&T) -> bool) -> Vec<T> {
+    let mut last: Option<&T> = None;
+    values
+        .iter()
+        .map(|value| match (is_null(value), last) {
+            (true, Some(previous)) => previous.clone(),
+            (true, None) => value.clone(),
+            (false, _) => {
+                last = Some(value);
+                value.clone()
+            }
+        })
+        .collect()
+}
+
+/// Apply a text transformation to a symbol atom, each symbol of a symbol list or a string. The
+///  attribute is dropped since the transformation may break it.
+fn map_text(operator: &'static str, object: &K, f: impl Fn(&str) -> String) -> Result<K> {
+    match object.get_type() {
+        qtype::SYMBOL_ATOM => Ok(K::new_symbol(f(object.get_symbol()?))),
+        qtype::SYMBOL_LIST => Ok(K::new_symbol_list(
+            object
+                .as_vec::<S>()?
+                .iter()
+                .map(|symbol| f(symbol))
+                .collect(),
+            qattribute::NONE,
+        )),
+        qtype::STRING => Ok(K::new_string(f(object.as_string()?), qattribute::NONE)),
+        qtype => Err(Error::invalid_operation(
+            operator,
+            qtype,
+            Some(qtype::SYMBOL_LIST),
+        )),
+    }
+}
+
+/// Flag null elements of a list of a nullable type.
+fn null_flags(operator: &'static str, list: &K) -> Result<Vec<bool>> {
+    fn flags<T: 'static>(list: &K, is_null: impl Fn(&T) -> bool) -> Result<Vec<bool>> {
+        Ok(list.as_vec::<T>()?.iter().map(is_null).collect())
+    }
+
+    match list.get_type() {
+        qtype::GUID_LIST => flags::<U>(list, |&value| value == qnull_base::U),
+        qtype::SHORT_LIST => flags::<H>(list, |&value| value == qnull_base::H),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => flags::<I>(list, |&value| value == qnull_base::I),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+            flags::<J>(list, |&value| value == qnull_base::J)
+        }
+        qtype::REAL_LIST => flags::<E>(list, |value| value.is_nan()),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => flags::<F>(list, |value| value.is_nan()),
+        qtype::STRING => Ok(list
+            .as_string()?
+            .chars()
+            .map(|c| c == qnull_base::C)
+            .collect()),
+        qtype::SYMBOL_LIST => flags::<S>(list, |value| value.is_empty()),
+        qtype => Err(Error::invalid_operation(operator, qtype, None)),
+    }
+}
+
+/// Extract values of an int or long list used as indices.
+fn index_values(operator: &'static str, indices: &K) -> Result<Vec<i64>> {
+    match indices.get_type() {
+        qtype::INT_LIST => Ok(indices
+            .as_vec::<I>()?
+            .iter()
+            .map(|&index| index as i64)
+            .collect()),
+        qtype::LONG_LIST => Ok(indices.as_vec::<J>()?.clone()),
+        _ => Err(Error::invalid_operation(
+            operator,
+            indices.get_type(),
+            Some(qtype::LONG_LIST),
+        )),
+    }
+}
+
+/// Extract an element of a list as an atom. An element of a compound list is returned as is.
+fn element_at(list: &K, index: usize) -> Result<K> {
+    fn get<T: Clone>(elements: &[T], index: usize) -> Result<T> {
+        elements
+            .get(index)
+            .cloned()
+            .ok_or_else(|| Error::index_out_of_bounds(elements.len(), index))
+    }
+
+    let qtype = list.get_type();
+    let inner = match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => k0_inner::byte(get(list.as_vec::<G>()?, index)?),
+        qtype::GUID_LIST => k0_inner::guid(get(list.as_vec::<U>()?, index)?),
+        qtype::SHORT_LIST => k0_inner::short(get(list.as_vec::<H>()?, index)?),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => k0_inner::int(get(list.as_vec::<I>()?, index)?),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+            k0_inner::long(get(list.as_vec::<J>()?, index)?)
+        }
+        qtype::REAL_LIST => k0_inner::real(get(list.as_vec::<E>()?, index)?),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+            k0_inner::float(get(list.as_vec::<F>()?, index)?)
+        }
+        qtype::STRING => {
+            let string = list.as_string()?;
+            let character = string
+                .chars()
+                .nth(index)
+                .ok_or_else(|| Error::index_out_of_bounds(string.chars().count(), index))?;
+            return Ok(K::new_char(character));
+        }
+        qtype::SYMBOL_LIST => k0_inner::symbol(get(list.as_vec::<S>()?, index)?),
+        qtype::COMPOUND_LIST => return get(list.as_vec::<K>()?, index),
+        _ => return Err(Error::invalid_operation("element_at", qtype, None)),
+    };
+    Ok(K::new(-qtype, qattribute::NONE, inner))
+}
+
+/// Check if an object is a list (including string and compound list).
+fn is_list(object: &K) -> bool {
+    (qtype::COMPOUND_LIST..=qtype::TIME_LIST).contains(&object.get_type())
+}
+
+/// Concatenate lists (q `raze`). Lists of a single type are joined into a list of that type,
+///  otherwise all elements are collected into a compound list.
+fn raze_lists(operator: &'static str, lists: &[K]) -> Result<K> {
+    fn concat<T: Clone + 'static>(lists: &[K]) -> Result<Vec<T>> {
+        let mut joined = Vec::new();
+        for list in lists {
+            joined.extend_from_slice(list.as_vec::<T>()?);
+        }
+        Ok(joined)
+    }
+
+    if let Some(object) = lists.iter().find(|object| !is_list(object)) {
+        return Err(Error::invalid_operation(
+            operator,
+            object.get_type(),
+            Some(qtype::COMPOUND_LIST),
+        ));
+    }
+    let qtype = match lists.first() {
+        Some(first) if lists.iter().all(|list| list.get_type() == first.get_type()) => {
+            first.get_type()
+        }
+        _ => qtype::COMPOUND_LIST,
+    };
+    let inner = match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => k0_inner::list(k0_list::new(concat::<G>(lists)?)),
+        qtype::GUID_LIST => k0_inner::list(k0_list::new(concat::<U>(lists)?)),
+        qtype::SHORT_LIST => k0_inner::list(k0_list::new(concat::<H>(lists)?)),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => k0_inner::list(k0_list::new(concat::<I>(lists)?)),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+            k0_inner::list(k0_list::new(concat::<J>(lists)?))
+        }
+        qtype::REAL_LIST => k0_inner::list(k0_list::new(concat::<E>(lists)?)),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+            k0_inner::list(k0_list::new(concat::<F>(lists)?))
+        }
+        qtype::STRING => k0_inner::symbol(
+            lists
+                .iter()
+                .map(|list| list.as_string())
+                .collect::<Result<String>>()?,
+        ),
+        qtype::SYMBOL_LIST => k0_inner::list(k0_list::new(concat::<S>(lists)?)),
+        _ => {
+            // Mixed types. Collect elements one by one.
+            let mut elements = Vec::new();
+            for list in lists {
+                for index in 0..list.len() {
+                    elements.push(element_at(list, index)?);
+                }
+            }
+            k0_inner::list(k0_list::new(elements))
+        }
+    };
+    Ok(K::new(qtype, qattribute::NONE, inner))
+}
+
+/// Arithmetic primitives supported by `apply`.
+#[derive(Clone, Copy)]
+enum Arithmetic {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    /// Unary negation. The second operand is ignored.
+    Neg,
+}
+
+/// Numeric values of an arithmetic operand. Integer nulls are `None` and float nulls are `NaN`.
+enum Numbers {
+    Integer(Vec<Option<i64>>),
+    Float(Vec<f64>),
+}
+
+impl Numbers {
+    fn integer(&self, index: usize) -> Option<i64> {
+        match self {
+            Numbers::Integer(values) => values[index],
+            Numbers::Float(_) => unreachable!("float operand of integer arithmetic"),
+        }
+    }
+
+    fn float(&self, index: usize) -> f64 {
+        match self {
+            Numbers::Integer(values) => values[index].map_or(f64::NAN, |value| value as f64),
+            Numbers::Float(values) => values[index],
+        }
+    }
+}
+
+/// Get the id of a binary primitive.
+fn primitive_id(primitive: &K) -> Result<u8> {
+    match &primitive.0.value {
+        k0_inner::opaque(payload) if payload.len() == 1 => Ok(payload[0]),
+        _ => Err(Error::DeserializationError(
+            "inconsistent K object for BINARY_PRIMITIVE".to_string(),
+        )),
+    }
+}
+
+/// Decompose an operand of arithmetic into its list type, whether it is an atom and its values.
+fn numbers(operator: &'static str, object: &K) -> Result<(i8, bool, Numbers)> {
+    let atom = object.get_type() < 0;
+    let qtype = object.get_type().abs();
+    let numbers = match (qtype, atom) {
+        (qtype::SHORT_LIST, true) => {
+            Numbers::Integer(vec![nullable(object.get_short()?, qnull_base::H)])
+        }
+        (qtype::INT_LIST, true) => {
+            Numbers::Integer(vec![nullable(object.get_int()?, qnull_base::I)])
+        }
+        (qtype::LONG_LIST, true) => {
+            Numbers::Integer(vec![nullable(object.get_long()?, qnull_base::J)])
+        }
+        (qtype::REAL_LIST, true) => Numbers::Float(vec![object.get_real()? as f64]),
+        (qtype::FLOAT_LIST, true) => Numbers::Float(vec![object.get_float()?]),
+        (qtype::SHORT_LIST, false) => Numbers::Integer(
+            object
+                .as_vec::<H>()?
+                .iter()
+                .map(|&value| nullable(value, qnull_base::H))
+                .collect(),
+        ),
+        (qtype::INT_LIST, false) => Numbers::Integer(
+            object
+                .as_vec::<I>()?
+                .iter()
+                .map(|&value| nullable(value, qnull_base::I))
+                .collect(),
+        ),
+        (qtype::LONG_LIST, false) => Numbers::Integer(
+            object
+                .as_vec::<J>()?
+                .iter()
+                .map(|&value| nullable(value, qnull_base::J))
+                .collect(),
+        ),
+        (qtype::REAL_LIST, false) => Numbers::Float(
+            object
+                .as_vec::<E>()?
+                .iter()
+                .map(|&value| value as f64)
+                .collect(),
+        ),
+        (qtype::FLOAT_LIST, false) => Numbers::Float(object.as_vec::<F>()?.clone()),
+        _ => {
+            return Err(Error::invalid_operation(
+                operator,
+                object.get_type(),
+                Some(qtype::FLOAT_LIST),
+            ))
+        }
+    };
+    Ok((qtype, atom, numbers))
+}
+
+/// Values of a numeric or temporal atom or list for ordering. Integer nulls are `None` and sort
+///  before any value. Returns the list type, whether the object is an atom and the values.
+fn ordinals(operator: &'static str, object: &K) -> Result<(i8, bool, Numbers)> {
+    let atom = object.get_type() < 0;
+    let enlisted;
+    let list = if atom {
+        enlisted = enlist(object);
+        &enlisted
+    } else {
+        object
+    };
+    let numbers = match list.get_type() {
+        qtype::SHORT_LIST => Numbers::Integer(
+            list.as_vec::<H>()?
+                .iter()
+                .map(|&value| nullable(value, qnull_base::H))
+                .collect(),
+        ),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => Numbers::Integer(
+            list.as_vec::<I>()?
+                .iter()
+                .map(|&value| nullable(value, qnull_base::I))
+                .collect(),
+        ),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => Numbers::Integer(
+            list.as_vec::<J>()?
+                .iter()
+                .map(|&value| nullable(value, qnull_base::J))
+                .collect(),
+        ),
+        qtype::REAL_LIST => Numbers::Float(
+            list.as_vec::<E>()?
+                .iter()
+                .map(|&value| value as f64)
+                .collect(),
+        ),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => Numbers::Float(list.as_vec::<F>()?.clone()),
+        _ => return Err(Error::invalid_operation(operator, object.get_type(), None)),
+    };
+    Ok((list.get_type(), atom, numbers))
+}
+
+/// Order of floats with null (`NaN`) sorting before any value as in q. Shared by sorting and
+///  searching so that both agree on where nulls go.
+fn float_cmp(x: f64, y: f64) -> Ordering {
+    match (x.is_nan(), y.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// `x <= y` for floats with null (`NaN`) sorting before any value.
+fn float_le(x: f64, y: f64) -> bool {
+    float_cmp(x, y) != Ordering::Greater
+}
+
+/// Map a null sentinel of an integer type to `None`.
+fn nullable<T: Into<i64> + PartialEq>(value: T, null: T) -> Option<i64> {
+    (value != null).then(|| value.into())
+}
+
+/// Apply an arithmetic primitive element-wise, pairing an atom with each element of a list.
+fn arithmetic(primitive: Arithmetic, left: &K, right: &K) -> Result<K> {
+    let (left_type, left_atom, left_values) = numbers("apply", left)?;
+    let (right_type, right_atom, right_values) = numbers("apply", right)?;
+    let length = match (left_atom, right_atom) {
+        (true, true) => 1,
+        (true, false) => right.len(),
+        (false, true) => left.len(),
+        (false, false) if left.len() == right.len() => left.len(),
+        (false, false) => return Err(Error::length_mismatch(left.len(), right.len())),
+    };
+    let left_index = |index: usize| if left_atom { 0 } else { index };
+    let right_index = |index: usize| if right_atom { 0 } else { index };
+    let qtype = match primitive {
+        Arithmetic::Divide => qtype::FLOAT_LIST,
+        Arithmetic::Neg => left_type,
+        // Shorts are promoted to int as in q
+        _ => left_type.max(right_type).max(qtype::INT_LIST),
+    };
+
+    let list = if qtype <= qtype::LONG_LIST {
+        let values = (0..length).map(|index| {
+            let x = left_values.integer(left_index(index));
+            let y = right_values.integer(right_index(index));
+            match (primitive, x, y) {
+                (Arithmetic::Neg, Some(x), _) => Some(x.wrapping_neg()),
+                (Arithmetic::Add, Some(x), Some(y)) => Some(x.wrapping_add(y)),
+                (Arithmetic::Subtract, Some(x), Some(y)) => Some(x.wrapping_sub(y)),
+                (Arithmetic::Multiply, Some(x), Some(y)) => Some(x.wrapping_mul(y)),
+                _ => None,
+            }
+        });
+        match qtype {
+            qtype::SHORT_LIST => k0_list::new(
+                values
+                    .map(|value| value.map_or(qnull_base::H, |value| value as H))
+                    .collect::<Vec<H>>(),
+            ),
+            qtype::INT_LIST => k0_list::new(
+                values
+                    .map(|value| value.map_or(qnull_base::I, |value| value as I))
+                    .collect::<Vec<I>>(),
+            ),
+            _ => k0_list::new(
+                values
+                    .map(|value| value.unwrap_or(qnull_base::J))
+                    .collect::<Vec<J>>(),
+            ),
+        }
+    } else {
+        let values = (0..length).map(|index| {
+            let x = left_values.float(left_index(index));
+            let y = right_values.float(right_index(index));
+            match primitive {
+                Arithmetic::Neg => -x,
+                Arithmetic::Add => x + y,
+                Arithmetic::Subtract => x - y,
+                Arithmetic::Multiply => x * y,
+                Arithmetic::Divide => x / y,
+            }
+        });
+        match qtype {
+            qtype::REAL_LIST => k0_list::new(values.map(|value| value as E).collect::<Vec<E>>()),
+            _ => k0_list::new(values.collect::<Vec<F>>()),
+        }
+    };
+    let result = K::new(qtype, qattribute::NONE, k0_inner::list(list));
+    match left_atom && right_atom {
+        true => element_at(&result, 0),
+        false => Ok(result),
+    }
+}
+
+/// Zip a pair of equal-length numeric lists into `f64` pairs, skipping pairs including null.
+fn numeric_pairs(operator: &'static str, values: &K, weights: &K) -> Result<Vec<(f64, f64)>> {
+    let values = numeric_values(operator, values)?;
+    let weights = numeric_values(operator, weights)?;
+    if values.len() != weights.len() {
+        return Err(Error::length_mismatch(values.len(), weights.len()));
+    }
+    Ok(values
+        .into_iter()
+        .zip(weights)
+        .filter_map(|(value, weight)| Some((value?, weight?)))
+        .collect())
+}
+
+/// Extract rows of a matrix represented as a compound list of equal-length numeric lists. Nulls
+///  are replaced with zero.
+fn matrix_rows(operator: &'static str, matrix: &K) -> Result<Vec<Vec<f64>>> {
+    if matrix.get_type() != qtype::COMPOUND_LIST {
+        return Err(Error::invalid_operation(
+            operator,
+            matrix.get_type(),
+            Some(qtype::COMPOUND_LIST),
+        ));
+    }
+    let rows = matrix
+        .as_vec::<K>()?
+        .iter()
+        .map(|row| {
+            numeric_values(operator, row).map(|values| {
+                values
+                    .into_iter()
+                    .map(|value| value.unwrap_or(0.0))
+                    .collect()
+            })
+        })
+        .collect::<Result<Vec<Vec<f64>>>>()?;
+    if let Some(first) = rows.first() {
+        if let Some(row) = rows.iter().find(|row| row.len() != first.len()) {
+            return Err(Error::length_mismatch(first.len(), row.len()));
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k;
+    use crate::qnull;
+
+    #[test]
+    fn test_wavg_wsum_vwap() {
+        let price = k!(float: vec![100.5, 101.0, 100.0, 102.0]);
+        let size = k!(long: vec![300, 100, 200, 400]);
+
+        assert_eq!(price.wsum(&size).unwrap(), 101_050.0);
+        assert_eq!(price.wavg(&size).unwrap(), 101.05);
+    }
+
+    #[test]
+    fn test_dot_mmu() {
+        let x = k!(float: vec![1.5, 2.0, -3.0]);
+        let y = k!(float: vec![2.0, 0.5, 4.0]);
+        assert_eq!(x.dot(&y).unwrap(), -8.0);
+        // Nulls count as zero
+        let z = k!(long: vec![2, qnull::LONG, 1]);
+        assert_eq!(x.dot(&z).unwrap(), 0.0);
+        assert!(matches!(
+            x.dot(&k!(float: vec![1.0, 2.0])),
+            Err(Error::LengthMismatch { .. })
+        ));
+        assert!(x.dot(&k!(sym: vec!["a", "b", "c"])).is_err());
+
+        // 2x3 by 3x2
+        let a = k!([k!(float: vec![1.0, 2.0, 3.0]), k!(long: vec![4, 5, 6])]);
+        let b = k!([
+            k!(float: vec![7.0, 8.0]),
+            k!(float: vec![9.0, 10.0]),
+            k!(float: vec![11.0, 12.0])
+        ]);
+        let product = a.mmu(&b).unwrap();
+        let rows = product.as_vec::<K>().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_vec::<F>().unwrap(), &vec![58.0, 64.0]);
+        assert_eq!(rows[1].as_vec::<F>().unwrap(), &vec![139.0, 154.0]);
+
+        assert!(matches!(a.mmu(&a), Err(Error::LengthMismatch { .. })));
+        let ragged = k!([k!(float: vec![1.0, 2.0]), k!(float: vec![3.0])]);
+        assert!(matches!(ragged.mmu(&b), Err(Error::LengthMismatch { .. })));
+        assert!(x.mmu(&y).is_err());
+    }
+
+    #[test]
+    fn test_mavg_sliding() {
+        let q_float_list = k!(float: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(
+            q_float_list.mavg(3).unwrap().as_vec::<F>().unwrap(),
+            &vec![1.0, 1.5, 2.0, 3.0, 4.0, 5.0]
+        );
+        assert_eq!(
+            q_float_list.mavg(10).unwrap().as_vec::<F>().unwrap(),
+            &vec![1.0, 1.5, 2.0, 2.5, 3.0, 3.5]
+        );
+
+        // Nulls are ignored and a window of only nulls is null
+        let q_long_list = k!(long: vec![qnull_base::J, qnull_base::J, 3, 6, qnull_base::J]);
+        let averages = q_long_list.mavg(2).unwrap();
+        assert_eq!(format!("{}", averages), String::from("0n 0n 3 4.5 6"));
+
+        let sums = k!(int: vec![1, 2, 3, 4])
+            .sliding(2, |values| values.iter().sum())
+            .unwrap();
+        assert_eq!(sums.as_vec::<F>().unwrap(), &vec![1.0, 3.0, 5.0, 7.0]);
+
+        assert_eq!(
+            q_float_list.mavg(0).unwrap_err(),
+            Error::invalid_operation("sliding", qtype::LONG_ATOM, None)
+        );
+        assert_eq!(
+            k!(sym: vec!["a"]).mavg(2).unwrap_err(),
+            Error::invalid_operation("sliding", qtype::SYMBOL_LIST, Some(qtype::FLOAT_LIST))
+        );
+    }
+
+    #[test]
+    fn test_wavg_wsum_ignore_null() {
+        let price = k!(float: vec![10.0, qnull::FLOAT, 12.0]);
+        let size = k!(int: vec![100, 200, qnull::INT]);
+
+        assert_eq!(price.wsum(&size).unwrap(), 1000.0);
+        assert_eq!(price.wavg(&size).unwrap(), 10.0);
+        assert!(k!(long: vec![qnull::LONG])
+            .wavg(&k!(long: vec![1]))
+            .unwrap()
+            .is_nan());
+    }
+
+    #[test]
+    fn test_wavg_wsum_error() {
+        let price = k!(float: vec![10.0, 11.0]);
+
+        assert!(matches!(
+            price.wavg(&k!(long: vec![1, 2, 3])),
+            Err(Error::LengthMismatch { .. })
+        ));
+        assert!(matches!(
+            price.wsum(&k!(sym: vec!["a", "b"])),
+            Err(Error::InvalidOperation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_count_distinct_frequencies() {
+        let side = k!(sym: vec!["buy", "sell", "buy", "buy", "hold", "sell"]);
+
+        assert_eq!(side.count_distinct().unwrap(), 3);
+        let frequencies = side.frequencies().unwrap();
+        assert_eq!(
+            frequencies.as_vec::<K>().unwrap()[0].as_vec::<S>().unwrap(),
+            &vec!["buy".to_string(), "sell".to_string(), "hold".to_string()]
+        );
+        assert_eq!(
+            frequencies.as_vec::<K>().unwrap()[1].as_vec::<J>().unwrap(),
+            &vec![3, 2, 1]
+        );
+        assert_eq!(
+            format!("{}", frequencies),
+            String::from("`buy`sell`hold!3 2 1")
+        );
+        assert_eq!(
+            K::new_symbol_list(Vec::new(), qattribute::NONE)
+                .count_distinct()
+                .unwrap(),
+            0
+        );
+        assert!(k!(long: 1).frequencies().is_err());
+    }
+
+    #[test]
+    fn test_distinct_group() {
+        let prices = k!(float: vec![1.5, qnull::FLOAT, 1.5, qnull::FLOAT, 2.0]);
+        assert_eq!(format!("{}", prices.distinct().unwrap()), "1.5 0n 2");
+        assert_eq!(
+            format!("{}", prices.group().unwrap()),
+            "1.5 0n 2!(0 2;1 3;,4)"
+        );
+
+        let mixed = k!([k!(long: 1), k!(sym: "a"), k!(long: 1)]);
+        assert_eq!(mixed.distinct().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_inner_join() {
+        let trades = k!(table: {
+            "sym" => k!(sym: vec!["AAPL", "MSFT", "AAPL", "IBM"]),
+            "size" => k!(long: vec![100, 200, 300, 400])
+        });
+        let quotes = k!(table: {
+            "sym" => k!(sym: vec!["AAPL", "MSFT", "GOOG"]),
+            "bid" => k!(float: vec![150.5, 300.25, 99.0])
+        });
+
+        let joined = trades.inner_join(&quotes, "sym").unwrap();
+        assert_eq!(joined.len(), 3);
+        assert_eq!(
+            joined.get_column("sym").unwrap().as_vec::<S>().unwrap(),
+            &vec!["AAPL".to_string(), "MSFT".to_string(), "AAPL".to_string()]
+        );
+        assert_eq!(
+            joined.get_column("size").unwrap().as_vec::<J>().unwrap(),
+            &vec![100, 200, 300]
+        );
+        assert_eq!(
+            joined.get_column("bid").unwrap().as_vec::<F>().unwrap(),
+            &vec![150.5, 300.25, 150.5]
+        );
+    }
+
+    #[test]
+    fn test_inner_join_error() {
+        let left = k!(table: {
+            "sym" => k!(sym: vec!["a"]),
+            "price" => k!(float: vec![1.0])
+        });
+        let collision = k!(table: {
+            "sym" => k!(sym: vec!["a"]),
+            "price" => k!(float: vec![2.0])
+        });
+        let wrong_key = k!(table: {
+            "sym" => k!(long: vec![1]),
+            "size" => k!(long: vec![2])
+        });
+
+        assert_eq!(
+            left.inner_join(&collision, "sym").unwrap_err(),
+            Error::DuplicateColumn("price".to_string())
+        );
+        assert!(matches!(
+            left.inner_join(&wrong_key, "sym"),
+            Err(Error::InvalidOperation { .. })
+        ));
+        assert!(matches!(
+            left.inner_join(&collision, "size"),
+            Err(Error::NoSuchColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_reorder_columns() {
+        let mut table = k!(table: {
+            "time" => k!(long: vec![1, 2]),
+            "sym" => k!(sym: vec!["a", "b"]),
+            "price" => k!(float: vec![1.5, 2.5])
+        });
+
+        table.reorder_columns(&["sym", "price", "time"]).unwrap();
+        assert_eq!(table.cols().unwrap(), vec!["sym", "price", "time"]);
+        assert_eq!(
+            table.get_column("price").unwrap().as_vec::<F>().unwrap(),
+            &vec![1.5, 2.5]
+        );
+        assert_eq!(
+            format!("{}", table),
+            String::from("+`sym`price`time!(`a`b;1.5 2.5;1 2)")
+        );
+
+        assert_eq!(
+            table.reorder_columns(&["sym", "size", "time"]).unwrap_err(),
+            Error::NoSuchColumn("size".to_string())
+        );
+        assert_eq!(
+            table.reorder_columns(&["sym", "time"]).unwrap_err(),
+            Error::NoSuchColumn("price".to_string())
+        );
+        assert_eq!(
+            table.reorder_columns(&["sym", "sym", "time"]).unwrap_err(),
+            Error::DuplicateColumn("sym".to_string())
+        );
+        // Failed reorder leaves the table intact
+        assert_eq!(table.cols().unwrap(), vec!["sym", "price", "time"]);
+    }
+
+    #[test]
+    fn test_ungroup() {
+        let table = k!(table: {
+            "sym" => k!(sym: vec!["AAPL", "MSFT", "IBM"]),
+            "price" => k!([
+                k!(float: vec![150.0, 150.5]),
+                k!(float: vec![300.0]),
+                k!(float: vec![120.0, 120.25, 120.5])
+            ]),
+            "size" => k!([
+                k!(long: vec![100, 200]),
+                k!(long: vec![300]),
+                k!(long: vec![10, 20, 30])
+            ])
+        });
+
+        let flat = table.ungroup().unwrap();
+        assert_eq!(flat.len(), 6);
+        assert_eq!(flat.cols().unwrap(), vec!["sym", "price", "size"]);
+        assert_eq!(
+            flat.get_column("sym").unwrap().as_vec::<S>().unwrap(),
+            &vec!["AAPL", "AAPL", "MSFT", "IBM", "IBM", "IBM"]
+        );
+        assert_eq!(
+            flat.get_column("price").unwrap().as_vec::<F>().unwrap(),
+            &vec![150.0, 150.5, 300.0, 120.0, 120.25, 120.5]
+        );
+        assert_eq!(
+            flat.get_column("size").unwrap().as_vec::<J>().unwrap(),
+            &vec![100, 200, 300, 10, 20, 30]
+        );
+    }
+
+    #[test]
+    fn test_ungroup_length_mismatch() {
+        let table = k!(table: {
+            "price" => k!([k!(float: vec![1.0, 2.0]), k!(float: vec![3.0])]),
+            "size" => k!([k!(long: vec![1, 2]), k!(long: vec![3, 4])])
+        });
+        assert_eq!(
+            table.ungroup().unwrap_err(),
+            Error::LengthMismatch {
+                key_length: 1,
+                value_length: 2
+            }
+        );
+        assert!(k!(long: vec![1, 2]).ungroup().is_err());
+    }
+
+    #[test]
+    fn test_til_range() {
+        let q_til = K::til(5);
+        assert_eq!(q_til.get_type(), qtype::LONG_LIST);
+        assert_eq!(q_til.as_vec::<J>().unwrap(), &vec![0, 1, 2, 3, 4]);
+        assert!(K::til(0).as_vec::<J>().unwrap().is_empty());
+        assert!(K::til(-3).as_vec::<J>().unwrap().is_empty());
+
+        let q_range = K::range(2, 11, 3).unwrap();
+        assert_eq!(q_range.as_vec::<J>().unwrap(), &vec![2, 5, 8]);
+        let q_range = K::range(3, -3, -2).unwrap();
+        assert_eq!(q_range.as_vec::<J>().unwrap(), &vec![3, 1, -1]);
+        assert!(K::range(5, 1, 1).unwrap().as_vec::<J>().unwrap().is_empty());
+        let q_range = K::range(J::MAX - 1, J::MAX, 5).unwrap();
+        assert_eq!(q_range.as_vec::<J>().unwrap(), &vec![J::MAX - 1]);
+
+        assert_eq!(
+            K::range(0, 5, 0).unwrap_err(),
+            Error::invalid_operation("range", qtype::LONG_ATOM, None)
+        );
+    }
+
+    #[test]
+    fn test_raze() {
+        let q_compound = k!([
+            k!(long: vec![1, 2]),
+            k!(long: vec![3]),
+            k!(long: vec![4, 5, 6])
+        ]);
+        let razed = q_compound.raze().unwrap();
+        assert_eq!(razed.get_type(), qtype::LONG_LIST);
+        assert_eq!(razed.as_vec::<J>().unwrap(), &vec![1, 2, 3, 4, 5, 6]);
+
+        // Strings are joined into a string
+        let q_strings = k!([k!(string: "ab"), k!(string: "c")]);
+        assert_eq!(q_strings.raze().unwrap().as_string().unwrap(), "abc");
+
+        // Mixed types are flattened into a compound list
+        let q_mixed = k!([
+            k!(long: vec![1, 2]),
+            k!(sym: vec!["a"]),
+            k!([k!(float: 1.5)])
+        ]);
+        let razed = q_mixed.raze().unwrap();
+        assert_eq!(razed.get_type(), qtype::COMPOUND_LIST);
+        let elements = razed.as_vec::<K>().unwrap();
+        assert_eq!(elements.len(), 4);
+        assert_eq!(elements[1].get_long().unwrap(), 2);
+        assert_eq!(elements[2].get_symbol().unwrap(), "a");
+        assert_eq!(elements[3].get_float().unwrap(), 1.5);
+
+        assert_eq!(k!([]).raze().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_raze_error() {
+        assert!(k!(long: vec![1, 2]).raze().is_err());
+        assert!(k!([k!(long: vec![1, 2]), k!(long: 3)]).raze().is_err());
+    }
+
+    #[test]
+    fn test_count_each() {
+        let q_ragged = k!([
+            k!(long: vec![1, 2, 3]),
+            K::new_float_list(Vec::new(), qattribute::NONE),
+            k!(long: 7),
+            k!([k!(sym: vec!["a", "b"]), k!(int: 1)]),
+            k!(string: "ab"),
+            k!(table: {"sym" => k!(sym: vec!["a", "b", "c", "d"])})
+        ]);
+        let counts = q_ragged.count_each().unwrap();
+        assert_eq!(counts.get_type(), qtype::LONG_LIST);
+        assert_eq!(counts.as_vec::<J>().unwrap(), &vec![3, 0, 1, 2, 2, 4]);
+
+        assert_eq!(
+            k!([]).count_each().unwrap().as_vec::<J>().unwrap(),
+            &Vec::<J>::new()
+        );
+        assert_eq!(
+            k!(long: vec![1, 2]).count_each().unwrap_err(),
+            Error::invalid_operation("count_each", qtype::LONG_LIST, Some(qtype::COMPOUND_LIST))
+        );
+    }
+
+    #[test]
+    fn test_to_compound() {
+        let q_compound = k!(long: vec![1, qnull_base::J, 3]).to_compound().unwrap();
+        assert_eq!(q_compound.get_type(), qtype::COMPOUND_LIST);
+        let elements = q_compound.as_vec::<K>().unwrap();
+        assert_eq!(elements.len(), 3);
+        for (element, expected) in elements.iter().zip([1, qnull_base::J, 3]) {
+            assert_eq!(element.get_type(), qtype::LONG_ATOM);
+            assert_eq!(element.get_long().unwrap(), expected);
+        }
+
+        // Temporal types, symbols and strings keep their atom type
+        let q_compound = k!(date: vec![chrono::NaiveDate::from_ymd_opt(2020, 1, 2).unwrap()])
+            .to_compound()
+            .unwrap();
+        assert_eq!(
+            q_compound.as_vec::<K>().unwrap()[0].get_type(),
+            qtype::DATE_ATOM
+        );
+        let q_compound = k!(sym: vec!["a", "b"]).to_compound().unwrap();
+        assert_eq!(format!("{}", q_compound), String::from("(`a;`b)"));
+        let q_compound = k!(string: "ab").to_compound().unwrap();
+        assert_eq!(
+            q_compound.as_vec::<K>().unwrap()[1].get_char().unwrap(),
+            'b'
+        );
+
+        let q_empty = K::new_long_list(Vec::new(), qattribute::NONE);
+        assert_eq!(q_empty.to_compound().unwrap().len(), 0);
+        let q_mixed = k!([k!(long: 1), k!(sym: "a")]);
+        assert_eq!(
+            format!("{}", q_mixed.to_compound().unwrap()),
+            format!("{}", q_mixed)
+        );
+        assert!(k!(long: 1).to_compound().is_err());
+    }
+
+    #[test]
+    fn test_cut() {
+        let q_long_list = k!(long: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let sublists = q_long_list.cut(&k!(int: vec![0, 3, 7])).unwrap();
+        let sublists = sublists.as_vec::<K>().unwrap();
+        assert_eq!(
+            sublists
+                .iter()
+                .map(|list| list.len())
+                .collect::<Vec<usize>>(),
+            vec![3, 4, 3]
+        );
+        assert_eq!(sublists[0].as_vec::<J>().unwrap(), &vec![0, 1, 2]);
+        assert_eq!(sublists[1].as_vec::<J>().unwrap(), &vec![3, 4, 5, 6]);
+        assert_eq!(sublists[2].as_vec::<J>().unwrap(), &vec![7, 8, 9]);
+
+        // Elements before the first cut point are dropped
+        let q_string = k!(string: "hello world");
+        let words = q_string.cut(&k!(long: vec![2, 6])).unwrap();
+        let words = words.as_vec::<K>().unwrap();
+        assert_eq!(words[0].as_string().unwrap(), "llo ");
+        assert_eq!(words[1].as_string().unwrap(), "world");
+
+        // Cut at the end gives an empty sublist
+        let sublists = q_long_list.cut(&k!(long: vec![8, 10])).unwrap();
+        assert_eq!(sublists.as_vec::<K>().unwrap()[1].len(), 0);
+    }
+
+    #[test]
+    fn test_cut_error() {
+        let q_long_list = k!(long: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(
+            q_long_list.cut(&k!(long: vec![3, 0])).unwrap_err(),
+            Error::NotAscending("cut")
+        );
+        assert_eq!(
+            q_long_list.cut(&k!(long: vec![0, 11])).unwrap_err(),
+            Error::IndexOutOfBounds {
+                length: 10,
+                index: 11
+            }
+        );
+        assert_eq!(
+            q_long_list.cut(&k!(long: vec![-1, 2])).unwrap_err(),
+            Error::InvalidStructure(String::from("cut point -1 is negative"))
+        );
+        assert!(q_long_list.cut(&k!(float: vec![0.0])).is_err());
+        assert!(k!(long: 1).cut(&k!(long: vec![0])).is_err());
+    }
+
+    #[test]
+    fn test_reshape() {
+        let q_long_list = k!(long: vec![0, 1, 2, 3, 4, 5]);
+        let matrix = q_long_list.reshape(2, 3).unwrap();
+        let rows = matrix.as_vec::<K>().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_vec::<J>().unwrap(), &vec![0, 1, 2]);
+        assert_eq!(rows[1].as_vec::<J>().unwrap(), &vec![3, 4, 5]);
+
+        let matrix = q_long_list.reshape(3, 2).unwrap();
+        let rows = matrix.as_vec::<K>().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].as_vec::<J>().unwrap(), &vec![0, 1]);
+        assert_eq!(rows[1].as_vec::<J>().unwrap(), &vec![2, 3]);
+        assert_eq!(rows[2].as_vec::<J>().unwrap(), &vec![4, 5]);
+        assert_eq!(rows[2].get_type(), qtype::LONG_LIST);
+
+        assert_eq!(
+            q_long_list.reshape(4, 2).unwrap_err(),
+            Error::LengthMismatch {
+                key_length: 8,
+                value_length: 6
+            }
+        );
+        assert!(k!(long: 1).reshape(1, 1).is_err());
+        assert!(matches!(
+            q_long_list.reshape(usize::MAX, 2),
+            Err(Error::SizeOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_ratios() {
+        let q_float_list = k!(float: vec![2.0, 4.0, 1.0, 0.0, 3.0, qnull::FLOAT, 5.0]);
+        let ratios = q_float_list.ratios().unwrap();
+        assert_eq!(ratios.get_type(), qtype::FLOAT_LIST);
+        let ratios = ratios.as_vec::<F>().unwrap();
+        assert_eq!(ratios.len(), 7);
+        assert_eq!(ratios[0], 2.0);
+        assert_eq!(ratios[1], 2.0);
+        assert_eq!(ratios[2], 0.25);
+        assert_eq!(ratios[3], 0.0);
+        // 3 % 0 is infinity
+        assert_eq!(ratios[4], f64::INFINITY);
+        // Nulls on either side give null
+        assert!(ratios[5].is_nan());
+        assert!(ratios[6].is_nan());
+
+        // 0 % 0 is null and -1 % 0 is negative infinity
+        let ratios = k!(long: vec![0, 0, -1]).ratios().unwrap();
+        let ratios = ratios.as_vec::<F>().unwrap();
+        assert_eq!(ratios[0], 0.0);
+        assert!(ratios[1].is_nan());
+        assert_eq!(ratios[2], f64::NEG_INFINITY);
+
+        assert_eq!(
+            K::new_float_list(vec![], qattribute::NONE)
+                .ratios()
+                .unwrap()
+                .len(),
+            0
+        );
+        assert!(k!(sym: vec!["a"]).ratios().is_err());
+    }
+
+    #[test]
+    fn test_each_prior() {
+        let q_long_list = k!(long: vec![1, 2, 3, 4, 5]);
+        let sums = q_long_list
+            .each_prior(|x, y| Ok(k!(long: x.get_long()? + y.get_long()?)))
+            .unwrap();
+        assert_eq!(sums.get_type(), qtype::COMPOUND_LIST);
+        assert_eq!(
+            sums.as_vec::<K>()
+                .unwrap()
+                .iter()
+                .map(|sum| sum.get_long().unwrap())
+                .collect::<Vec<J>>(),
+            vec![1, 3, 5, 7, 9]
+        );
+
+        // Previous element is passed as the second argument
+        let q_symbol_list = k!(sym: vec!["a", "b"]);
+        let pairs = q_symbol_list
+            .each_prior(|x, y| Ok(k!(sym: format!("{}{}", x.get_symbol()?, y.get_symbol()?))))
+            .unwrap();
+        assert_eq!(pairs.as_vec::<K>().unwrap()[1].get_symbol().unwrap(), "ba");
+
+        // Errors from the function are propagated
+        assert!(q_long_list
+            .each_prior(|x, _| Err(Error::invalid_operation("f", x.get_type(), None)))
+            .is_err());
+        assert_eq!(
+            K::new_long_list(vec![], qattribute::NONE)
+                .each_prior(|x, _| Ok(x))
+                .unwrap()
+                .len(),
+            0
+        );
+        assert!(k!(long: 1).each_prior(|x, _| Ok(x)).is_err());
+    }
+
+    #[test]
+    fn test_fills() {
+        let q_long_list = k!(long: vec![1, qnull::LONG, qnull::LONG, 4]);
+        assert_eq!(
+            q_long_list.fills().unwrap().as_vec::<J>().unwrap(),
+            &vec![1, 1, 1, 4]
+        );
+
+        // Leading nulls stay null
+        let q_long_list = k!(long: vec![qnull::LONG, qnull::LONG, 2, qnull::LONG]);
+        assert_eq!(
+            q_long_list.fills().unwrap().as_vec::<J>().unwrap(),
+            &vec![qnull::LONG, qnull::LONG, 2, 2]
+        );
+
+        let q_symbol_list = k!(sym: vec!["", "a", "", "b", ""]);
+        assert_eq!(
+            q_symbol_list.fills().unwrap().as_vec::<S>().unwrap(),
+            &vec!["", "a", "a", "b", "b"]
+        );
+
+        // Temporal type is kept
+        let q_date_list = K::new(
+            qtype::DATE_LIST,
+            qattribute::NONE,
+            k0_inner::list(k0_list::new(vec![10, qnull_base::I, 12])),
+        );
+        let filled = q_date_list.fills().unwrap();
+        assert_eq!(filled.get_type(), qtype::DATE_LIST);
+        assert_eq!(filled.as_vec::<I>().unwrap(), &vec![10, 10, 12]);
+
+        assert!(k!([k!(long: 1)]).fills().is_err());
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let left = k!(long: vec![1, 2, 3]);
+        let right = k!(long: vec![10, 20, 30]);
+        let sums = left
+            .zip_with(&right, |x, y| Ok(k!(long: x.get_long()? + y.get_long()?)))
+            .unwrap();
+        assert_eq!(sums.get_type(), qtype::COMPOUND_LIST);
+        assert_eq!(
+            sums.as_vec::<K>()
+                .unwrap()
+                .iter()
+                .map(|sum| sum.get_long().unwrap())
+                .collect::<Vec<J>>(),
+            vec![11, 22, 33]
+        );
+
+        // Error from the function is propagated
+        let symbols = k!(sym: vec!["a", "b", "c"]);
+        assert!(left
+            .zip_with(&symbols, |x, y| Ok(k!(long: x.get_long()? + y.get_long()?)))
+            .is_err());
+
+        assert_eq!(
+            left.zip_with(&k!(long: vec![1]), |x, _| Ok(x)).unwrap_err(),
+            Error::LengthMismatch {
+                key_length: 3,
+                value_length: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let plus = K::q_ipc_decode(&[102, 1], 1).unwrap();
+        let sum = plus.apply(&[k!(long: 1), k!(long: 2)]).unwrap();
+        assert_eq!(sum.get_type(), qtype::LONG_ATOM);
+        assert_eq!(sum.get_long().unwrap(), 3);
+
+        let neg = K::q_ipc_decode(&[101, 2], 1).unwrap();
+        let negated = neg.apply(&[k!(long: vec![1, -2, qnull::LONG])]).unwrap();
+        assert_eq!(negated.get_type(), qtype::LONG_LIST);
+        assert_eq!(negated.as_vec::<J>().unwrap(), &vec![-1, 2, qnull::LONG]);
+
+        // Type is widened
+        let times = K::q_ipc_decode(&[102, 3], 1).unwrap();
+        let product = times.apply(&[k!(int: vec![1, 2]), k!(real: 1.5)]).unwrap();
+        assert_eq!(product.get_type(), qtype::REAL_LIST);
+        assert_eq!(product.as_vec::<E>().unwrap(), &vec![1.5, 3.0]);
+
+        // Shorts are promoted to int except by `neg`
+        let sum = plus.apply(&[k!(short: 32767), k!(short: 1)]).unwrap();
+        assert_eq!(sum.get_type(), qtype::INT_ATOM);
+        assert_eq!(sum.get_int().unwrap(), 32768);
+        let product = times
+            .apply(&[k!(short: vec![200, qnull::SHORT]), k!(short: 300)])
+            .unwrap();
+        assert_eq!(product.get_type(), qtype::INT_LIST);
+        assert_eq!(product.as_vec::<I>().unwrap(), &vec![60_000, qnull::INT]);
+        let negated = neg.apply(&[k!(short: 3)]).unwrap();
+        assert_eq!(negated.get_type(), qtype::SHORT_ATOM);
+        assert_eq!(negated.get_short().unwrap(), -3);
+
+        // Division always gives float
+        let divide = K::q_ipc_decode(&[102, 4], 1).unwrap();
+        let ratio = divide.apply(&[k!(long: 1), k!(short: 4)]).unwrap();
+        assert_eq!(ratio.get_type(), qtype::FLOAT_ATOM);
+        assert_eq!(ratio.get_float().unwrap(), 0.25);
+
+        // Null propagates
+        let minus = K::q_ipc_decode(&[102, 2], 1).unwrap();
+        let difference = minus
+            .apply(&[k!(int: vec![5, qnull::INT]), k!(int: vec![1, 1])])
+            .unwrap();
+        assert_eq!(difference.as_vec::<I>().unwrap(), &vec![4, qnull::INT]);
+    }
+
+    #[test]
+    fn test_apply_error() {
+        let plus = K::q_ipc_decode(&[102, 1], 1).unwrap();
+        // Wrong number of arguments
+        assert!(plus.apply(&[k!(long: 1)]).is_err());
+        // Not a number
+        assert!(plus.apply(&[k!(long: 1), k!(sym: "a")]).is_err());
+        // Length mismatch
+        assert!(plus
+            .apply(&[k!(long: vec![1, 2]), k!(long: vec![1])])
+            .is_err());
+        // Unsupported primitive (`&`)
+        let and = K::q_ipc_decode(&[102, 5], 1).unwrap();
+        assert!(and.apply(&[k!(long: 1), k!(long: 2)]).is_err());
+        // Not a function
+        assert!(k!(long: 1).apply(&[k!(long: 1)]).is_err());
+    }
+
+    #[test]
+    fn test_which_gather() {
+        let q_long_list = k!(long: vec![5, 12, 3, 20, qnull::LONG, 11]);
+        let indices = q_long_list.which_gt(&k!(long: 10)).unwrap();
+        assert_eq!(indices.as_vec::<J>().unwrap(), &vec![1, 3, 5]);
+        let selected = q_long_list.gather(&indices).unwrap();
+        assert_eq!(selected.as_vec::<J>().unwrap(), &vec![12, 20, 11]);
+
+        // Threshold of another type
+        let indices = q_long_list.which_gt(&k!(float: 11.5)).unwrap();
+        assert_eq!(indices.as_vec::<J>().unwrap(), &vec![1, 3]);
+
+        // Same result through a predicate
+        let indices = q_long_list
+            .which(|element| element.get_long().unwrap() > 10)
+            .unwrap();
+        assert_eq!(indices.as_vec::<J>().unwrap(), &vec![1, 3, 5]);
+
+        // Gather works with any list
+        let q_symbol_list = k!(sym: vec!["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(
+            q_symbol_list
+                .gather(&indices)
+                .unwrap()
+                .as_vec::<S>()
+                .unwrap(),
+            &vec!["b", "d", "f"]
+        );
+    }
+
+    #[test]
+    fn test_which_gather_error() {
+        let q_long_list = k!(long: vec![1, 2]);
+        assert!(q_long_list.which_gt(&k!(long: vec![1])).is_err());
+        assert!(q_long_list.which_gt(&k!(sym: "a")).is_err());
+        assert!(k!(long: 1).which(|_| true).is_err());
+        assert_eq!(
+            q_long_list.gather(&k!(long: vec![0, 2])).unwrap_err(),
+            Error::IndexOutOfBounds {
+                length: 2,
+                index: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_dict_dedup() {
+        let keys = k!(sym: vec!["a", "b", "a", "c", "b"]);
+        let values = k!([
+            k!(long: 1),
+            k!(float: 2.0),
+            k!(long: 3),
+            k!(sym: "four"),
+            k!(long: 5)
+        ]);
+        let q_dictionary = K::dict_dedup(keys, values).unwrap();
+        assert_eq!(
+            q_dictionary.as_vec::<K>().unwrap()[0]
+                .as_vec::<S>()
+                .unwrap(),
+            &vec!["a", "b", "c"]
+        );
+        // First value wins
+        assert_eq!(
+            q_dictionary
+                .try_find(&k!(sym: "a"))
+                .unwrap()
+                .get_long()
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            q_dictionary
+                .try_find(&k!(sym: "b"))
+                .unwrap()
+                .get_float()
+                .unwrap(),
+            2.0
+        );
+        assert_eq!(q_dictionary.len(), 3);
+
+        // Unique keys keep their attribute
+        let keys = K::new_int_list(vec![1, 2], qattribute::SORTED);
+        let q_dictionary = K::dict_dedup(keys, k!(long: vec![10, 20])).unwrap();
+        assert_eq!(format!("{}", q_dictionary), String::from("`s#1 2i!10 20"));
+
+        assert!(K::dict_dedup(k!(long: vec![1, 1]), k!(long: vec![1])).is_err());
+    }
+
+    #[test]
+    fn test_datetime_timestamp() {
+        use chrono::{Duration, TimeZone, Utc};
+
+        let instant = Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 15).unwrap();
+        let q_timestamp = K::new_timestamp(instant + Duration::nanoseconds(123456789));
+        let q_datetime = q_timestamp.timestamp_to_datetime().unwrap();
+        assert_eq!(q_datetime.get_type(), qtype::DATETIME_ATOM);
+        assert_eq!(
+            q_datetime.get_datetime().unwrap(),
+            instant + Duration::milliseconds(123)
+        );
+        // Nanoseconds are lost but the instant survives to datetime precision
+        let round_trip = q_datetime
+            .datetime_to_timestamp()
+            .unwrap()
+            .get_timestamp()
+            .unwrap();
+        let error = round_trip - (instant + Duration::nanoseconds(123456789));
+        assert!(error.num_microseconds().unwrap().abs() < 1000);
+
+        let nanos = K::new_timestamp(instant).get_long().unwrap();
+        let q_timestamps = K::new(
+            qtype::TIMESTAMP_LIST,
+            qattribute::SORTED,
+            k0_inner::list(k0_list::new(vec![
+                qnull_base::J,
+                nanos,
+                nanos + 86_400_000_000_000,
+            ])),
+        );
+        let q_datetimes = q_timestamps.timestamp_to_datetime().unwrap();
+        assert_eq!(q_datetimes.get_type(), qtype::DATETIME_LIST);
+        assert_eq!(q_datetimes.get_attribute(), qattribute::SORTED);
+        assert_eq!(
+            format!("{}", q_datetimes),
+            String::from("`s#0N 2024.03.15T10:30:15.000 2024.03.16T10:30:15.000")
+        );
+        assert_eq!(
+            q_datetimes.datetime_to_timestamp().unwrap().q_ipc_encode(),
+            q_timestamps.q_ipc_encode()
+        );
+
+        assert_eq!(
+            k!(long: vec![1]).datetime_to_timestamp().unwrap_err(),
+            Error::invalid_operation(
+                "datetime_to_timestamp",
+                qtype::LONG_LIST,
+                Some(qtype::DATETIME_LIST)
+            )
+        );
+    }
+
+    #[test]
+    fn test_value() {
+        let q_dictionary = k!(dict: k!(sym: vec!["a", "b", "c"]) => k!(float: vec![1.5, 2.5, 3.5]));
+        let values = q_dictionary.value().unwrap();
+        assert_eq!(values.get_type(), qtype::FLOAT_LIST);
+        assert_eq!(values.as_vec::<F>().unwrap(), &vec![1.5, 2.5, 3.5]);
+
+        let q_keyed_table = k!(table: {
+            "sym" => k!(sym: vec!["a", "b"]),
+            "price" => k!(float: vec![1.5, 2.5]),
+            "size" => k!(long: vec![10, 20])
+        })
+        .enkey(1)
+        .unwrap();
+        let values = q_keyed_table.value().unwrap();
+        assert_eq!(values.get_type(), qtype::TABLE);
+        assert_eq!(values.cols().unwrap(), vec!["price", "size"]);
+        assert_eq!(
+            format!("{}", values),
+            String::from("+`price`size!(1.5 2.5;10 20)")
+        );
+
+        assert_eq!(
+            k!(long: vec![1, 2]).value().unwrap_err(),
+            Error::invalid_operation("value", qtype::LONG_LIST, Some(qtype::DICTIONARY))
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let q_long_list = k!(long: vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            q_long_list.rotate(1).unwrap().as_vec::<J>().unwrap(),
+            &vec![2, 3, 4, 5, 1]
+        );
+        assert_eq!(
+            q_long_list.rotate(-1).unwrap().as_vec::<J>().unwrap(),
+            &vec![5, 1, 2, 3, 4]
+        );
+        // Wraps around
+        assert_eq!(
+            q_long_list.rotate(7).unwrap().as_vec::<J>().unwrap(),
+            &vec![3, 4, 5, 1, 2]
+        );
+        assert_eq!(
+            q_long_list.rotate(-5).unwrap().as_vec::<J>().unwrap(),
+            &vec![1, 2, 3, 4, 5]
+        );
+
+        let q_compound_list = k!([k!(long: 1), k!(sym: "a"), k!(string: "bc")]);
+        assert_eq!(
+            format!("{}", q_compound_list.rotate(-1).unwrap()),
+            String::from("(\"bc\";1;`a)")
+        );
+        let q_string = k!(string: "abc");
+        assert_eq!(q_string.rotate(2).unwrap().as_string().unwrap(), "cab");
+
+        // Extreme shifts do not overflow
+        assert_eq!(
+            q_long_list.rotate(i64::MAX).unwrap().as_vec::<J>().unwrap(),
+            &vec![3, 4, 5, 1, 2]
+        );
+        assert_eq!(
+            q_long_list.rotate(i64::MIN).unwrap().as_vec::<J>().unwrap(),
+            &vec![3, 4, 5, 1, 2]
+        );
+
+        assert_eq!(k!(long: vec![]).rotate(3).unwrap().len(), 0);
+        assert_eq!(k!(long: vec![]).rotate(i64::MIN).unwrap().len(), 0);
+        assert!(k!(long: 1).rotate(1).is_err());
+    }
+
+    #[test]
+    fn test_asc_desc() {
+        let q_long_list = k!(long: vec![3, qnull_base::J, 1, 2, 1]);
+        let sorted = q_long_list.asc().unwrap();
+        assert_eq!(
+            sorted.as_vec::<J>().unwrap(),
+            &vec![qnull_base::J, 1, 1, 2, 3]
+        );
+        assert_eq!(sorted.get_attribute(), qattribute::SORTED);
+        let sorted = q_long_list.desc().unwrap();
+        assert_eq!(
+            sorted.as_vec::<J>().unwrap(),
+            &vec![3, 2, 1, 1, qnull_base::J]
+        );
+        assert_eq!(sorted.get_attribute(), qattribute::NONE);
+
+        let q_symbol_list = k!(sym: vec!["msft", "aapl", "ibm", "goog"]);
+        assert_eq!(
+            q_symbol_list.desc().unwrap().as_vec::<S>().unwrap(),
+            &vec!["msft", "ibm", "goog", "aapl"]
+        );
+        assert_eq!(
+            q_symbol_list.asc().unwrap().as_vec::<S>().unwrap(),
+            &vec!["aapl", "goog", "ibm", "msft"]
+        );
+
+        // Nulls of floats sort first
+        let q_float_list = k!(float: vec![2.5, qnull_base::F, -1.0]);
+        let sorted = q_float_list.asc().unwrap();
+        assert!(sorted.as_vec::<F>().unwrap()[0].is_nan());
+        assert_eq!(&sorted.as_vec::<F>().unwrap()[1..], &[-1.0, 2.5]);
+
+        // Temporal lists keep their type
+        let q_date_list = K::new_date_list(
+            vec![
+                chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                chrono::NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+            ],
+            qattribute::NONE,
+        );
+        let sorted = q_date_list.asc().unwrap();
+        assert_eq!(sorted.get_type(), qtype::DATE_LIST);
+        assert_eq!(
+            format!("{}", sorted),
+            String::from("`s#2023.12.31 2024.03.01")
+        );
+
+        assert!(k!([k!(long: 1), k!(sym: "a")]).asc().is_err());
+        assert!(k!(long: 1).desc().is_err());
+    }
+
+    #[test]
+    fn test_row_to_table() {
+        let q_dictionary = k!(dict: k!(sym: vec!["a", "b"]) => k!([k!(long: 1), k!(sym: "x")]));
+        let q_table = q_dictionary.row_to_table().unwrap();
+        assert_eq!(q_table.get_type(), qtype::TABLE);
+        assert_eq!(q_table.cols().unwrap(), vec!["a", "b"]);
+        assert_eq!(q_table.len(), 1);
+        let a = q_table.get_column("a").unwrap();
+        assert_eq!(a.get_type(), qtype::LONG_LIST);
+        assert_eq!(a.as_vec::<J>().unwrap(), &vec![1]);
+        let b = q_table.get_column("b").unwrap();
+        assert_eq!(b.get_type(), qtype::SYMBOL_LIST);
+        assert_eq!(b.as_vec::<S>().unwrap(), &vec!["x"]);
+
+        // Typed values, and a list value nested in a compound column
+        let q_dictionary =
+            k!(dict: k!(sym: vec!["c", "d"]) => k!([k!(char: 'z'), k!(int: vec![1, 2])]));
+        assert_eq!(
+            format!("{}", q_dictionary.row_to_table().unwrap()),
+            String::from("+`c`d!(,\"z\";,1 2i)")
+        );
+        let q_dictionary = k!(dict: k!(sym: vec!["e", "f"]) => k!(float: vec![1.5, 2.5]));
+        assert_eq!(
+            format!("{}", q_dictionary.row_to_table().unwrap()),
+            String::from("+`e`f!(,1.5;,2.5)")
+        );
+
+        // Keys must be symbols
+        let q_dictionary = k!(dict: k!(long: vec![1, 2]) => k!(long: vec![3, 4]));
+        assert!(q_dictionary.row_to_table().is_err());
+        assert!(k!(long: vec![1]).row_to_table().is_err());
+    }
+
+    #[test]
+    fn test_null_mask_drop_null_rows() {
+        let q_float_list = k!(float: vec![1.0, f64::NAN, 3.0]);
+        assert_eq!(
+            q_float_list.null_mask().unwrap().as_vec::<G>().unwrap(),
+            &vec![0, 1, 0]
+        );
+        assert_eq!(
+            k!(sym: vec!["a", "", "c"])
+                .null_mask()
+                .unwrap()
+                .as_vec::<G>()
+                .unwrap(),
+            &vec![0, 1, 0]
+        );
+        assert!(k!(bool: vec![true, false]).null_mask().is_err());
+
+        let table = k!(table: {
+            "sym" => k!(sym: vec!["a", "b", "c", "d", "e"]),
+            "size" => k!(long: vec![qnull_base::J, 2, qnull_base::J, 4, qnull_base::J]),
+            "price" => k!(float: vec![1.5, 2.5, 3.5, 4.5, 5.5])
+        });
+        let filtered = table.drop_null_rows("size").unwrap();
+        assert_eq!(filtered.get_type(), qtype::TABLE);
+        assert_eq!(filtered.cols().unwrap(), vec!["sym", "size", "price"]);
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(
+            filtered.get_column("sym").unwrap().as_vec::<S>().unwrap(),
+            &vec!["b", "d"]
+        );
+        assert_eq!(
+            filtered.get_column("size").unwrap().as_vec::<J>().unwrap(),
+            &vec![2, 4]
+        );
+        assert_eq!(
+            filtered.get_column("price").unwrap().as_vec::<F>().unwrap(),
+            &vec![2.5, 4.5]
+        );
+
+        // No nulls keeps every row
+        assert_eq!(table.drop_null_rows("price").unwrap().len(), 5);
+
+        // Unknown column, non-nullable column and non-table
+        assert!(matches!(
+            table.drop_null_rows("qty"),
+            Err(Error::NoSuchColumn(_))
+        ));
+        let table = k!(table: {"flag" => k!(bool: vec![true, false])});
+        assert!(table.drop_null_rows("flag").is_err());
+        assert!(k!(long: vec![1]).drop_null_rows("size").is_err());
+    }
+
+    #[test]
+    fn test_distinct_rows() {
+        let table = k!(table: {
+            "sym" => k!(sym: vec!["a", "b", "a", "a"]),
+            "size" => k!(long: vec![1, 2, 1, 3]),
+            "price" => k!(float: vec![1.5, 2.5, 1.5, 1.5])
+        });
+        let distinct = table.distinct_rows().unwrap();
+        assert_eq!(distinct.get_type(), qtype::TABLE);
+        assert_eq!(distinct.cols().unwrap(), vec!["sym", "size", "price"]);
+        assert_eq!(distinct.len(), 3);
+        assert_eq!(
+            distinct.get_column("sym").unwrap().as_vec::<S>().unwrap(),
+            &vec!["a", "b", "a"]
+        );
+        assert_eq!(
+            distinct.get_column("size").unwrap().as_vec::<J>().unwrap(),
+            &vec![1, 2, 3]
+        );
+        assert_eq!(
+            distinct.get_column("price").unwrap().as_vec::<F>().unwrap(),
+            &vec![1.5, 2.5, 1.5]
+        );
+
+        // Rows equal in one column only are kept
+        let table = k!(table: {
+            "sym" => k!(sym: vec!["a", "a"]),
+            "size" => k!(long: vec![1, 2])
+        });
+        assert_eq!(table.distinct_rows().unwrap().len(), 2);
+
+        assert!(k!(long: vec![1, 1]).distinct_rows().is_err());
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let table = k!(table: {
+            "sym" => k!(sym: vec!["a", "b", "c"]),
+            "price" => k!(float: vec![1.5, 2.5, 3.5]),
+            "size" => k!(long: vec![100, 200, 300])
+        });
+        let projected = table.select_columns(&["size", "sym"]).unwrap();
+        assert_eq!(projected.get_type(), qtype::TABLE);
+        assert_eq!(projected.cols().unwrap(), vec!["size", "sym"]);
+        assert_eq!(projected.len(), 3);
+        assert_eq!(
+            projected.get_column("size").unwrap().as_vec::<J>().unwrap(),
+            &vec![100, 200, 300]
+        );
+        assert_eq!(
+            projected.get_column("sym").unwrap().as_vec::<S>().unwrap(),
+            &vec!["a", "b", "c"]
+        );
+        // Source table is untouched
+        assert_eq!(table.cols().unwrap(), vec!["sym", "price", "size"]);
+
+        assert!(matches!(
+            table.select_columns(&["sym", "qty"]),
+            Err(Error::NoSuchColumn(column)) if column == "qty"
+        ));
+        assert!(matches!(
+            table.select_columns(&["sym", "sym"]),
+            Err(Error::DuplicateColumn(_))
+        ));
+        assert!(k!(long: vec![1]).select_columns(&["sym"]).is_err());
+    }
+
+    #[test]
+    fn test_symbol_to_string_string_to_symbol() {
+        let q_symbol_list = k!(sym: vec!["apple", "", "b c"]);
+        let strings = q_symbol_list.symbol_to_string().unwrap();
+        assert_eq!(strings.get_type(), qtype::COMPOUND_LIST);
+        let elements = strings.as_vec::<K>().unwrap();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0].as_string().unwrap(), "apple");
+        assert_eq!(elements[1].get_type(), qtype::STRING);
+        assert_eq!(elements[1].as_string().unwrap(), "");
+        assert_eq!(elements[2].as_string().unwrap(), "b c");
+
+        // Round trip
+        let symbols = strings.string_to_symbol().unwrap();
+        assert_eq!(symbols.get_type(), qtype::SYMBOL_LIST);
+        assert_eq!(
+            symbols.as_vec::<S>().unwrap(),
+            q_symbol_list.as_vec::<S>().unwrap()
+        );
+
+        let q_symbol = k!(sym: "price");
+        let q_string = q_symbol.symbol_to_string().unwrap();
+        assert_eq!(q_string.as_string().unwrap(), "price");
+        assert_eq!(
+            q_string.string_to_symbol().unwrap().get_symbol().unwrap(),
+            "price"
+        );
+
+        assert!(k!(long: 1).symbol_to_string().is_err());
+        assert!(k!([k!(string: "a"), k!(sym: "b")])
+            .string_to_symbol()
+            .is_err());
+    }
+
+    #[test]
+    fn test_except_inter_union() {
+        let x = k!(sym: vec!["a", "b", "c", "b", "d"]);
+        let y = k!(sym: vec!["b", "d", "e", "e"]);
+
+        // `a`b`c`b`d except `b`d`e`e
+        let result = x.except(&y).unwrap();
+        assert_eq!(result.get_type(), qtype::SYMBOL_LIST);
+        assert_eq!(result.as_vec::<S>().unwrap(), &vec!["a", "c"]);
+
+        // `a`b`c`b`d inter `b`d`e`e
+        let result = x.inter(&y).unwrap();
+        assert_eq!(result.as_vec::<S>().unwrap(), &vec!["b", "b", "d"]);
+
+        // `a`b`c`b`d union `b`d`e`e
+        let result = x.union(&y).unwrap();
+        assert_eq!(result.get_type(), qtype::SYMBOL_LIST);
+        assert_eq!(
+            result.as_vec::<S>().unwrap(),
+            &vec!["a", "b", "c", "d", "e"]
+        );
+
+        // Empty operands
+        let empty = K::new_symbol_list(vec![], qattribute::NONE);
+        assert_eq!(x.except(&empty).unwrap().len(), 5);
+        assert_eq!(x.inter(&empty).unwrap().len(), 0);
+        assert_eq!(
+            empty.union(&y).unwrap().as_vec::<S>().unwrap(),
+            &vec!["b", "d", "e"]
+        );
+
+        // Nulls are equal to each other
+        let floats = k!(float: vec![1.0, qnull::FLOAT, 2.0]);
+        assert_eq!(
+            floats.except(&k!(float: vec![qnull::FLOAT])).unwrap().len(),
+            2
+        );
+
+        assert_eq!(
+            x.except(&k!(long: vec![1])).unwrap_err(),
+            Error::invalid_operation("except", qtype::LONG_LIST, Some(qtype::SYMBOL_LIST))
+        );
+        assert!(x.union(&k!(sym: "a")).is_err());
+    }
+
+    #[test]
+    fn test_to_lower_to_upper_trim() {
+        let q_symbol_list = k!(sym: vec!["AB", "Cd"]; @sorted);
+        let lower = q_symbol_list.to_lower().unwrap();
+        assert_eq!(lower.get_type(), qtype::SYMBOL_LIST);
+        assert_eq!(lower.get_attribute(), qattribute::NONE);
+        assert_eq!(lower.as_vec::<S>().unwrap(), &vec!["ab", "cd"]);
+        assert_eq!(
+            q_symbol_list.to_upper().unwrap().as_vec::<S>().unwrap(),
+            &vec!["AB", "CD"]
+        );
+        assert_eq!(
+            k!(sym: "Ticker").to_upper().unwrap().get_symbol().unwrap(),
+            "TICKER"
+        );
+        // Non-ASCII letters are kept
+        assert_eq!(
+            k!(string: "ÄbC").to_lower().unwrap().as_string().unwrap(),
+            "Äbc"
+        );
+
+        let trimmed = k!(string: "  hello world \t").trim().unwrap();
+        assert_eq!(trimmed.get_type(), qtype::STRING);
+        assert_eq!(trimmed.as_string().unwrap(), "hello world");
+        assert_eq!(
+            k!(sym: vec![" a", "b ", "c"])
+                .trim()
+                .unwrap()
+                .as_vec::<S>()
+                .unwrap(),
+            &vec!["a", "b", "c"]
+        );
+
+        assert!(k!(long: vec![1]).to_lower().is_err());
+        assert!(k!(char: 'a').trim().is_err());
+    }
+
+    #[test]
+    fn test_ssr() {
+        let q_string = k!(string: "the cat sat on the mat");
+        let replaced = q_string.ssr("at", "og").unwrap();
+        assert_eq!(replaced.get_type(), qtype::STRING);
+        assert_eq!(replaced.as_string().unwrap(), "the cog sog on the mog");
+        // Literal pattern and non-overlapping matches
+        assert_eq!(
+            k!(string: "a?a?")
+                .ssr("a?", "b")
+                .unwrap()
+                .as_string()
+                .unwrap(),
+            "bb"
+        );
+        assert_eq!(
+            k!(string: "aaa")
+                .ssr("aa", "b")
+                .unwrap()
+                .as_string()
+                .unwrap(),
+            "ba"
+        );
+
+        let unchanged = q_string.ssr("dog", "cat").unwrap();
+        assert_eq!(
+            unchanged.as_string().unwrap(),
+            q_string.as_string().unwrap()
+        );
+
+        assert!(matches!(
+            q_string.ssr("", "x"),
+            Err(Error::InvalidOperation { .. })
+        ));
+        assert!(k!(sym: "cat").ssr("a", "o").is_err());
+    }
+
+    #[test]
+    fn test_find_index() {
+        let q_long_list = k!(long: vec![10, 20, qnull_base::J, 20]);
+        assert_eq!(q_long_list.find_index(&k!(long: 20)).unwrap(), 1);
+        assert_eq!(q_long_list.find_index(&k!(long: 40)).unwrap(), 4);
+        assert_eq!(q_long_list.find_index(&k!(long: qnull_base::J)).unwrap(), 2);
+        assert_eq!(
+            K::new_long_list(Vec::new(), qattribute::NONE)
+                .find_index(&k!(long: 1))
+                .unwrap(),
+            0
+        );
+
+        let q_float_list = k!(float: vec![1.5, f64::NAN]);
+        assert_eq!(q_float_list.find_index(&k!(float: f64::NAN)).unwrap(), 1);
+        assert_eq!(
+            k!(sym: vec!["a", "b"]).find_index(&k!(sym: "b")).unwrap(),
+            1
+        );
+        assert_eq!(
+            k!(string: "hello").find_index(&K::new_char('l')).unwrap(),
+            2
+        );
+        let q_compound = k!([k!(long: 1), k!(sym: "a")]);
+        assert_eq!(q_compound.find_index(&k!(sym: "a")).unwrap(), 1);
+        assert_eq!(q_compound.find_index(&k!(int: 1)).unwrap(), 2);
+
+        // The value must have the type of the elements
+        assert!(matches!(
+            q_long_list.find_index(&k!(int: 20)),
+            Err(Error::InvalidOperation { .. })
+        ));
+        assert!(k!(long: 1).find_index(&k!(long: 1)).is_err());
+        // An error object has type -128 which has no list counterpart
+        let q_error = K::new_error(String::from("type"));
+        assert!(matches!(
+            q_error.find_index(&k!(long: 1)),
+            Err(Error::InvalidOperation { .. })
+        ));
+        assert!(q_error
+            .find_index(&K::new_error(String::from("type")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_xrank() {
+        // q)5 xrank 3 1 4 1.5 5 9 2 6 5.5 3.5
+        let q_float_list = k!(float: vec![3.0, 1.0, 4.0, 1.5, 5.0, 9.0, 2.0, 6.0, 5.5, 3.5]);
+        assert_eq!(
+            q_float_list.xrank(5).unwrap().as_vec::<J>().unwrap(),
+            &vec![1, 0, 2, 0, 3, 4, 1, 4, 3, 2]
+        );
+        // q)4 xrank til 10
+        assert_eq!(
+            K::til(10).xrank(4).unwrap().as_vec::<J>().unwrap(),
+            &vec![0, 0, 0, 1, 1, 2, 2, 2, 3, 3]
+        );
+
+        // Equal elements are ranked in order and nulls first
+        let q_long_list = k!(long: vec![5, qnull_base::J, 5, 1]);
+        assert_eq!(
+            q_long_list.xrank(4).unwrap().as_vec::<J>().unwrap(),
+            &vec![2, 0, 3, 1]
+        );
+
+        let q_empty = K::new_float_list(Vec::new(), qattribute::NONE);
+        assert_eq!(q_empty.xrank(10).unwrap().len(), 0);
+        assert!(k!(long: 1).xrank(2).is_err());
+    }
+
+    #[test]
+    fn test_bin() {
+        let q_long_list = k!(long: vec![1, 3, 3, 7, 10]; @sorted);
+        let indices = q_long_list
+            .bin(&k!(long: vec![0, 1, 2, 3, 6, 7, 10, 11]))
+            .unwrap();
+        assert_eq!(
+            indices.as_vec::<J>().unwrap(),
+            &vec![-1, 0, 0, 2, 2, 3, 4, 4]
+        );
+        assert_eq!(
+            q_long_list.bin(&k!(long: 8)).unwrap().get_long().unwrap(),
+            3
+        );
+
+        // Sortedness is verified without the attribute
+        let q_long_list = k!(long: vec![1, 3, 7]);
+        assert_eq!(
+            q_long_list
+                .bin(&k!(long: vec![4]))
+                .unwrap()
+                .as_vec::<J>()
+                .unwrap(),
+            &vec![1]
+        );
+        assert_eq!(
+            k!(long: vec![3, 1]).bin(&k!(long: 2)).unwrap_err(),
+            Error::NotAscending("bin")
+        );
+
+        // Temporal and float lists, nulls sort first
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let q_date_list = K::new_date_list(vec![date(1), date(10)], qattribute::NONE);
+        let query = K::new_date(date(5));
+        assert_eq!(q_date_list.bin(&query).unwrap().get_long().unwrap(), 0);
+        let q_float_list = k!(float: vec![qnull::FLOAT, 0.5, 1.5]);
+        assert_eq!(
+            q_float_list
+                .bin(&k!(float: vec![qnull::FLOAT, 0.0, 1.5]))
+                .unwrap()
+                .as_vec::<J>()
+                .unwrap(),
+            &vec![0, 0, 2]
+        );
+
+        // Types must match
+        assert!(q_long_list.bin(&k!(int: 1)).is_err());
+        assert!(k!(sym: vec!["a"]).bin(&k!(sym: "a")).is_err());
+        assert!(k!(long: 1).bin(&k!(long: 1)).is_err());
+    }
+}
@@ -0,0 +1,539 @@
+//! Parsing of q temporal literals.
+//!
+//! Configuration files often hold temporal values written as q literals, e.g. `2024.03.15` or
+//! `10:30:00.000`. This module parses such literals into the corresponding q atoms. A literal may
+//! end with the type suffix of q (e.g. `2024.03m`) and nulls and infinities are written as in q
+//! (`0Nd`, `0Wd`, `-0Wd`).
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use crate::conversions::{ONE_DAY_MILLIS, ONE_DAY_NANOS};
+use crate::error::Error;
+use crate::qconsts::{qattribute, qinf_base, qninf_base, qnull_base, qtype};
+use crate::types::*;
+use chrono::NaiveDate;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl K {
+    /// Parse a q timestamp literal such as `2024.03.15D10:30:00.000000000`. Fractional seconds
+    ///  may have up to 9 digits or be omitted.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::prelude::*;
+    ///
+    /// fn main() {
+    ///     let q_timestamp = K::parse_timestamp("2024.03.15D10:30:00.123456789").unwrap();
+    ///     assert_eq!(
+    ///         q_timestamp.get_timestamp(),
+    ///         Ok(Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 0).unwrap()
+    ///             + chrono::Duration::nanoseconds(123456789))
+    ///     );
+    /// }
+    /// ```
+    pub fn parse_timestamp(literal: &str) -> Result<K> {
+        let long = match special_long(literal, 'p') {
+            Some(long) => long,
+            None => {
+                let text = literal.strip_suffix('p').unwrap_or(literal);
+                let (date, time) = text
+                    .split_once('D')
+                    .ok_or_else(|| parse_error("timestamp", literal))?;
+                let days =
+                    days_since_2000(date).ok_or_else(|| parse_error("timestamp", literal))?;
+                let nanos = clock(time, 9)
+                    .filter(|&nanos| nanos < ONE_DAY_NANOS)
+                    .ok_or_else(|| parse_error("timestamp", literal))?;
+                (days as J)
+                    .checked_mul(ONE_DAY_NANOS)
+                    .and_then(|day_nanos| day_nanos.checked_add(nanos))
+                    .ok_or_else(|| parse_error("timestamp", literal))?
+            }
+        };
+        Ok(K::new(
+            qtype::TIMESTAMP_ATOM,
+            qattribute::NONE,
+            k0_inner::long(long),
+        ))
+    }
+
+    /// Parse a q month literal such as `2024.03m`. The suffix `m` may be omitted.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_month = K::parse_month("2024.03m").unwrap();
+    ///     assert_eq!(format!("{}", q_month), String::from("2024.03m"));
+    /// }
+    /// ```
+    pub fn parse_month(literal: &str) -> Result<K> {
+        let int = match special_int(literal, 'm') {
+            Some(int) => int,
+            None => {
+                let text = literal.strip_suffix('m').unwrap_or(literal);
+                text.split_once('.')
+                    .filter(|(year, _)| year.len() == 4)
+                    .and_then(|(year, month)| {
+                        let month = two_digits(month).filter(|month| (1..=12).contains(month))?;
+                        Some(((number(year)? - 2000) * 12 + month - 1) as I)
+                    })
+                    .ok_or_else(|| parse_error("month", literal))?
+            }
+        };
+        Ok(K::new(
+            qtype::MONTH_ATOM,
+            qattribute::NONE,
+            k0_inner::int(int),
+        ))
+    }
+
+    /// Parse a q date literal such as `2024.03.15`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// fn main() {
+    ///     let q_date = K::parse_date("2024.03.15").unwrap();
+    ///     assert_eq!(q_date.get_date(), Ok(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()));
+    /// }
+    /// ```
+    pub fn parse_date(literal: &str) -> Result<K> {
+        let int = match special_int(literal, 'd') {
+            Some(int) => int,
+            None => {
+                let text = literal.strip_suffix('d').unwrap_or(literal);
+                days_since_2000(text).ok_or_else(|| parse_error("date", literal))?
+            }
+        };
+        Ok(K::new(
+            qtype::DATE_ATOM,
+            qattribute::NONE,
+            k0_inner::int(int),
+        ))
+    }
+
+    /// Parse a q datetime literal such as `2024.03.15T10:30:00.000`. Fractional seconds may have
+    ///  up to 3 digits or be omitted.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_datetime = K::parse_datetime("2024.03.15T10:30:00.250").unwrap();
+    ///     assert_eq!(format!("{}", q_datetime), String::from("2024.03.15T10:30:00.250"));
+    /// }
+    /// ```
+    pub fn parse_datetime(literal: &str) -> Result<K> {
+        let float = match special_long(literal, 'z') {
+            Some(qnull_base::J) => qnull_base::F,
+            Some(qinf_base::J) => qinf_base::F,
+            Some(_) => qninf_base::F,
+            None => {
+                let text = literal.strip_suffix('z').unwrap_or(literal);
+                let (date, time) = text
+                    .split_once('T')
+                    .ok_or_else(|| parse_error("datetime", literal))?;
+                let days = days_since_2000(date).ok_or_else(|| parse_error("datetime", literal))?;
+                let millis = clock(time, 3)
+                    .filter(|&millis| millis < ONE_DAY_MILLIS)
+                    .ok_or_else(|| parse_error("datetime", literal))?;
+                days as F + millis as F / ONE_DAY_MILLIS as F
+            }
+        };
+        Ok(K::new(
+            qtype::DATETIME_ATOM,
+            qattribute::NONE,
+            k0_inner::float(float),
+        ))
+    }
+
+    /// Parse a q timespan literal such as `1D02:30:00.000000000` or `-0D00:00:01.5`. The day
+    ///  count may be omitted and fractional seconds may have up to 9 digits.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::Duration;
+    ///
+    /// fn main() {
+    ///     let q_timespan = K::parse_timespan("1D02:30:00.000000001").unwrap();
+    ///     assert_eq!(
+    ///         q_timespan.get_timespan(),
+    ///         Ok(Duration::hours(26) + Duration::minutes(30) + Duration::nanoseconds(1))
+    ///     );
+    /// }
+    /// ```
+    pub fn parse_timespan(literal: &str) -> Result<K> {
+        let long = match special_long(literal, 'n') {
+            Some(long) => long,
+            None => {
+                let text = literal.strip_suffix('n').unwrap_or(literal);
+                let (negative, text) = match text.strip_prefix('-') {
+                    Some(text) => (true, text),
+                    None => (false, text),
+                };
+                let (days, time) = match text.split_once('D') {
+                    Some((days, time)) => (number(days), time),
+                    None => (Some(0), text),
+                };
+                let nanos = days
+                    .and_then(|days| days.checked_mul(ONE_DAY_NANOS))
+                    .zip(clock(time, 9))
+                    .and_then(|(day_nanos, nanos)| day_nanos.checked_add(nanos))
+                    .ok_or_else(|| parse_error("timespan", literal))?;
+                match negative {
+                    true => -nanos,
+                    false => nanos,
+                }
+            }
+        };
+        Ok(K::new(
+            qtype::TIMESPAN_ATOM,
+            qattribute::NONE,
+            k0_inner::long(long),
+        ))
+    }
+
+    /// Parse a q minute literal such as `10:30`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::Duration;
+    ///
+    /// fn main() {
+    ///     let q_minute = K::parse_minute("10:30").unwrap();
+    ///     assert_eq!(q_minute.get_minute(), Ok(Duration::minutes(630)));
+    /// }
+    /// ```
+    pub fn parse_minute(literal: &str) -> Result<K> {
+        let int = match special_int(literal, 'u') {
+            Some(int) => int,
+            None => {
+                let text = literal.strip_suffix('u').unwrap_or(literal);
+                signed_clock(text, &[60], 0).ok_or_else(|| parse_error("minute", literal))?
+            }
+        };
+        Ok(K::new(
+            qtype::MINUTE_ATOM,
+            qattribute::NONE,
+            k0_inner::int(int),
+        ))
+    }
+
+    /// Parse a q second literal such as `10:30:15`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::Duration;
+    ///
+    /// fn main() {
+    ///     let q_second = K::parse_second("10:30:15").unwrap();
+    ///     assert_eq!(q_second.get_second(), Ok(Duration::seconds(37815)));
+    /// }
+    /// ```
+    pub fn parse_second(literal: &str) -> Result<K> {
+        let int = match special_int(literal, 'v') {
+            Some(int) => int,
+            None => {
+                let text = literal.strip_suffix('v').unwrap_or(literal);
+                signed_clock(text, &[60, 60], 0).ok_or_else(|| parse_error("second", literal))?
+            }
+        };
+        Ok(K::new(
+            qtype::SECOND_ATOM,
+            qattribute::NONE,
+            k0_inner::int(int),
+        ))
+    }
+
+    /// Parse a q time literal such as `10:30:15.250`. Fractional seconds may have up to 3 digits
+    ///  or be omitted.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::Duration;
+    ///
+    /// fn main() {
+    ///     let q_time = K::parse_time("10:30:15.250").unwrap();
+    ///     assert_eq!(q_time.get_time(), Ok(Duration::milliseconds(37815250)));
+    /// }
+    /// ```
+    pub fn parse_time(literal: &str) -> Result<K> {
+        let int = match special_int(literal, 't') {
+            Some(int) => int,
+            None => {
+                let text = literal.strip_suffix('t').unwrap_or(literal);
+                signed_clock(text, &[60, 60], 3).ok_or_else(|| parse_error("time", literal))?
+            }
+        };
+        Ok(K::new(
+            qtype::TIME_ATOM,
+            qattribute::NONE,
+            k0_inner::int(int),
+        ))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build an error for a malformed literal of the given type.
+fn parse_error(kind: &str, literal: &str) -> Error {
+    Error::ParseError(format!("invalid {} literal: {}", kind, literal))
+}
+
+/// Value of a null or infinity literal of the int family, e.g. `0Nd`.
+fn special_int(literal: &str, suffix: char) -> Option<I> {
+    special_long(literal, suffix).map(|long| match long {
+        qnull_base::J => qnull_base::I,
+        qinf_base::J => qinf_base::I,
+        _ => qninf_base::I,
+    })
+}
+
+/// Value of a null or infinity literal of the long family, e.g. `0Np`.
+fn special_long(literal: &str, suffix: char) -> Option<J> {
+    match literal.strip_suffix(suffix)? {
+        "0N" => Some(qnull_base::J),
+        "0W" => Some(qinf_base::J),
+        "-0W" => Some(qninf_base::J),
+        _ => None,
+    }
+}
+
+/// Parse a non-negative decimal number made only of ASCII digits.
+fn number(text: &str) -> Option<J> {
+    match !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit()) {
+        true => text.parse().ok(),
+        false => None,
+    }
+}
+
+/// Parse a number of exactly two digits.
+fn two_digits(text: &str) -> Option<J> {
+    (text.len() == 2).then(|| number(text)).flatten()
+}
+
+/// Days since `2000.01.01` of a date written as `YYYY.MM.DD`.
+fn days_since_2000(text: &str) -> Option<I> {
+    let mut parts = text.split('.');
+    let year = parts
+        .next()
+        .filter(|year| year.len() == 4)
+        .and_then(number)?;
+    let month = parts.next().and_then(two_digits)?;
+    let day = parts.next().and_then(two_digits)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let date = NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32)?;
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    I::try_from(date.signed_duration_since(epoch).num_days()).ok()
+}
+
+/// Time of `hh:mm:ss[.f]` in units of 10^-`digits` seconds. Hours are not limited so that the
+///  caller can apply its own bound.
+fn clock(text: &str, digits: u32) -> Option<J> {
+    let (hms, fraction) = match text.split_once('.') {
+        Some((hms, fraction)) => (hms, fraction),
+        None => (text, ""),
+    };
+    let seconds = units(hms, &[60, 60])?;
+    seconds
+        .checked_mul(10_i64.pow(digits))?
+        .checked_add(fraction_units(fraction, digits)?)
+}
+
+/// Time of `[-]hh:mm[:ss[.f]]` with as many `:` separated fields as `radices` plus one, in units
+///  of 10^-`digits` of the last field.
+fn signed_clock(text: &str, radices: &[J], digits: u32) -> Option<I> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (fields, fraction) = match (digits, text.split_once('.')) {
+        (0, Some(_)) => return None,
+        (_, Some((fields, fraction))) => (fields, fraction),
+        (_, None) => (text, ""),
+    };
+    let value = units(fields, radices)?
+        .checked_mul(10_i64.pow(digits))?
+        .checked_add(fraction_units(fraction, digits)?)?;
+    I::try_from(if negative { -value } else { value }).ok()
+}
+
+/// Combine `:` separated fields into units of the last field. The first field may be any number
+///  and each following field must be two digits below its radix.
+fn units(text: &str, radices: &[J]) -> Option<J> {
+    let mut fields = text.split(':');
+    let mut value = number(fields.next()?)?;
+    for &radix in radices {
+        let field = two_digits(fields.next()?).filter(|&field| field < radix)?;
+        value = value.checked_mul(radix)?.checked_add(field)?;
+    }
+    fields.next().is_none().then_some(value)
+}
+
+/// Convert fractional digits into units of 10^-`digits`, e.g. `5` into 500 for 3 digits.
+fn fraction_units(fraction: &str, digits: u32) -> Option<J> {
+    if fraction.is_empty() {
+        return Some(0);
+    }
+    if fraction.len() > digits as usize {
+        return None;
+    }
+    Some(number(fraction)? * 10_i64.pow(digits - fraction.len() as u32))
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Tests
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_parse_temporal() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let time = Duration::hours(10) + Duration::minutes(30) + Duration::seconds(15);
+        let timestamp = Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 15).unwrap();
+
+        let cases = [
+            (
+                K::parse_timestamp("2024.03.15D10:30:15.123456789").unwrap(),
+                K::new_timestamp(timestamp + Duration::nanoseconds(123456789)),
+            ),
+            (
+                K::parse_timestamp("1999.12.31D23:59:59p").unwrap(),
+                K::new_timestamp(Utc.with_ymd_and_hms(1999, 12, 31, 23, 59, 59).unwrap()),
+            ),
+            (
+                K::parse_month("2024.03").unwrap(),
+                K::new_month(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()),
+            ),
+            (
+                K::parse_month("1998.11m").unwrap(),
+                K::new_month(NaiveDate::from_ymd_opt(1998, 11, 1).unwrap()),
+            ),
+            (K::parse_date("2024.03.15").unwrap(), K::new_date(date)),
+            (
+                K::parse_date("1970.01.01d").unwrap(),
+                K::new_date(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
+            ),
+            (
+                K::parse_datetime("2024.03.15T10:30:15.250").unwrap(),
+                K::new_datetime(timestamp + Duration::milliseconds(250)),
+            ),
+            (
+                K::parse_timespan("0D10:30:15.000000001").unwrap(),
+                K::new_timespan(time + Duration::nanoseconds(1)),
+            ),
+            (
+                K::parse_timespan("-1D00:00:00.5n").unwrap(),
+                K::new_timespan(-(Duration::days(1) + Duration::milliseconds(500))),
+            ),
+            (
+                K::parse_timespan("10:30:15").unwrap(),
+                K::new_timespan(time),
+            ),
+            (
+                K::parse_minute("10:30").unwrap(),
+                K::new_minute(Duration::minutes(630)),
+            ),
+            (K::parse_second("10:30:15v").unwrap(), K::new_second(time)),
+            (
+                K::parse_time("10:30:15.25").unwrap(),
+                K::new_time(time + Duration::milliseconds(250)),
+            ),
+            (
+                K::parse_time("-00:00:01.000").unwrap(),
+                K::new_time(Duration::seconds(-1)),
+            ),
+        ];
+        for (parsed, expected) in cases {
+            assert_eq!(parsed.get_type(), expected.get_type());
+            assert_eq!(
+                parsed.q_ipc_encode(),
+                expected.q_ipc_encode(),
+                "{}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_temporal_round_trip() {
+        let literals = [
+            "2024.03.15D10:30:15.123456789",
+            "2024.03m",
+            "2024.03.15",
+            "2024.03.15T10:30:15.250",
+            "1D10:30:15.123456789",
+            "10:30",
+            "10:30:15",
+            "10:30:15.250",
+        ];
+        let parsers: [fn(&str) -> Result<K>; 8] = [
+            K::parse_timestamp,
+            K::parse_month,
+            K::parse_date,
+            K::parse_datetime,
+            K::parse_timespan,
+            K::parse_minute,
+            K::parse_second,
+            K::parse_time,
+        ];
+        for (literal, parse) in literals.iter().zip(parsers) {
+            assert_eq!(format!("{}", parse(literal).unwrap()), *literal);
+        }
+    }
+
+    #[test]
+    fn test_parse_temporal_null_infinity() {
+        assert_eq!(
+            K::parse_timestamp("0Np").unwrap().get_long(),
+            Ok(qnull_base::J)
+        );
+        assert_eq!(K::parse_date("0Wd").unwrap().get_int(), Ok(qinf_base::I));
+        assert_eq!(K::parse_time("-0Wt").unwrap().get_int(), Ok(qninf_base::I));
+        assert!(K::parse_datetime("0Nz")
+            .unwrap()
+            .get_float()
+            .unwrap()
+            .is_nan());
+        assert_eq!(
+            K::parse_minute("0Nu").unwrap().get_type(),
+            qtype::MINUTE_ATOM
+        );
+    }
+
+    #[test]
+    fn test_parse_temporal_error() {
+        assert_eq!(
+            K::parse_date("2024.02.30").unwrap_err(),
+            Error::ParseError(String::from("invalid date literal: 2024.02.30"))
+        );
+        assert!(K::parse_date("2024-03-15").is_err());
+        assert!(K::parse_date("24.03.15").is_err());
+        assert!(K::parse_month("2024.3").is_err());
+        assert!(K::parse_timestamp("2024.03.15").is_err());
+        assert!(K::parse_timestamp("2024.03.15D24:00:00").is_err());
+        assert!(K::parse_timestamp("2024.03.15D10:30:00.1234567890").is_err());
+        assert!(K::parse_datetime("2024.03.15T10:30:00.0001").is_err());
+        assert!(K::parse_minute("10:60").is_err());
+        assert!(K::parse_minute("10:30:00").is_err());
+        assert!(K::parse_second("10:30:1").is_err());
+        assert!(K::parse_time("ab:cd:ef").is_err());
+        assert!(K::parse_timespan("").is_err());
+        assert!(K::parse_time("0Nd").is_err());
+    }
+}
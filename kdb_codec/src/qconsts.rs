@@ -153,6 +153,53 @@ pub mod qattribute {
     pub const GROUPED: c_char = 4;
 }
 
+pub mod qprimitive {
+    //! This module provides names of q primitive functions indexed by the id carried on the wire,
+    //!  e.g., `qprimitive::UNARY[2]` is `"neg"` and `qprimitive::BINARY[1]` is `"+"`. Unary id 0 is
+    //!  the general null `(::)`.
+
+    /// Names of unary primitives in the order of their ids. Each corresponds to the monadic form of
+    ///  a q verb, e.g. `flip` is `+:`.
+    pub const UNARY: [&str; 20] = [
+        "::",
+        "flip",
+        "neg",
+        "first",
+        "reciprocal",
+        "where",
+        "reverse",
+        "null",
+        "group",
+        "iasc",
+        "idesc",
+        "string",
+        "enlist",
+        "count",
+        "floor",
+        "not",
+        "key",
+        "distinct",
+        "type",
+        "value",
+    ];
+
+    /// Get a name of a unary primitive from its id. `None` is returned for an unknown id.
+    pub fn unary_name(id: u8) -> Option<&'static str> {
+        UNARY.get(id as usize).copied()
+    }
+
+    /// Names of binary primitives (type 102) in the order of their ids.
+    pub const BINARY: [&str; 20] = [
+        ":", "+", "-", "*", "%", "&", "|", "^", "=", "<", ">", "$", ",", "#", "_", "~", "!", "?",
+        "@", ".",
+    ];
+
+    /// Get a name of a binary primitive from its id. `None` is returned for an unknown id.
+    pub fn binary_name(id: u8) -> Option<&'static str> {
+        BINARY.get(id as usize).copied()
+    }
+}
+
 pub mod qnull_base {
     //! This module provides a list of underlying null values of q objects. The motivation to contain
     //!  them in a module is to tie them up as related items rather than scattered values. Hence user
@@ -33,10 +33,45 @@ impl K {
     ///  header (encoding, message type, compressed, reserved null byte and total message length).
     pub fn q_ipc_encode(&self) -> Vec<u8> {
         let mut stream = Vec::new();
-        serialize_q(self, &mut stream);
+        serialize_q(self, &mut stream, ENCODING);
         stream
     }
 
+    /// Serialize q object to bytes like `q_ipc_encode` but in the given endianness instead of
+    ///  the one of the host.
+    /// # Parameter
+    /// - `encode`: `0` for big endian and `1` for little endian.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// let q_int = K::new_int(1);
+    /// assert_eq!(q_int.q_ipc_encode_with(0), vec![0xfa, 0, 0, 0, 1]);
+    /// assert_eq!(q_int.q_ipc_encode_with(1), vec![0xfa, 1, 0, 0, 0]);
+    /// ```
+    pub fn q_ipc_encode_with(&self, encode: u8) -> Vec<u8> {
+        let mut stream = Vec::new();
+        serialize_q(self, &mut stream, encode);
+        stream
+    }
+
+    /// SHA1 digest of the serialized payload of q object. The payload is always encoded in little
+    ///  endian so that the hash does not depend on the host. Useful as a cache key or for
+    ///  deduplication of messages.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_list = k!(long: vec![1, 2, 3]);
+    ///     assert_eq!(q_list.content_hash(), k!(long: vec![1, 2, 3]).content_hash());
+    ///     assert_ne!(q_list.content_hash(), k!(long: vec![1, 2, 4]).content_hash());
+    /// }
+    /// ```
+    pub fn content_hash(&self) -> [u8; 20] {
+        sha1_smol::Sha1::from(self.q_ipc_encode_with(1)).digest().bytes()
+    }
+
     /// Serialize q object to complete IPC message bytes including the 8-byte IPC message header,
     /// optionally attempting kdb+ IPC compression.
     ///
@@ -83,13 +118,34 @@ impl K {
         out.extend_from_slice(&payload_bytes);
         out
     }
-    
+
+    /// Compute the exact number of bytes `q_ipc_encode` would produce for this object without
+    ///  building the buffer. Useful for checking a message against `max_message_size` before
+    ///  sending it.
+    /// # Note
+    /// Error is returned for an object which cannot be serialized.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_symbol_list = k!(sym: vec!["a", "bc"]);
+    ///     assert_eq!(
+    ///         q_symbol_list.serialized_size().unwrap(),
+    ///         q_symbol_list.q_ipc_encode().len()
+    ///     );
+    /// }
+    /// ```
+    pub fn serialized_size(&self) -> Result<usize> {
+        serialized_size_q(self)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::connection::qmsg_type;
+    use chrono::prelude::*;
 
     fn read_u32(bytes: &[u8]) -> u32 {
         match ENCODING {
@@ -160,6 +216,21 @@ mod tests {
         assert_eq!(&msg[MessageHeader::size()..], payload.as_slice());
     }
 
+    #[test]
+    fn q_ipc_encode_large_symbol_list_roundtrips() {
+        let symbols = (0..1_000_000)
+            .map(|i| format!("sym{}", i % 1000))
+            .collect::<Vec<String>>();
+        let expected_size = 6 + symbols.iter().map(|s| s.len() + 1).sum::<usize>();
+        let k = K::new_symbol_list(symbols.clone(), qattribute::NONE);
+        let payload = k.q_ipc_encode();
+        assert_eq!(payload.len(), expected_size);
+        assert_eq!(payload.len(), k.serialized_size().unwrap());
+
+        let decoded = K::q_ipc_decode(&payload, ENCODING).unwrap();
+        assert_eq!(decoded.as_vec::<S>().unwrap(), &symbols);
+    }
+
     #[test]
     fn ipc_msg_decode_uncompressed_roundtrips() {
         let original = K::new_int(42);
@@ -218,49 +289,321 @@ mod tests {
         let result = K::ipc_msg_decode(&invalid_msg);
         assert!(result.is_err());
     }
+
+    fn assert_dictionary_roundtrips(original: &K, value_type: i8) {
+        let bytes = original.q_ipc_encode();
+        let decoded = K::q_ipc_decode(&bytes, ENCODING).unwrap();
+
+        assert_eq!(decoded.get_type(), original.get_type());
+        assert_eq!(decoded.as_vec::<K>().unwrap()[1].get_type(), value_type);
+        assert_eq!(decoded.q_ipc_encode(), bytes);
+        assert_eq!(format!("{}", decoded), format!("{}", original));
+    }
+
+    #[test]
+    fn sorted_keyed_table_roundtrips() {
+        // `s#`sym xkey ([] sym:`a`b; price:1 2) in little endian
+        #[rustfmt::skip]
+        let bytes = vec![
+            // Sorted dictionary
+            0x7f,
+            // Key table: attribute, dictionary marker, `sym and ,`s#`a`b
+            0x62, 0x00, 0x63,
+            0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, b's', b'y', b'm', 0x00,
+            0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x0b, 0x01, 0x02, 0x00, 0x00, 0x00, b'a', 0x00, b'b', 0x00,
+            // Value table: `price and ,1 2
+            0x62, 0x00, 0x63,
+            0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, b'p', b'r', b'i', b'c', b'e', 0x00,
+            0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x07, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let decoded = K::q_ipc_decode(&bytes, 1).unwrap();
+        assert_eq!(decoded.get_type(), qtype::SORTED_DICTIONARY);
+        let key_value = decoded.as_vec::<K>().unwrap();
+        assert_eq!(key_value[0].get_type(), qtype::TABLE);
+        let sym = key_value[0].get_column("sym").unwrap();
+        assert_eq!(sym.get_attribute(), qattribute::SORTED);
+        assert_eq!(sym.as_vec::<S>().unwrap(), &vec!["a", "b"]);
+        assert_eq!(
+            key_value[1].get_column("price").unwrap().as_vec::<J>().unwrap(),
+            &vec![1, 2]
+        );
+        assert_eq!(decoded.q_ipc_encode_with(1), bytes);
+
+        // Sorted attribute on the key table itself is kept as well
+        let mut bytes = bytes;
+        bytes[2] = qattribute::SORTED as u8;
+        let decoded = K::q_ipc_decode(&bytes, 1).unwrap();
+        assert_eq!(decoded.get_type(), qtype::SORTED_DICTIONARY);
+        assert_eq!(decoded.as_vec::<K>().unwrap()[0].get_attribute(), qattribute::SORTED);
+        assert_eq!(decoded.q_ipc_encode_with(1), bytes);
+    }
+
+    #[test]
+    fn unique_keyed_table_roundtrips() {
+        // ([sym:`u#`a`b] price:1.5 2.5) in little endian
+        #[rustfmt::skip]
+        let bytes = vec![
+            // Dictionary
+            0x63,
+            // Key table: attribute, dictionary marker, `sym and ,`u#`a`b
+            0x62, 0x00, 0x63,
+            0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, b's', b'y', b'm', 0x00,
+            0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x0b, 0x02, 0x02, 0x00, 0x00, 0x00, b'a', 0x00, b'b', 0x00,
+            // Value table: `price and ,1.5 2.5
+            0x62, 0x00, 0x63,
+            0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, b'p', b'r', b'i', b'c', b'e', 0x00,
+            0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x09, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0x3f,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x40,
+        ];
+        let decoded = K::q_ipc_decode(&bytes, 1).unwrap();
+        assert_eq!(decoded.get_type(), qtype::DICTIONARY);
+        assert!(decoded.is_keyed_table());
+        let sym = decoded.as_vec::<K>().unwrap()[0].get_column("sym").unwrap();
+        assert_eq!(sym.get_attribute(), qattribute::UNIQUE);
+        assert_eq!(decoded.q_ipc_encode_with(1), bytes);
+
+        // Keying a table with a unique column keeps the attribute as well
+        let table = K::new_dictionary(
+            K::new_symbol_list(vec!["sym".to_string(), "price".to_string()], qattribute::NONE),
+            K::new_compound_list(vec![
+                K::new_symbol_list(vec!["a".to_string(), "b".to_string()], qattribute::UNIQUE),
+                K::new_float_list(vec![1.5, 2.5], qattribute::NONE),
+            ]),
+        )
+        .unwrap()
+        .flip()
+        .unwrap();
+        assert_eq!(table.enkey(1).unwrap().q_ipc_encode_with(1), bytes);
+    }
+
+    #[test]
+    fn content_hash_follows_structure() {
+        let table = || {
+            K::new_dictionary(
+                K::new_symbol_list(vec!["sym".to_string(), "price".to_string()], qattribute::NONE),
+                K::new_compound_list(vec![
+                    K::new_symbol_list(vec!["a".to_string(), "b".to_string()], qattribute::NONE),
+                    K::new_float_list(vec![1.5, 2.5], qattribute::NONE),
+                ]),
+            )
+            .unwrap()
+            .flip()
+            .unwrap()
+        };
+        assert_eq!(table().content_hash(), table().content_hash());
+        assert_eq!(
+            table().content_hash(),
+            sha1_smol::Sha1::from(table().q_ipc_encode_with(1)).digest().bytes()
+        );
+
+        // Different values, types or attributes give a different hash
+        let q_long_list = K::new_long_list(vec![1, 2, 3], qattribute::NONE);
+        assert_ne!(q_long_list.content_hash(), table().content_hash());
+        assert_ne!(
+            q_long_list.content_hash(),
+            K::new_long_list(vec![1, 2, 4], qattribute::NONE).content_hash()
+        );
+        assert_ne!(
+            q_long_list.content_hash(),
+            K::new_int_list(vec![1, 2, 3], qattribute::NONE).content_hash()
+        );
+        assert_ne!(
+            q_long_list.content_hash(),
+            K::new_long_list(vec![1, 2, 3], qattribute::SORTED).content_hash()
+        );
+    }
+
+    #[test]
+    fn dictionary_with_typed_list_values_roundtrips() {
+        let original = K::new_dictionary(
+            K::new_symbol_list(vec!["a".to_string(), "b".to_string()], qattribute::NONE),
+            K::new_long_list(vec![10, 20], qattribute::NONE),
+        )
+        .unwrap();
+        assert_dictionary_roundtrips(&original, qtype::LONG_LIST);
+    }
+
+    #[test]
+    fn dictionary_with_compound_list_values_roundtrips() {
+        let original = K::new_dictionary(
+            K::new_symbol_list(
+                vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                qattribute::NONE,
+            ),
+            K::new_compound_list(vec![
+                K::new_long(1),
+                K::new_symbol("x".to_string()),
+                K::new_float(2.5),
+            ]),
+        )
+        .unwrap();
+        assert_dictionary_roundtrips(&original, qtype::COMPOUND_LIST);
+
+        // Compound list of same-typed atoms must stay compound
+        let original = K::new_dictionary(
+            K::new_symbol_list(vec!["a".to_string(), "b".to_string()], qattribute::NONE),
+            K::new_compound_list(vec![K::new_long(1), K::new_long(2)]),
+        )
+        .unwrap();
+        assert_dictionary_roundtrips(&original, qtype::COMPOUND_LIST);
+    }
+
+    #[test]
+    fn dictionary_with_nested_dictionary_values_roundtrips() {
+        let inner = K::new_dictionary(
+            K::new_symbol_list(vec!["x".to_string(), "y".to_string()], qattribute::NONE),
+            K::new_int_list(vec![1, 2], qattribute::NONE),
+        )
+        .unwrap();
+        let original = K::new_dictionary(
+            K::new_symbol_list(vec!["a".to_string(), "b".to_string()], qattribute::NONE),
+            inner,
+        )
+        .unwrap();
+        assert_dictionary_roundtrips(&original, qtype::DICTIONARY);
+    }
+
+    #[test]
+    fn compound_list_with_general_null_roundtrips() {
+        let original = K::new_compound_list(vec![
+            K::new_long(1),
+            K::new_null(),
+            K::new_symbol("sym".to_string()),
+        ]);
+        let bytes = original.q_ipc_encode();
+        // (::) is encoded as unary primitive id 0
+        assert_eq!(&bytes[15..17], &[qtype::NULL as u8, 0x00]);
+
+        let decoded = K::q_ipc_decode(&bytes, ENCODING).unwrap();
+        let elements = decoded.as_vec::<K>().unwrap();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0].get_long().unwrap(), 1);
+        assert_eq!(elements[1].get_type(), qtype::NULL);
+        assert_eq!(elements[1].unary_primitive_id().unwrap(), 0);
+        assert_eq!(elements[2].get_symbol().unwrap(), "sym");
+        assert_eq!(decoded.q_ipc_encode(), bytes);
+        assert_eq!(format!("{}", decoded), format!("{}", original));
+    }
+
+    #[test]
+    fn serialized_size_matches_encoded_length() {
+        let atom = K::new_long(42);
+        assert_eq!(atom.serialized_size().unwrap(), atom.q_ipc_encode().len());
+
+        let symbol_list = K::new_symbol_list(
+            vec!["a".to_string(), "bc".to_string(), "".to_string()],
+            qattribute::NONE,
+        );
+        assert_eq!(
+            symbol_list.serialized_size().unwrap(),
+            symbol_list.q_ipc_encode().len()
+        );
+
+        let table = K::new_dictionary(
+            K::new_symbol_list(
+                vec!["time".to_string(), "sym".to_string(), "note".to_string()],
+                qattribute::NONE,
+            ),
+            K::new_compound_list(vec![
+                K::new_timestamp_list(vec![Utc::now(), Utc::now()], qattribute::NONE),
+                K::new_symbol_list(vec!["x".to_string(), "yy".to_string()], qattribute::NONE),
+                K::new_compound_list(vec![
+                    K::new_string("héllo".to_string(), qattribute::NONE),
+                    K::new_null(),
+                ]),
+            ]),
+        )
+        .unwrap()
+        .flip()
+        .unwrap();
+        assert_eq!(table.serialized_size().unwrap(), table.q_ipc_encode().len());
+
+        // Unsupported or inconsistent objects give an error instead of a panic
+        let unknown = K::new(77, qattribute::NONE, k0_inner::null(()));
+        assert!(matches!(
+            unknown.serialized_size(),
+            Err(Error::InvalidOperation { .. })
+        ));
+        let inconsistent = K::new(qtype::SYMBOL_ATOM, qattribute::NONE, k0_inner::long(1));
+        assert!(inconsistent.serialized_size().is_err());
+    }
+
+    #[test]
+    fn datetime_list_roundtrips_as_datetime() {
+        let datetimes = vec![
+            Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+            Utc.with_ymd_and_hms(1999, 12, 31, 23, 59, 59).unwrap(),
+        ];
+        let original = K::new_datetime_list(datetimes.clone(), qattribute::NONE);
+        let bytes = original.q_ipc_encode();
+        assert_eq!(bytes[0], qtype::DATETIME_LIST as u8);
+
+        let mut decoded = K::q_ipc_decode(&bytes, ENCODING).unwrap();
+        assert_eq!(decoded.get_type(), qtype::DATETIME_LIST);
+        assert_eq!(format!("{}", decoded), format!("{}", original));
+
+        // Elements are datetimes, not floats
+        let dictionary = K::new_dictionary(
+            K::new_symbol_list(vec!["a".to_string(), "b".to_string()], qattribute::NONE),
+            decoded.clone(),
+        )
+        .unwrap();
+        let element = dictionary.try_find_owned(&K::new_symbol("b".to_string())).unwrap();
+        assert_eq!(element.get_type(), qtype::DATETIME_ATOM);
+        assert_eq!(element.get_datetime().unwrap(), datetimes[1]);
+        assert_eq!(decoded.remove_datetime(0).unwrap(), datetimes[0]);
+        assert_eq!(decoded.pop().unwrap().get_datetime().unwrap(), datetimes[1]);
+    }
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Private Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-fn serialize_q(obj: &K, stream: &mut Vec<u8>) {
+fn serialize_q(obj: &K, stream: &mut Vec<u8>, encode: u8) {
     match obj.0.qtype {
         qtype::BOOL_ATOM | qtype::BYTE_ATOM | qtype::CHAR => serialize_byte(obj, stream),
         qtype::GUID_ATOM => serialize_guid(obj, stream),
-        qtype::SHORT_ATOM => serialize_short(obj, stream),
+        qtype::SHORT_ATOM => serialize_short(obj, stream, encode),
         qtype::INT_ATOM
         | qtype::MONTH_ATOM
         | qtype::DATE_ATOM
         | qtype::MINUTE_ATOM
         | qtype::SECOND_ATOM
-        | qtype::TIME_ATOM => serialize_int(obj, stream),
+        | qtype::TIME_ATOM => serialize_int(obj, stream, encode),
         qtype::LONG_ATOM | qtype::TIMESTAMP_ATOM | qtype::TIMESPAN_ATOM => {
-            serialize_long(obj, stream)
+            serialize_long(obj, stream, encode)
         }
-        qtype::REAL_ATOM => serialize_real(obj, stream),
-        qtype::FLOAT_ATOM | qtype::DATETIME_ATOM => serialize_float(obj, stream),
+        qtype::REAL_ATOM => serialize_real(obj, stream, encode),
+        qtype::FLOAT_ATOM | qtype::DATETIME_ATOM => serialize_float(obj, stream, encode),
         qtype::SYMBOL_ATOM => serialize_symbol(obj, stream),
-        qtype::COMPOUND_LIST => serialize_compound_list(obj, stream),
-        qtype::BOOL_LIST | qtype::BYTE_LIST => serialize_byte_list(obj, stream),
-        qtype::GUID_LIST => serialize_guid_list(obj, stream),
-        qtype::SHORT_LIST => serialize_short_list(obj, stream),
+        qtype::COMPOUND_LIST => serialize_compound_list(obj, stream, encode),
+        qtype::BOOL_LIST | qtype::BYTE_LIST => serialize_byte_list(obj, stream, encode),
+        qtype::GUID_LIST => serialize_guid_list(obj, stream, encode),
+        qtype::SHORT_LIST => serialize_short_list(obj, stream, encode),
         qtype::INT_LIST
         | qtype::MONTH_LIST
         | qtype::DATE_LIST
         | qtype::MINUTE_LIST
         | qtype::SECOND_LIST
-        | qtype::TIME_LIST => serialize_int_list(obj, stream),
+        | qtype::TIME_LIST => serialize_int_list(obj, stream, encode),
         qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
-            serialize_long_list(obj, stream)
+            serialize_long_list(obj, stream, encode)
         }
-        qtype::REAL_LIST => serialize_real_list(obj, stream),
-        qtype::FLOAT_LIST | qtype::DATETIME_LIST => serialize_float_list(obj, stream),
-        qtype::STRING => serialize_string(obj, stream),
-        qtype::SYMBOL_LIST => serialize_symbol_list(obj, stream),
-        qtype::TABLE => serialize_table(obj, stream),
-        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => serialize_dictionary(obj, stream),
-        qtype::LAMBDA => serialize_lambda(obj, stream),
+        qtype::REAL_LIST => serialize_real_list(obj, stream, encode),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => serialize_float_list(obj, stream, encode),
+        qtype::STRING => serialize_string(obj, stream, encode),
+        qtype::SYMBOL_LIST => serialize_symbol_list(obj, stream, encode),
+        qtype::TABLE => serialize_table(obj, stream, encode),
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => serialize_dictionary(obj, stream, encode),
+        qtype::LAMBDA => serialize_lambda(obj, stream, encode),
         qtype::UNARY_PRIMITIVE => serialize_unary_primitive_or_null(obj, stream),
         qtype::BINARY_PRIMITIVE => serialize_opaque_payload_type(obj, stream),
         qtype::PROJECTION => serialize_opaque_payload_type(obj, stream),
@@ -276,6 +619,94 @@ fn serialize_q(obj: &K, stream: &mut Vec<u8>) {
     };
 }
 
+/// Byte length of the type, attribute and length prefix of a list.
+const LIST_HEADER_SIZE: usize = 6;
+
+fn serialized_size_q(obj: &K) -> Result<usize> {
+    let size = match obj.0.qtype {
+        qtype::BOOL_ATOM | qtype::BYTE_ATOM | qtype::CHAR => 2,
+        qtype::GUID_ATOM => 17,
+        qtype::SHORT_ATOM => 3,
+        qtype::INT_ATOM
+        | qtype::MONTH_ATOM
+        | qtype::DATE_ATOM
+        | qtype::MINUTE_ATOM
+        | qtype::SECOND_ATOM
+        | qtype::TIME_ATOM
+        | qtype::REAL_ATOM => 5,
+        qtype::LONG_ATOM
+        | qtype::TIMESTAMP_ATOM
+        | qtype::TIMESPAN_ATOM
+        | qtype::FLOAT_ATOM
+        | qtype::DATETIME_ATOM => 9,
+        qtype::SYMBOL_ATOM => 2 + obj.get_symbol()?.len(),
+        qtype::COMPOUND_LIST => {
+            LIST_HEADER_SIZE
+                + obj
+                    .as_vec::<K>()?
+                    .iter()
+                    .map(serialized_size_q)
+                    .sum::<Result<usize>>()?
+        }
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => LIST_HEADER_SIZE + obj.len(),
+        qtype::GUID_LIST => LIST_HEADER_SIZE + 16 * obj.len(),
+        qtype::SHORT_LIST => LIST_HEADER_SIZE + 2 * obj.len(),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST
+        | qtype::REAL_LIST => LIST_HEADER_SIZE + 4 * obj.len(),
+        qtype::LONG_LIST
+        | qtype::TIMESTAMP_LIST
+        | qtype::TIMESPAN_LIST
+        | qtype::FLOAT_LIST
+        | qtype::DATETIME_LIST => LIST_HEADER_SIZE + 8 * obj.len(),
+        qtype::SYMBOL_LIST => {
+            LIST_HEADER_SIZE
+                + obj
+                    .as_vec::<S>()?
+                    .iter()
+                    .map(|symbol| symbol.len() + 1)
+                    .sum::<usize>()
+        }
+        qtype::TABLE => {
+            // Type, attribute and dictionary marker
+            let vector = obj.get_dictionary()?.as_vec::<K>()?;
+            3 + serialized_size_q(&vector[0])? + serialized_size_q(&vector[1])?
+        }
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+            let vector = obj.as_vec::<K>()?;
+            1 + serialized_size_q(&vector[0])? + serialized_size_q(&vector[1])?
+        }
+        qtype::LAMBDA => {
+            let (context, body) = obj.as_lambda()?;
+            // Type, context with null terminator and char vector
+            1 + context.len() + 1 + LIST_HEADER_SIZE + body.len()
+        }
+        qtype::UNARY_PRIMITIVE => match &obj.0.value {
+            k0_inner::opaque(payload) => 1 + payload.len(),
+            _ => 2,
+        },
+        qtype::BINARY_PRIMITIVE
+        | qtype::PROJECTION
+        | qtype::COMPOSITION
+        | qtype::EACH
+        | qtype::OVER
+        | qtype::SCAN
+        | qtype::EACH_PRIOR
+        | qtype::EACH_LEFT
+        | qtype::EACH_RIGHT
+        | qtype::FOREIGN => match &obj.0.value {
+            k0_inner::opaque(payload) => 1 + payload.len(),
+            _ => 1,
+        },
+        qtype => return Err(Error::invalid_operation("serialized_size", qtype, None)),
+    };
+    Ok(size)
+}
+
 fn serialize_unary_primitive_or_null(obj: &K, stream: &mut Vec<u8>) {
     // Type
     stream.push(qtype::UNARY_PRIMITIVE as u8);
@@ -310,7 +741,7 @@ fn serialize_opaque_payload_type(obj: &K, stream: &mut Vec<u8>) {
     }
 }
 
-fn serialize_lambda(lambda: &K, stream: &mut Vec<u8>) {
+fn serialize_lambda(lambda: &K, stream: &mut Vec<u8>, encode: u8) {
     let (context, body) = lambda.as_lambda().unwrap();
 
     // Type
@@ -325,7 +756,7 @@ fn serialize_lambda(lambda: &K, stream: &mut Vec<u8>) {
     stream.push(qattribute::NONE as u8);
 
     let bytes = body.as_bytes();
-    let length = match ENCODING {
+    let length = match encode {
         0 => (bytes.len() as u32).to_be_bytes(),
         _ => (bytes.len() as u32).to_le_bytes(),
     };
@@ -347,51 +778,51 @@ fn serialize_byte(byte: &K, stream: &mut Vec<u8>) {
     stream.push(byte.get_byte().unwrap());
 }
 
-fn serialize_short(short: &K, stream: &mut Vec<u8>) {
+fn serialize_short(short: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0xfb);
     // Element
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => short.get_short().unwrap().to_be_bytes(),
         _ => short.get_short().unwrap().to_le_bytes(),
     });
 }
 
-fn serialize_int(int: &K, stream: &mut Vec<u8>) {
+fn serialize_int(int: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(int.0.qtype as u8);
     // Element
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => int.get_int().unwrap().to_be_bytes(),
         _ => int.get_int().unwrap().to_le_bytes(),
     });
 }
 
-fn serialize_long(long: &K, stream: &mut Vec<u8>) {
+fn serialize_long(long: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(long.0.qtype as u8);
     // Element
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => long.get_long().unwrap().to_be_bytes(),
         _ => long.get_long().unwrap().to_le_bytes(),
     });
 }
 
-fn serialize_real(real: &K, stream: &mut Vec<u8>) {
+fn serialize_real(real: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0xf8);
     // Element
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => real.get_real().unwrap().to_be_bytes(),
         _ => real.get_real().unwrap().to_le_bytes(),
     });
 }
 
-fn serialize_float(float: &K, stream: &mut Vec<u8>) {
+fn serialize_float(float: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(float.0.qtype as u8);
     // Element
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => float.get_float().unwrap().to_be_bytes(),
         _ => float.get_float().unwrap().to_le_bytes(),
     });
@@ -406,7 +837,7 @@ fn serialize_symbol(symbol: &K, stream: &mut Vec<u8>) {
     stream.push(0x00);
 }
 
-fn serialize_guid_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_guid_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0x02);
     // Attribute
@@ -414,7 +845,7 @@ fn serialize_guid_list(list: &K, stream: &mut Vec<u8>) {
     // Length and data
     let vector = list.as_vec::<U>().unwrap();
     // Length of vector
-    let length = match ENCODING {
+    let length = match encode {
         0 => (vector.len() as u32).to_be_bytes(),
         _ => (vector.len() as u32).to_le_bytes(),
     };
@@ -424,7 +855,7 @@ fn serialize_guid_list(list: &K, stream: &mut Vec<u8>) {
         .for_each(|element| stream.extend_from_slice(element));
 }
 
-fn serialize_byte_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_byte_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(list.0.qtype as u8);
     // Attribute
@@ -432,7 +863,7 @@ fn serialize_byte_list(list: &K, stream: &mut Vec<u8>) {
     // Length and data
     let vector = list.as_vec::<G>().unwrap();
     // Length of vector
-    let length = match ENCODING {
+    let length = match encode {
         0 => (vector.len() as u32).to_be_bytes(),
         _ => (vector.len() as u32).to_le_bytes(),
     };
@@ -440,14 +871,14 @@ fn serialize_byte_list(list: &K, stream: &mut Vec<u8>) {
     stream.extend_from_slice(vector.as_slice());
 }
 
-fn serialize_short_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_short_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0x05);
     // Attribute
     stream.push(list.0.attribute as u8);
     // Length and data
     let vector = list.as_vec::<H>().unwrap();
-    match ENCODING {
+    match encode {
         0 => {
             // Length of vector
             stream.extend_from_slice(&(vector.len() as u32).to_be_bytes());
@@ -467,14 +898,14 @@ fn serialize_short_list(list: &K, stream: &mut Vec<u8>) {
     }
 }
 
-fn serialize_int_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_int_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(list.0.qtype as u8);
     // Attribute
     stream.push(list.0.attribute as u8);
     // Length and data
     let vector = list.as_vec::<I>().unwrap();
-    match ENCODING {
+    match encode {
         0 => {
             // Length of vector
             stream.extend_from_slice(&(vector.len() as u32).to_be_bytes());
@@ -494,14 +925,14 @@ fn serialize_int_list(list: &K, stream: &mut Vec<u8>) {
     }
 }
 
-fn serialize_long_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_long_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(list.0.qtype as u8);
     // Attribute
     stream.push(list.0.attribute as u8);
     // Length and data
     let vector = list.as_vec::<J>().unwrap();
-    match ENCODING {
+    match encode {
         0 => {
             // Length of vector
             stream.extend_from_slice(&(vector.len() as u32).to_be_bytes());
@@ -521,14 +952,14 @@ fn serialize_long_list(list: &K, stream: &mut Vec<u8>) {
     }
 }
 
-fn serialize_real_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_real_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0x08);
     // Attribute
     stream.push(list.0.attribute as u8);
     // Length and data
     let vector = list.as_vec::<E>().unwrap();
-    match ENCODING {
+    match encode {
         0 => {
             // Length of vector
             stream.extend_from_slice(&(vector.len() as u32).to_be_bytes());
@@ -548,14 +979,14 @@ fn serialize_real_list(list: &K, stream: &mut Vec<u8>) {
     }
 }
 
-fn serialize_float_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_float_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(list.0.qtype as u8);
     // Attribute
     stream.push(list.0.attribute as u8);
     // Length and data
     let vector = list.as_vec::<F>().unwrap();
-    match ENCODING {
+    match encode {
         0 => {
             // Length of vector
             stream.extend_from_slice(&(vector.len() as u32).to_be_bytes());
@@ -575,7 +1006,7 @@ fn serialize_float_list(list: &K, stream: &mut Vec<u8>) {
     }
 }
 
-fn serialize_string(list: &K, stream: &mut Vec<u8>) {
+fn serialize_string(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0x0a);
     // Attribute
@@ -583,7 +1014,7 @@ fn serialize_string(list: &K, stream: &mut Vec<u8>) {
     // Length and data
     let vector = list.as_string().unwrap().as_bytes();
     // Length of vector
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => (vector.len() as u32).to_be_bytes(),
         _ => (vector.len() as u32).to_le_bytes(),
     });
@@ -591,7 +1022,7 @@ fn serialize_string(list: &K, stream: &mut Vec<u8>) {
     stream.extend_from_slice(&vector);
 }
 
-fn serialize_symbol_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_symbol_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0x0b);
     // Attribute
@@ -599,18 +1030,20 @@ fn serialize_symbol_list(list: &K, stream: &mut Vec<u8>) {
     // Length and data
     let vector = list.as_vec::<S>().unwrap();
     // Length of vector
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => (vector.len() as u32).to_be_bytes(),
         _ => (vector.len() as u32).to_le_bytes(),
     });
-    // Data
+    // Data. Reserve once for all symbols and their null terminators to avoid reallocations
+    //  on large columns.
+    stream.reserve(vector.iter().map(|element| element.len()).sum::<usize>() + vector.len());
     vector.iter().for_each(|element| {
         stream.extend_from_slice(&element.as_bytes());
         stream.push(0x00);
     });
 }
 
-fn serialize_compound_list(list: &K, stream: &mut Vec<u8>) {
+fn serialize_compound_list(list: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(list.0.qtype as u8);
     // Attribute
@@ -618,17 +1051,17 @@ fn serialize_compound_list(list: &K, stream: &mut Vec<u8>) {
     // Length and data
     let vector = list.as_vec::<K>().unwrap();
     // Length and data
-    stream.extend_from_slice(&match ENCODING {
+    stream.extend_from_slice(&match encode {
         0 => (vector.len() as u32).to_be_bytes(),
         _ => (vector.len() as u32).to_le_bytes(),
     });
     // Data
     vector.iter().for_each(|element| {
-        serialize_q(element, stream);
+        serialize_q(element, stream, encode);
     });
 }
 
-fn serialize_table(table: &K, stream: &mut Vec<u8>) {
+fn serialize_table(table: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(0x62);
     // Attribute (e.g. `s#` for sorted table)
@@ -638,20 +1071,20 @@ fn serialize_table(table: &K, stream: &mut Vec<u8>) {
     // Retrieve underying dictionary
     let vector = table.get_dictionary().unwrap().as_vec::<K>().unwrap();
     // Serialize keys
-    serialize_symbol_list(&vector[0], stream);
+    serialize_symbol_list(&vector[0], stream, encode);
     // Serialize values
-    serialize_compound_list(&vector[1], stream);
+    serialize_compound_list(&vector[1], stream, encode);
 }
 
-fn serialize_dictionary(dictionary: &K, stream: &mut Vec<u8>) {
+fn serialize_dictionary(dictionary: &K, stream: &mut Vec<u8>, encode: u8) {
     // Type
     stream.push(dictionary.0.qtype as u8);
     // Data
     let vector = dictionary.as_vec::<K>().unwrap();
     // Serialize keys
-    serialize_q(&vector[0], stream);
+    serialize_q(&vector[0], stream, encode);
     // Serialize values
-    serialize_q(&vector[1], stream);
+    serialize_q(&vector[1], stream, encode);
 }
 
 fn serialize_null(stream: &mut Vec<u8>) {
@@ -0,0 +1,104 @@
+//! # Transport Compression
+//!
+//! This module provides an optional compression layer wrapping the whole byte stream of a
+//! connection. It is distinct from the kdb+ `compressed` flag in the message header and is only
+//! meant for non-standard gateways which compress IPC traffic at the transport level.
+//! A q process never speaks it.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use bytes::{BufMut, BytesMut};
+use flate2::write::{GzDecoder, GzEncoder};
+use flate2::Compression;
+use std::io::{self, Write};
+use std::{fmt, mem};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Enums
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Compression algorithm applied to the whole byte stream by a gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransportCompression {
+    /// A single gzip member spanning the lifetime of the connection. Each outgoing message is
+    ///  sync-flushed so that the peer can decode it without waiting for more data.
+    Gzip,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Streaming compressor and decompressor of one connection.
+pub(crate) struct TransportLayer {
+    /// Algorithm of the stream.
+    compression: TransportCompression,
+    /// Compressor of outgoing bytes. Compressed output is collected in the inner vector.
+    encoder: GzEncoder<Vec<u8>>,
+    /// Decompressor of incoming bytes. Decompressed output is collected in the inner vector.
+    decoder: GzDecoder<Vec<u8>>,
+    /// Decompressed bytes not consumed by the kdb+ decoder yet.
+    plain: BytesMut,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl TransportLayer {
+    /// Start a new compressed stream in each direction.
+    pub(crate) fn new(compression: TransportCompression) -> Self {
+        TransportLayer {
+            compression,
+            encoder: GzEncoder::new(Vec::new(), Compression::default()),
+            decoder: GzDecoder::new(Vec::new()),
+            plain: BytesMut::new(),
+        }
+    }
+
+    /// Algorithm of the stream.
+    pub(crate) fn compression(&self) -> TransportCompression {
+        self.compression
+    }
+
+    /// Compress a complete IPC frame and write the compressed bytes to `dst`.
+    pub(crate) fn compress(&mut self, frame: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        self.encoder.write_all(frame)?;
+        self.encoder.flush()?;
+        let compressed = mem::take(self.encoder.get_mut());
+        dst.put_slice(&compressed);
+        Ok(())
+    }
+
+    /// Consume all compressed bytes in `src` and return the buffer of decompressed bytes which
+    ///  the kdb+ decoder reads frames from.
+    pub(crate) fn decompress(&mut self, src: &mut BytesMut) -> io::Result<&mut BytesMut> {
+        if !src.is_empty() {
+            self.decoder.write_all(src)?;
+            self.decoder.flush()?;
+            src.clear();
+            let decompressed = mem::take(self.decoder.get_mut());
+            self.plain.put_slice(&decompressed);
+        }
+        Ok(&mut self.plain)
+    }
+}
+
+/// A clone starts fresh streams; the state of a stream cannot be shared with another connection.
+impl Clone for TransportLayer {
+    fn clone(&self) -> Self {
+        TransportLayer::new(self.compression)
+    }
+}
+
+impl fmt::Debug for TransportLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportLayer")
+            .field("compression", &self.compression)
+            .field("pending", &self.plain.len())
+            .finish()
+    }
+}
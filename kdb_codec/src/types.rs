@@ -318,6 +318,11 @@ impl k0_list {
             G0: Box::new(array),
         }
     }
+
+    /// Get a length of the list.
+    pub(crate) fn len(&self) -> usize {
+        self.n as usize
+    }
 }
 
 impl Clone for k0_list {
@@ -1225,6 +1230,85 @@ impl K {
         )
     }
 
+    /// Construct q compound list directly from an iterator of `K` objects.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_compound_list = K::compound_from_iter((1..=3).map(K::new_long));
+    ///     assert_eq!(format!("{}", q_compound_list), String::from("(1;2;3)"));
+    /// }
+    /// ```
+    pub fn compound_from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        K::new_compound_list(iter.into_iter().collect())
+    }
+
+    /// Construct q compound list from an iterator of fallible `K` objects. The first error stops
+    ///  the iteration and is returned.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let items = vec![K::new_long(1), K::new_symbol(String::from("a"))];
+    ///     // Symbol cannot be read as long
+    ///     let q_compound_list = K::compound_from_iter_result(
+    ///         items.iter().map(|item| item.get_long().map(K::new_long)),
+    ///     );
+    ///     assert!(q_compound_list.is_err());
+    /// }
+    /// ```
+    pub fn compound_from_iter_result<I: IntoIterator<Item = Result<K>>>(iter: I) -> Result<Self> {
+        Ok(K::new_compound_list(
+            iter.into_iter().collect::<Result<Vec<K>>>()?,
+        ))
+    }
+
+    /// Construct an empty list of the given list type, e.g. to build a column with
+    ///  [`push`](#method.push).
+    /// # Parameters
+    /// - `qtype`: List type (`qtype::COMPOUND_LIST` to `qtype::TIME_LIST`).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    /// use chrono::prelude::*;
+    ///
+    /// fn main() {
+    ///     let mut q_date_list = K::empty_list(qtype::DATE_LIST).unwrap();
+    ///     assert_eq!(format!("{}", q_date_list), String::from("`date$()"));
+    ///     q_date_list.push(&NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()).unwrap();
+    ///     assert_eq!(format!("{}", q_date_list), String::from(",2000.01.01"));
+    /// }
+    /// ```
+    /// # Note
+    /// Error is returned for a type other than a list type.
+    pub fn empty_list(qtype: i8) -> Result<Self> {
+        let inner = match qtype {
+            qtype::BOOL_LIST | qtype::BYTE_LIST => k0_inner::list(k0_list::new(Vec::<G>::new())),
+            qtype::GUID_LIST => k0_inner::list(k0_list::new(Vec::<U>::new())),
+            qtype::SHORT_LIST => k0_inner::list(k0_list::new(Vec::<H>::new())),
+            qtype::INT_LIST
+            | qtype::MONTH_LIST
+            | qtype::DATE_LIST
+            | qtype::MINUTE_LIST
+            | qtype::SECOND_LIST
+            | qtype::TIME_LIST => k0_inner::list(k0_list::new(Vec::<I>::new())),
+            qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+                k0_inner::list(k0_list::new(Vec::<J>::new()))
+            }
+            qtype::REAL_LIST => k0_inner::list(k0_list::new(Vec::<E>::new())),
+            qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+                k0_inner::list(k0_list::new(Vec::<F>::new()))
+            }
+            qtype::STRING => k0_inner::symbol(String::new()),
+            qtype::SYMBOL_LIST => k0_inner::list(k0_list::new(Vec::<S>::new())),
+            qtype::COMPOUND_LIST => k0_inner::list(k0_list::new(Vec::<K>::new())),
+            _ => return Err(Error::invalid_operation("empty_list", qtype, None)),
+        };
+        Ok(K::new(qtype, qattribute::NONE, inner))
+    }
+
     /// Construct q dictionary from a pair of keys (`K`) and values (`K`).
     /// # Example
     /// ```
@@ -1262,6 +1346,39 @@ impl K {
         }
     }
 
+    /// Construct q dictionary with symbol keys and a compound list of values from key-value pairs.
+    ///  Unlike [`new_dictionary`](#method.new_dictionary), this never fails on heterogeneous values
+    ///  because values are always held in a compound list.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_dictionary = K::dict_str_keys(vec![
+    ///         ("size", K::new_long(100)),
+    ///         ("side", K::new_symbol(String::from("buy"))),
+    ///     ]);
+    ///     assert_eq!(
+    ///         format!("{}", q_dictionary),
+    ///         String::from("`size`side!(100;`buy)")
+    ///     );
+    /// }
+    /// ```
+    pub fn dict_str_keys(pairs: Vec<(&str, K)>) -> Self {
+        let (keys, values): (Vec<S>, Vec<K>) = pairs
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .unzip();
+        K::new(
+            qtype::DICTIONARY,
+            qattribute::NONE,
+            k0_inner::list(k0_list::new(vec![
+                K::new_symbol_list(keys, qattribute::NONE),
+                K::new_compound_list(values),
+            ])),
+        )
+    }
+
     /// Construct q null.
     /// # Example
     /// ```
@@ -1893,6 +2010,37 @@ impl K {
         }
     }
 
+    /// Borrow the bytes of a byte list, a char or a string without copying them, e.g. to feed a
+    ///  received byte vector straight into a parser.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_byte_list = K::new_byte_list(vec![0x3c, 0x22, 0xf4], qattribute::NONE);
+    ///     assert_eq!(q_byte_list.as_bytes().unwrap(), &[0x3c, 0x22, 0xf4]);
+    ///
+    ///     let q_string = K::new_string(String::from("kdb+"), qattribute::NONE);
+    ///     assert_eq!(q_string.as_bytes().unwrap(), b"kdb+");
+    ///
+    ///     assert!(K::new_long(42).as_bytes().is_err());
+    /// }
+    /// ```
+    pub fn as_bytes(&self) -> Result<&[u8]> {
+        match (self.0.qtype, &self.0.value) {
+            (qtype::BYTE_LIST, k0_inner::list(list)) => {
+                match list.G0.as_any().downcast_ref::<Vec<G>>() {
+                    Some(bytes) => Ok(bytes),
+                    _ => Err(Error::invalid_cast_list(qtype::BYTE_LIST)),
+                }
+            }
+            (qtype::CHAR, k0_inner::byte(ch)) => Ok(std::slice::from_ref(ch)),
+            (qtype::STRING, k0_inner::symbol(string)) => Ok(string.as_bytes()),
+            // Other types, or a byte list, char or string holding an inconsistent value
+            _ => Err(Error::invalid_operation("as_bytes", self.0.qtype, None)),
+        }
+    }
+
     /// Get the underlying lambda parts `(context, body)`.
     pub fn as_lambda(&self) -> Result<(&str, &str)> {
         match self.0.qtype {
@@ -1906,6 +2054,35 @@ impl K {
         }
     }
 
+    /// Get the id of a unary primitive function. The general null `(::)` has id 0. The name of the
+    ///  primitive can be looked up with [`qprimitive::unary_name`](qprimitive/fn.unary_name.html).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     // neg
+    ///     let q_neg = K::q_ipc_decode(&[101, 2], 1).unwrap();
+    ///     let id = q_neg.unary_primitive_id().unwrap();
+    ///     assert_eq!(id, 2);
+    ///     assert_eq!(qprimitive::unary_name(id), Some("neg"));
+    ///
+    ///     assert_eq!(K::new_null().unary_primitive_id().unwrap(), 0);
+    /// }
+    /// ```
+    pub fn unary_primitive_id(&self) -> Result<u8> {
+        match self.0.qtype {
+            qtype::UNARY_PRIMITIVE => match &self.0.value {
+                k0_inner::null(()) => Ok(0),
+                k0_inner::opaque(payload) if payload.len() == 1 => Ok(payload[0]),
+                _ => Err(Error::DeserializationError(
+                    "inconsistent K object for UNARY_PRIMITIVE".to_string(),
+                )),
+            },
+            _ => Err(Error::invalid_cast(self.0.qtype, qtype::UNARY_PRIMITIVE)),
+        }
+    }
+
     /// Get underlying mutable `String` value.
     /// # Example
     /// ```ignore
@@ -2034,6 +2211,134 @@ impl K {
         }
     }
 
+    /// Iterate over the elements of a compound list, or over the keys and the values of a
+    ///  dictionary.
+    /// # Note
+    /// Error is returned for other types. Elements of a typed list such as a long list are not `K`
+    ///  objects; use [`as_vec`](#method.as_vec) with the element type instead, e.g.
+    ///  `as_vec::<J>()`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let q_compound = k!([k!(long: 1), k!(sym: "a"), k!(string: "bc")]);
+    ///     let mut types = Vec::new();
+    ///     for element in q_compound.iter()? {
+    ///         types.push(element.get_type());
+    ///     }
+    ///     assert_eq!(types, vec![qtype::LONG_ATOM, qtype::SYMBOL_ATOM, qtype::STRING]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn iter(&self) -> Result<std::slice::Iter<'_, K>> {
+        match self.0.qtype {
+            qtype::COMPOUND_LIST | qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+                Ok(self.as_vec::<K>()?.iter())
+            }
+            qtype => Err(Error::invalid_operation(
+                "iter",
+                qtype,
+                Some(qtype::COMPOUND_LIST),
+            )),
+        }
+    }
+
+    /// Iterate mutably over the elements of a compound list, or over the keys and the values of a
+    ///  dictionary.
+    /// # Note
+    /// Error is returned for other types as in [`iter`](#method.iter). Replacing the keys or the
+    ///  values of a dictionary with a list of another length breaks the dictionary.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut q_compound = k!([k!(long: 1), k!(long: 2)]);
+    ///     for element in q_compound.iter_mut()? {
+    ///         *element = K::new_long(element.get_long()? * 10);
+    ///     }
+    ///     assert_eq!(format!("{}", q_compound), String::from("(10;20)"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn iter_mut(&mut self) -> Result<std::slice::IterMut<'_, K>> {
+        match self.0.qtype {
+            qtype::COMPOUND_LIST | qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+                Ok(self.as_mut_vec::<K>()?.iter_mut())
+            }
+            qtype => Err(Error::invalid_operation(
+                "iter_mut",
+                qtype,
+                Some(qtype::COMPOUND_LIST),
+            )),
+        }
+    }
+
+    /// Get elements of a short, int or long list widened to `i64`. Nulls and infinities are mapped to
+    ///  those of long.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_short_list = K::new_short_list(vec![1, qnull::SHORT, qinf::SHORT], qattribute::NONE);
+    ///     assert_eq!(
+    ///         q_short_list.as_vec_i64().unwrap(),
+    ///         vec![1, qnull::LONG, qinf::LONG]
+    ///     );
+    /// }
+    /// ```
+    pub fn as_vec_i64(&self) -> Result<Vec<i64>> {
+        match self.0.qtype {
+            qtype::SHORT_LIST => Ok(self
+                .as_vec::<H>()?
+                .iter()
+                .map(|&value| widen_long(value, qnull_base::H, qinf_base::H, qninf_base::H))
+                .collect()),
+            qtype::INT_LIST => Ok(self
+                .as_vec::<I>()?
+                .iter()
+                .map(|&value| widen_long(value, qnull_base::I, qinf_base::I, qninf_base::I))
+                .collect()),
+            qtype::LONG_LIST => Ok(self.as_vec::<J>()?.clone()),
+            _ => Err(Error::invalid_cast_list(self.0.qtype)),
+        }
+    }
+
+    /// Get elements of a short, int, long, real or float list widened to `f64`. Nulls are mapped to
+    ///  `NaN` and infinities to `f64` infinities.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_int_list = K::new_int_list(vec![1, qninf::INT], qattribute::NONE);
+    ///     assert_eq!(q_int_list.as_vec_f64().unwrap(), vec![1.0, f64::NEG_INFINITY]);
+    /// }
+    /// ```
+    pub fn as_vec_f64(&self) -> Result<Vec<f64>> {
+        match self.0.qtype {
+            qtype::SHORT_LIST | qtype::INT_LIST | qtype::LONG_LIST => Ok(self
+                .as_vec_i64()?
+                .into_iter()
+                .map(|value| match value {
+                    qnull_base::J => qnull_base::F,
+                    qinf_base::J => qinf_base::F,
+                    qninf_base::J => qninf_base::F,
+                    _ => value as f64,
+                })
+                .collect()),
+            qtype::REAL_LIST => Ok(self
+                .as_vec::<E>()?
+                .iter()
+                .map(|&value| value as f64)
+                .collect()),
+            qtype::FLOAT_LIST => Ok(self.as_vec::<F>()?.clone()),
+            _ => Err(Error::invalid_cast_list(self.0.qtype)),
+        }
+    }
+
     /// Get an immutable column of a table with a specified name.
     /// # Example
     /// ```
@@ -2266,6 +2571,43 @@ impl K {
         self.0.attribute
     }
 
+    /// Check if q object is a table. A keyed table is a dictionary and is not a table; see
+    ///  [`is_keyed_table`](#method.is_keyed_table).
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_table = k!(table: {"a" => k!(long: vec![1, 2]), "b" => k!(sym: vec!["x", "y"])});
+    ///     assert!(q_table.is_table());
+    ///     assert!(!q_table.enkey(1).unwrap().is_table());
+    /// }
+    /// ```
+    pub fn is_table(&self) -> bool {
+        self.0.qtype == qtype::TABLE
+    }
+
+    /// Check if q object is a keyed table, i.e. a dictionary whose keys and values are both tables.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let q_table = k!(table: {"a" => k!(long: vec![1, 2]), "b" => k!(sym: vec!["x", "y"])});
+    ///     assert!(!q_table.is_keyed_table());
+    ///     assert!(q_table.enkey(1).unwrap().is_keyed_table());
+    /// }
+    /// ```
+    pub fn is_keyed_table(&self) -> bool {
+        match self.0.qtype {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => match self.as_vec::<K>() {
+                Ok(parts) => parts.len() == 2 && parts.iter().all(K::is_table),
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+
     // Setter //---------------------------------/
 
     /// Set an attribute to the underlying q object.
@@ -4448,6 +4790,9 @@ impl K {
     /// # Note
     /// - Key type must be a symbol.
     /// - This function does not check if lengths of columns are same.
+    /// - Columns of the table are in the order of the keys of the dictionary. The order is kept by
+    ///   [`get_dictionary`](#method.get_dictionary), [`enkey`](#method.enkey) and
+    ///   [`unkey`](#method.unkey).
     /// # Example
     /// ```
     /// use kdb_codec::*;
@@ -4744,3 +5089,16 @@ fn datetime_to_q_datetime(datetime: DateTime<Utc>) -> f64 {
 }
 
 //%% Getter //%%vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Widen an integer to `i64` keeping null and infinities.
+fn widen_long<T: Into<i64> + PartialEq>(value: T, null: T, inf: T, ninf: T) -> i64 {
+    if value == null {
+        qnull_base::J
+    } else if value == inf {
+        qinf_base::J
+    } else if value == ninf {
+        qninf_base::J
+    } else {
+        value.into()
+    }
+}
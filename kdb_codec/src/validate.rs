@@ -0,0 +1,368 @@
+//! Structural validation of q objects.
+//!
+//! Objects decoded from an untrusted peer are well-formed on the wire level but may still be
+//! inconsistent, e.g. a table whose columns have different lengths. This module checks such
+//! invariants so that the object can be handed to code which assumes them. A `MessageSchema`
+//! additionally checks that a message has the shape an application expects.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use crate::error::Error;
+use crate::qconsts::qtype;
+use crate::types::*;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Enums
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Expected shape of a message, checked by [`K::matches_schema`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageSchema {
+    /// Any object.
+    Any,
+    /// Object of the given q type, e.g. `qtype::LONG_ATOM`.
+    Type(i8),
+    /// Compound list whose elements match the given schemas in order.
+    List(Vec<MessageSchema>),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl K {
+    /// Check recursively that the object is structurally consistent:
+    /// - a table is a dictionary of a symbol list to a compound list of columns, and all columns are
+    ///   lists of the same length as each other
+    /// - a dictionary has as many keys as values
+    /// - each element of a compound list is itself consistent
+    ///
+    /// The first violation found is returned as `Error::InvalidStructure` describing where it is.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let table = k!(table: {
+    ///         "sym" => k!(sym: vec!["a", "b"]),
+    ///         "price" => k!(float: vec![1.0, 2.0])
+    ///     });
+    ///     assert!(table.validate_structure().is_ok());
+    /// }
+    /// ```
+    pub fn validate_structure(&self) -> Result<()> {
+        validate(self, "")
+    }
+
+    /// Check that the object has the shape described by `schema`. The first mismatch found is
+    ///  returned as `Error::InvalidStructure` naming its position, e.g. `[2]`.
+    /// # Example
+    /// ```
+    /// use kdb_codec::*;
+    ///
+    /// fn main() {
+    ///     let schema = MessageSchema::List(vec![
+    ///         MessageSchema::Type(qtype::SYMBOL_ATOM),
+    ///         MessageSchema::Type(qtype::LONG_ATOM),
+    ///         MessageSchema::Type(qtype::FLOAT_ATOM),
+    ///     ]);
+    ///     let message = k!([k!(sym: "AAPL"), k!(long: 100), k!(float: 189.5)]);
+    ///     assert!(message.matches_schema(&schema).is_ok());
+    ///
+    ///     let message = k!([k!(sym: "AAPL"), k!(long: 100), k!(long: 189)]);
+    ///     assert_eq!(
+    ///         message.matches_schema(&schema).unwrap_err().to_string(),
+    ///         String::from("invalid structure: element at [2] is long instead of float")
+    ///     );
+    /// }
+    /// ```
+    pub fn matches_schema(&self, schema: &MessageSchema) -> Result<()> {
+        match_schema(self, schema, "")
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Validate an object located at `path` (empty for the root), e.g. `[1].price`.
+fn validate(object: &K, path: &str) -> Result<()> {
+    match object.get_type() {
+        qtype::TABLE => validate_table(object, path),
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => validate_dictionary(object, path),
+        qtype::COMPOUND_LIST => {
+            let elements = list_elements(object, path)?;
+            for (index, element) in elements.iter().enumerate() {
+                validate(element, &format!("{}[{}]", path, index))?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_dictionary(dictionary: &K, path: &str) -> Result<()> {
+    let parts = list_elements(dictionary, path)?;
+    let [keys, values] = parts.as_slice() else {
+        return Err(Error::invalid_structure(format!(
+            "dictionary{} has {} parts instead of keys and values",
+            at(path),
+            parts.len()
+        )));
+    };
+    if keys.len() != values.len() {
+        return Err(Error::invalid_structure(format!(
+            "dictionary{} has {} keys but {} values",
+            at(path),
+            keys.len(),
+            values.len()
+        )));
+    }
+    validate(keys, &format!("{}.key", path))?;
+    validate(values, &format!("{}.value", path))
+}
+
+fn validate_table(table: &K, path: &str) -> Result<()> {
+    let k0_inner::table(dictionary) = &table.0.value else {
+        return Err(Error::invalid_structure(format!(
+            "table{} does not hold a dictionary",
+            at(path)
+        )));
+    };
+    if dictionary.get_type() != qtype::DICTIONARY {
+        return Err(Error::invalid_structure(format!(
+            "table{} holds {} instead of a dictionary",
+            at(path),
+            crate::error::type_to_string(dictionary.get_type())
+        )));
+    }
+    let parts = list_elements(dictionary, path)?;
+    let [names, columns] = parts.as_slice() else {
+        return Err(Error::invalid_structure(format!(
+            "table{} has {} parts instead of column names and columns",
+            at(path),
+            parts.len()
+        )));
+    };
+    if names.get_type() != qtype::SYMBOL_LIST || columns.get_type() != qtype::COMPOUND_LIST {
+        return Err(Error::invalid_structure(format!(
+            "table{} has {} column names and {} columns instead of symbol list and compound list",
+            at(path),
+            crate::error::type_to_string(names.get_type()),
+            crate::error::type_to_string(columns.get_type())
+        )));
+    }
+    let names = names.as_vec::<S>()?;
+    let columns = list_elements(columns, path)?;
+    if names.len() != columns.len() {
+        return Err(Error::invalid_structure(format!(
+            "table{} has {} column names but {} columns",
+            at(path),
+            names.len(),
+            columns.len()
+        )));
+    }
+
+    let mut rows = None;
+    for (name, column) in names.iter().zip(columns) {
+        let column_path = format!("{}.{}", path, name);
+        if !(qtype::COMPOUND_LIST..=qtype::TIME_LIST).contains(&column.get_type()) {
+            return Err(Error::invalid_structure(format!(
+                "column `{}`{} is {} instead of a list",
+                name,
+                at(path),
+                crate::error::type_to_string(column.get_type())
+            )));
+        }
+        match rows {
+            Some(rows) if rows != column.len() => {
+                return Err(Error::invalid_structure(format!(
+                    "column `{}`{} has {} rows but the previous columns have {}",
+                    name,
+                    at(path),
+                    column.len(),
+                    rows
+                )))
+            }
+            _ => rows = Some(column.len()),
+        }
+        validate(column, &column_path)?;
+    }
+    Ok(())
+}
+
+/// Match an object located at `path` (empty for the root) against a schema.
+fn match_schema(object: &K, schema: &MessageSchema, path: &str) -> Result<()> {
+    let expected = match schema {
+        MessageSchema::Any => return Ok(()),
+        MessageSchema::Type(qtype) => *qtype,
+        MessageSchema::List(_) => qtype::COMPOUND_LIST,
+    };
+    let subject = match path {
+        "" => String::from("message"),
+        _ => format!("element at {}", path),
+    };
+    if object.get_type() != expected {
+        return Err(Error::invalid_structure(format!(
+            "{} is {} instead of {}",
+            subject,
+            crate::error::type_to_string(object.get_type()),
+            crate::error::type_to_string(expected)
+        )));
+    }
+    if let MessageSchema::List(schemas) = schema {
+        let elements = list_elements(object, path)?;
+        if elements.len() != schemas.len() {
+            return Err(Error::invalid_structure(format!(
+                "{} has {} elements instead of {}",
+                subject,
+                elements.len(),
+                schemas.len()
+            )));
+        }
+        for (index, (element, schema)) in elements.iter().zip(schemas).enumerate() {
+            match_schema(element, schema, &format!("{}[{}]", path, index))?;
+        }
+    }
+    Ok(())
+}
+
+/// Get elements of a compound list, dictionary or a table dictionary, reporting an internal
+///  state not holding them as a violation.
+fn list_elements<'a>(object: &'a K, path: &str) -> Result<&'a Vec<K>> {
+    object.as_vec::<K>().map_err(|_| {
+        Error::invalid_structure(format!(
+            "{}{} does not hold a list of objects",
+            crate::error::type_to_string(object.get_type()),
+            at(path)
+        ))
+    })
+}
+
+/// Location suffix of an error message.
+fn at(path: &str) -> String {
+    match path {
+        "" => String::new(),
+        _ => format!(" at {}", path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k;
+    use crate::qconsts::qattribute;
+
+    #[test]
+    fn test_validate_structure() {
+        let table = k!(table: {
+            "sym" => k!(sym: vec!["a", "b", "c"]),
+            "price" => k!(float: vec![1.0, 2.0, 3.0]),
+            "tags" => k!([k!(sym: vec!["x"]), k!(sym: vec![]), k!(sym: vec!["y", "z"])])
+        });
+        assert!(table.validate_structure().is_ok());
+
+        // Nested in a compound list and a keyed table
+        let keyed = table.clone().enkey(1).unwrap();
+        assert!(keyed.validate_structure().is_ok());
+        assert!(k!([k!(long: 1), table, keyed]).validate_structure().is_ok());
+    }
+
+    #[test]
+    fn test_validate_structure_error() {
+        // Columns of different lengths
+        let dictionary = k!(dict:
+            k!(sym: vec!["sym", "price"]) =>
+            k!([k!(sym: vec!["a", "b", "c"]), k!(float: vec![1.0, 2.0])])
+        );
+        let table = K::new(qtype::TABLE, qattribute::NONE, k0_inner::table(dictionary));
+        assert_eq!(
+            table.validate_structure().unwrap_err(),
+            Error::InvalidStructure(String::from(
+                "column `price` has 2 rows but the previous columns have 3"
+            ))
+        );
+
+        // Location of a nested violation is reported
+        let wrapped = k!([k!(long: 1), table]);
+        assert_eq!(
+            wrapped.validate_structure().unwrap_err(),
+            Error::InvalidStructure(String::from(
+                "column `price` at [1] has 2 rows but the previous columns have 3"
+            ))
+        );
+
+        // Dictionary with more keys than values
+        let dictionary = K::new(
+            qtype::DICTIONARY,
+            qattribute::NONE,
+            k0_inner::list(k0_list::new(vec![
+                k!(sym: vec!["a", "b"]),
+                k!(long: vec![1]),
+            ])),
+        );
+        assert_eq!(
+            dictionary.validate_structure().unwrap_err(),
+            Error::InvalidStructure(String::from("dictionary has 2 keys but 1 values"))
+        );
+    }
+
+    #[test]
+    fn test_matches_schema() {
+        let schema = MessageSchema::List(vec![
+            MessageSchema::Type(qtype::SYMBOL_ATOM),
+            MessageSchema::Type(qtype::LONG_ATOM),
+            MessageSchema::List(vec![
+                MessageSchema::Type(qtype::FLOAT_LIST),
+                MessageSchema::Any,
+            ]),
+        ]);
+        let message = k!([
+            k!(sym: "upd"),
+            k!(long: 3),
+            k!([k!(float: vec![1.5, 2.5]), k!(sym: vec!["a", "b"])])
+        ]);
+        assert!(message.matches_schema(&schema).is_ok());
+        assert!(k!(long: 1).matches_schema(&MessageSchema::Any).is_ok());
+    }
+
+    #[test]
+    fn test_matches_schema_error() {
+        let schema = MessageSchema::List(vec![
+            MessageSchema::Type(qtype::SYMBOL_ATOM),
+            MessageSchema::Type(qtype::LONG_ATOM),
+            MessageSchema::List(vec![
+                MessageSchema::Type(qtype::FLOAT_LIST),
+                MessageSchema::Any,
+            ]),
+        ]);
+
+        // Wrong element type is reported with its position
+        let message = k!([
+            k!(sym: "upd"),
+            k!(long: 3),
+            k!([k!(long: vec![1, 2]), k!(sym: vec!["a", "b"])])
+        ]);
+        assert_eq!(
+            message.matches_schema(&schema).unwrap_err(),
+            Error::InvalidStructure(String::from(
+                "element at [2][0] is long list instead of float list"
+            ))
+        );
+
+        // Wrong number of elements
+        let message = k!([k!(sym: "upd"), k!(long: 3)]);
+        assert_eq!(
+            message.matches_schema(&schema).unwrap_err(),
+            Error::InvalidStructure(String::from("message has 2 elements instead of 3"))
+        );
+
+        // Wrong top-level type
+        assert_eq!(
+            k!(long: 3).matches_schema(&schema).unwrap_err(),
+            Error::InvalidStructure(String::from("message is long instead of compound list"))
+        );
+    }
+}
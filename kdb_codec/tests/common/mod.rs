@@ -0,0 +1,80 @@
+//! Helpers shared by integration tests which need a peer to talk to.
+
+#![allow(dead_code)]
+
+use kdb_codec::{qmsg_type, K};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Accept a connection and complete the handshake on the server side, answering with capacity 3.
+pub async fn accept_handshake(listener: &TcpListener) -> TcpStream {
+    let (mut socket, _) = listener.accept().await.unwrap();
+    complete_handshake(&mut socket).await;
+    socket
+}
+
+/// Complete the handshake on the server side of an accepted socket, answering with capacity 3.
+pub async fn complete_handshake<S>(socket: &mut S)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Read credential until null terminator
+    let mut byte = [0u8; 1];
+    loop {
+        socket.read_exact(&mut byte).await.unwrap();
+        if byte[0] == 0 {
+            break;
+        }
+    }
+    // Send back capacity
+    socket.write_all(&[3]).await.unwrap();
+}
+
+/// Minimal q-like server listening on `address` which completes the handshake and then waits for
+///  the client to close. Returns the port picked by the OS.
+pub async fn spawn_handshake_server(address: &str) -> u16 {
+    let listener = TcpListener::bind((address, 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        // Drain until the client shuts down
+        let mut buffer = Vec::new();
+        let _ = socket.read_to_end(&mut buffer).await;
+    });
+    port
+}
+
+/// Read one message and return its header and payload. Returns `None` once the client is gone.
+pub async fn read_raw_frame<S>(socket: &mut S) -> Option<([u8; 8], Vec<u8>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 8];
+    socket.read_exact(&mut header).await.ok()?;
+    let length = match header[0] {
+        0 => u32::from_be_bytes(header[4..8].try_into().unwrap()),
+        _ => u32::from_le_bytes(header[4..8].try_into().unwrap()),
+    } as usize;
+    let mut body = vec![0u8; length - 8];
+    socket.read_exact(&mut body).await.unwrap();
+    Some((header, body))
+}
+
+/// Read one uncompressed message and return its message type and decoded payload. Returns `None`
+///  once the client is gone.
+pub async fn read_frame<S>(socket: &mut S) -> Option<(u8, K)>
+where
+    S: AsyncRead + Unpin,
+{
+    let (header, body) = read_raw_frame(socket).await?;
+    Some((header[1], K::q_ipc_decode(&body, header[0]).unwrap()))
+}
+
+/// Send `response` as a response message. Returns `false` once the client is gone.
+pub async fn answer<S>(socket: &mut S, response: &K) -> bool
+where
+    S: AsyncWrite + Unpin,
+{
+    let response = response.ipc_msg_encode(qmsg_type::response, false);
+    socket.write_all(&response).await.is_ok()
+}
@@ -0,0 +1,57 @@
+use kdb_codec::*;
+
+/// Build a client and an acceptor talking over an in-memory pipe of `capacity` bytes.
+fn duplex_pair(capacity: usize) -> (QStream, QStream) {
+    let (client, server) = tokio::io::duplex(capacity);
+    let (reader, writer) = tokio::io::split(client);
+    let client = QStream::from_async_read_write(
+        reader,
+        writer,
+        ConnectionMethod::TCP,
+        KdbCodec::new(true),
+        false,
+    );
+    let (reader, writer) = tokio::io::split(server);
+    let server = QStream::from_async_read_write(
+        reader,
+        writer,
+        ConnectionMethod::TCP,
+        KdbCodec::new(true),
+        true,
+    );
+    (client, server)
+}
+
+#[tokio::test]
+async fn from_async_read_write_exchanges_messages() -> Result<()> {
+    let (mut client, mut server) = duplex_pair(4096);
+    let echo = tokio::spawn(async move {
+        let (message_type, message) = server.receive_message().await?;
+        assert_eq!(message_type, qmsg_type::synchronous);
+        server.send_message(&message, qmsg_type::response).await?;
+        server.shutdown().await
+    });
+
+    let response = client.send_sync_message(&"1+1").await?;
+    assert_eq!(response.as_string()?, "1+1");
+    assert_eq!(client.get_connection_type(), "TCP");
+    echo.await.unwrap()?;
+    client.shutdown().await
+}
+
+#[tokio::test]
+async fn from_async_read_write_streams_through_small_buffer() -> Result<()> {
+    // The pipe holds far less than the message so the writer has to wait for the reader
+    let (mut client, mut server) = duplex_pair(64);
+    let message = K::new_long_list((0..10_000).collect(), qattribute::NONE);
+    let expected = message.q_ipc_encode();
+    let sender = tokio::spawn(async move {
+        client.send_async_message(&message).await?;
+        client.shutdown().await
+    });
+
+    let (message_type, received) = server.receive_message().await?;
+    assert_eq!(message_type, qmsg_type::asynchronous);
+    assert_eq!(received.q_ipc_encode(), expected);
+    sender.await.unwrap()
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{accept_handshake, answer, read_frame};
+use kdb_codec::*;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// Serve a handshake, record every received query with its message type and answer synchronous
+///  messages with `1b` once they are recorded.
+async fn spawn_server(log: Arc<Mutex<Vec<(u8, String)>>>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        while let Some((message_type, query)) = read_frame(&mut socket).await {
+            log.lock()
+                .unwrap()
+                .push((message_type, query.as_string().unwrap().to_string()));
+            if message_type == qmsg_type::synchronous {
+                answer(&mut socket, &K::new_bool(true)).await;
+            }
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn sync_barrier_follows_async_messages() -> Result<()> {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let port = spawn_server(log.clone()).await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+
+    for query in ["a:1", "b:2", "c:3"] {
+        socket.send_async_message(&query).await?;
+    }
+    socket.sync_barrier().await?;
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            (qmsg_type::asynchronous, String::from("a:1")),
+            (qmsg_type::asynchronous, String::from("b:2")),
+            (qmsg_type::asynchronous, String::from("c:3")),
+            (qmsg_type::synchronous, String::from("1b")),
+        ]
+    );
+    socket.shutdown().await
+}
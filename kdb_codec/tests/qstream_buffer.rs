@@ -0,0 +1,47 @@
+mod common;
+
+use common::accept_handshake;
+use futures::FutureExt;
+use kdb_codec::*;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn partial_frame_is_buffered() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let frame = K::new_long_list(vec![1, 2, 3], qattribute::NONE)
+        .ipc_msg_encode(qmsg_type::asynchronous, false);
+    let split = frame.len() - 5;
+    let (partial, rest) = (frame[..split].to_vec(), frame[split..].to_vec());
+    let (sent, wait_sent) = futures::channel::oneshot::channel::<()>();
+    let (resume, wait_resume) = futures::channel::oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        socket.write_all(&partial).await.unwrap();
+        sent.send(()).unwrap();
+        wait_resume.await.unwrap();
+        socket.write_all(&rest).await.unwrap();
+    });
+
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    assert_eq!(socket.buffered_read_bytes(), 0);
+    wait_sent.await.unwrap();
+
+    // Poll without waiting for the rest until the partial frame lands in the buffer
+    for _ in 0..10_000 {
+        assert!(socket.receive_message().now_or_never().is_none());
+        if socket.buffered_read_bytes() != 0 {
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(socket.buffered_read_bytes(), split);
+
+    resume.send(()).unwrap();
+    let (_, message) = socket.receive_message().await?;
+    assert_eq!(message.as_vec::<J>()?, &vec![1, 2, 3]);
+    assert_eq!(socket.buffered_read_bytes(), 0);
+    Ok(())
+}
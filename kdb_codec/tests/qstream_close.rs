@@ -0,0 +1,52 @@
+use kdb_codec::*;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+
+/// Build a client whose peer is the returned raw end of an in-memory pipe.
+fn client_with_raw_peer() -> (QStream, DuplexStream) {
+    let (client, peer) = tokio::io::duplex(4096);
+    let (reader, writer) = tokio::io::split(client);
+    let codec = KdbCodec::new(true);
+    let socket =
+        QStream::from_async_read_write(reader, writer, ConnectionMethod::TCP, codec, false);
+    (socket, peer)
+}
+
+#[tokio::test]
+async fn clean_close_is_connection_closed() {
+    let (mut socket, mut peer) = client_with_raw_peer();
+    let message = K::new_long(42).ipc_msg_encode(qmsg_type::asynchronous, false);
+    peer.write_all(&message).await.unwrap();
+    drop(peer);
+
+    let (_, received) = socket.receive_message().await.unwrap();
+    assert_eq!(received.get_long(), Ok(42));
+    assert_eq!(
+        socket.receive_message().await.unwrap_err(),
+        Error::ConnectionClosed
+    );
+}
+
+#[tokio::test]
+async fn malformed_frame_is_protocol_error() {
+    let (mut socket, mut peer) = client_with_raw_peer();
+    // Message type 7 does not exist
+    peer.write_all(&[1, 7, 0, 0, 9, 0, 0, 0, 0xff])
+        .await
+        .unwrap();
+    drop(peer);
+
+    let error = socket.receive_message().await.unwrap_err();
+    assert!(matches!(error, Error::Protocol(_)), "got {:?}", error);
+}
+
+#[tokio::test]
+async fn close_in_middle_of_message_is_protocol_error() {
+    let (mut socket, mut peer) = client_with_raw_peer();
+    let message = K::new_long_list(vec![1, 2, 3], qattribute::NONE)
+        .ipc_msg_encode(qmsg_type::asynchronous, false);
+    peer.write_all(&message[..message.len() - 4]).await.unwrap();
+    drop(peer);
+
+    let error = socket.receive_message().await.unwrap_err();
+    assert!(matches!(error, Error::Protocol(_)), "got {:?}", error);
+}
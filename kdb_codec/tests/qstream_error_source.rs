@@ -0,0 +1,40 @@
+use kdb_codec::*;
+use std::error::Error as StdError;
+use std::io;
+use tokio::net::TcpListener;
+
+/// Downcast the source of an error to the underlying IO error.
+fn io_source(error: &Error) -> &io::Error {
+    error
+        .source()
+        .expect("error has no source")
+        .downcast_ref::<io::Error>()
+        .expect("source is not an IO error")
+}
+
+#[tokio::test]
+async fn connection_failure_exposes_io_error() {
+    // Pick a port nobody listens on
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+
+    let error = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass")
+        .await
+        .err()
+        .expect("connection should fail");
+    assert_eq!(io_source(&error).kind(), io::ErrorKind::ConnectionRefused);
+}
+
+#[test]
+fn network_error_wraps_io_error() {
+    let error = io_error_to_kdb_error(io::Error::new(io::ErrorKind::BrokenPipe, "peer went away"));
+    assert!(matches!(error, Error::NetworkError(_)));
+    assert_eq!(error.to_string(), "Network error: peer went away");
+    let source = io_source(&error);
+    assert_eq!(source.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(source.to_string(), "peer went away");
+
+    // Errors not caused by IO have no source
+    assert!(Error::InvalidMessageSize.source().is_none());
+}
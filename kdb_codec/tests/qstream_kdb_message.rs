@@ -0,0 +1,42 @@
+mod common;
+
+use common::{accept_handshake, read_raw_frame};
+use kdb_codec::*;
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn kdb_message_is_sent_as_built() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let peer = tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        let mut frames = Vec::new();
+        for _ in 0..2 {
+            frames.push(read_raw_frame(&mut socket).await.unwrap());
+        }
+        frames
+    });
+
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    let payload = K::new_long_list(vec![1, 2, 3], qattribute::NONE);
+    socket
+        .send_kdb_message(KdbMessage::new(qmsg_type::response, payload.clone()).with_encoding(0))
+        .await?;
+    // Without an override the encoding of the connection is used
+    socket
+        .send_kdb_message(KdbMessage::new(qmsg_type::asynchronous, payload.clone()))
+        .await?;
+
+    let frames = peer.await.unwrap();
+    let (header, body) = &frames[0];
+    assert_eq!(header[0], 0);
+    assert_eq!(header[1], qmsg_type::response);
+    assert_eq!(body, &payload.q_ipc_encode_with(0));
+    let (header, body) = &frames[1];
+    assert_eq!(header[0], cfg!(target_endian = "little") as u8);
+    assert_eq!(header[1], qmsg_type::asynchronous);
+    assert_eq!(body, &payload.q_ipc_encode());
+    assert_eq!(socket.stats().messages_sent, 2);
+    socket.shutdown().await
+}
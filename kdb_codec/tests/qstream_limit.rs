@@ -0,0 +1,81 @@
+use kdb_codec::*;
+use sha1_smol::Sha1;
+use std::net::TcpListener;
+use std::time::Duration;
+
+fn pick_free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind ephemeral port");
+    listener.local_addr().expect("local addr").port()
+}
+
+/// Point the acceptor at an account file holding `user:pass`.
+fn write_account_file() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("kdb_codec_limit_{}", std::process::id()));
+    let password = Sha1::from("pass").digest().to_string();
+    std::fs::write(&path, format!("user:{password}\n")).unwrap();
+    std::env::set_var("KDBPLUS_ACCOUNT_FILE", &path);
+}
+
+/// Connect to an acceptor, retrying until it is listening.
+async fn connect(port: u16) -> QStream {
+    for _ in 0..100 {
+        if let Ok(socket) =
+            QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await
+        {
+            return socket;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("acceptor on port {port} never came up");
+}
+
+#[tokio::test]
+async fn accept_is_gated_by_connection_limit() -> Result<()> {
+    write_account_file();
+    let limit = ConnectionLimit::new(1);
+    assert_eq!(limit.max_connections(), 1);
+
+    let port = pick_free_port();
+    let first = tokio::spawn({
+        let limit = limit.clone();
+        async move { QStream::accept_with_limit(ConnectionMethod::TCP, "127.0.0.1", port, &limit).await }
+    });
+    let _client = connect(port).await;
+    let first = first.await.unwrap()?;
+    assert_eq!(limit.available(), 0);
+
+    // The second acceptor waits for the first connection to go away before listening
+    let port = pick_free_port();
+    let second = tokio::spawn({
+        let limit = limit.clone();
+        async move { QStream::accept_with_limit(ConnectionMethod::TCP, "127.0.0.1", port, &limit).await }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!second.is_finished());
+    assert!(
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass")
+            .await
+            .is_err()
+    );
+
+    drop(first);
+    let _client = connect(port).await;
+    let second = second.await.unwrap()?;
+    assert_eq!(limit.available(), 0);
+
+    // A rejecting limit fails right away
+    let rejecting = limit.clone().reject_when_full(true);
+    let result = QStream::accept_with_limit(
+        ConnectionMethod::TCP,
+        "127.0.0.1",
+        pick_free_port(),
+        &rejecting,
+    )
+    .await;
+    assert!(matches!(result, Err(Error::TooManyConnections(1))));
+
+    drop(second);
+    assert_eq!(limit.available(), 1);
+    Ok(())
+}
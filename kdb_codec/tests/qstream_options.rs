@@ -0,0 +1,98 @@
+mod common;
+
+use common::spawn_handshake_server;
+use kdb_codec::*;
+
+async fn connect(host: &str, port: u16) -> Result<QStream> {
+    QStream::connect_with_options(
+        ConnectionMethod::TCP,
+        host,
+        port,
+        "user:pass",
+        CompressionMode::Always,
+        ValidationMode::Strict,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn loopback_host_is_local() -> Result<()> {
+    let port = spawn_handshake_server("127.0.0.1").await;
+    let socket = connect("127.0.0.1", port).await?;
+    assert!(socket.is_local());
+    assert_eq!(socket.compression_mode(), CompressionMode::Always);
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn peer_capability_from_handshake() -> Result<()> {
+    // The test server answers the handshake with capacity 3
+    let port = spawn_handshake_server("127.0.0.1").await;
+    let socket = connect("127.0.0.1", port).await?;
+    assert_eq!(socket.peer_capability(), Some(Capability::Guid));
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn other_host_is_remote() -> Result<()> {
+    // Any address other than `localhost`, a loopback or a link-local address is treated as remote.
+    //  The wildcard address still reaches the listener on the loopback interface.
+    let port = spawn_handshake_server("0.0.0.0").await;
+    let socket = connect("0.0.0.0", port).await?;
+    assert!(!socket.is_local());
+    assert_eq!(socket.compression_mode(), CompressionMode::Always);
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn ipv6_loopback_host() -> Result<()> {
+    // IPv6 may be disabled on the host
+    if tokio::net::TcpListener::bind("[::1]:0").await.is_err() {
+        return Ok(());
+    }
+    let port = spawn_handshake_server("::1").await;
+    let socket = connect("::1", port).await?;
+    assert_eq!(socket.get_connection_type(), "TCP");
+    assert!(socket.is_local());
+    socket.shutdown().await?;
+
+    // Bracketed form is accepted as well
+    let port = spawn_handshake_server("::1").await;
+    let socket = connect("[::1]", port).await?;
+    assert!(socket.is_local());
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn force_local_overrides_detection() -> Result<()> {
+    let port = spawn_handshake_server("127.0.0.1").await;
+    let mut socket = QStream::builder()
+        .method(ConnectionMethod::TCP)
+        .host("127.0.0.1")
+        .port(port)
+        .credential("user:pass")
+        .force_local(false)
+        .build()
+        .await?;
+    assert!(!socket.is_local());
+    assert_eq!(socket.compression_mode(), CompressionMode::Auto);
+
+    // A large repetitive message is now compressed on the wire
+    let message = K::new_bool_list(vec![true; 100_000], qattribute::NONE);
+    let plain = message.ipc_msg_encode(qmsg_type::asynchronous, false).len() as u64;
+    socket.send_async_message(&message).await?;
+    assert!(socket.stats().bytes_sent < plain);
+    socket.shutdown().await?;
+
+    let port = spawn_handshake_server("0.0.0.0").await;
+    let socket = QStream::builder()
+        .method(ConnectionMethod::TCP)
+        .host("0.0.0.0")
+        .port(port)
+        .credential("user:pass")
+        .force_local(true)
+        .build()
+        .await?;
+    assert!(socket.is_local());
+    socket.shutdown().await
+}
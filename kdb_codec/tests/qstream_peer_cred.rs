@@ -0,0 +1,46 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use kdb_codec::*;
+use std::os::unix::fs::MetadataExt;
+use tokio::io::AsyncReadExt;
+use tokio::net::UnixListener;
+
+/// Listen on the abstract socket `QStream` connects to for `port` and complete the handshake.
+fn spawn_uds_server(port: u16) {
+    let path = match std::env::var("QUDSPATH") {
+        Ok(dir) => format!("\x00{}/kx.{}", dir, port),
+        Err(_) => format!("\x00/tmp/kx.{}", port),
+    };
+    let listener = UnixListener::bind(path).unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        common::complete_handshake(&mut socket).await;
+        let mut buffer = Vec::new();
+        let _ = socket.read_to_end(&mut buffer).await;
+    });
+}
+
+#[tokio::test]
+async fn peer_cred_over_uds() -> Result<()> {
+    // Abstract socket names are shared by every process on the host
+    let port = 40_000 + (std::process::id() % 20_000) as u16;
+    spawn_uds_server(port);
+    let socket = QStream::connect(ConnectionMethod::UDS, "", port, "user:pass").await?;
+
+    // The server runs in this very process
+    let credential = socket.peer_cred()?;
+    assert_eq!(credential.uid, std::fs::metadata("/proc/self")?.uid());
+    assert_eq!(credential.gid, std::fs::metadata("/proc/self")?.gid());
+    assert_eq!(credential.pid, Some(std::process::id() as i32));
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn peer_cred_is_unavailable_over_tcp() -> Result<()> {
+    let port = common::spawn_handshake_server("127.0.0.1").await;
+    let socket = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    assert!(socket.peer_cred().is_err());
+    socket.shutdown().await
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::accept_handshake;
+use kdb_codec::*;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+/// Serve a handshake and write the given messages back to back.
+async fn spawn_server(messages: Vec<(u8, K)>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        for (message_type, message) in messages {
+            socket
+                .write_all(&message.ipc_msg_encode(message_type, false))
+                .await
+                .unwrap();
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn empty_async_messages_are_skipped() -> Result<()> {
+    let port = spawn_server(vec![
+        (qmsg_type::asynchronous, K::new_null()),
+        (
+            qmsg_type::asynchronous,
+            K::new_string(String::new(), qattribute::NONE),
+        ),
+        (
+            qmsg_type::asynchronous,
+            K::new_long_list(vec![], qattribute::NONE),
+        ),
+        (qmsg_type::asynchronous, K::new_long(42)),
+        // Only asynchronous messages are treated as pings
+        (qmsg_type::response, K::new_null()),
+    ])
+    .await;
+
+    let mut socket = QStream::builder()
+        .method(ConnectionMethod::TCP)
+        .host("127.0.0.1")
+        .port(port)
+        .credential("user:pass")
+        .skip_empty_async(true)
+        .build()
+        .await?;
+    assert!(socket.skip_empty_async());
+
+    let (message_type, message) = socket.receive_message().await?;
+    assert_eq!(message_type, qmsg_type::asynchronous);
+    assert_eq!(message.get_long()?, 42);
+    let (message_type, message) = socket.receive_message().await?;
+    assert_eq!(message_type, qmsg_type::response);
+    assert_eq!(message.get_type(), qtype::NULL);
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn empty_async_messages_are_delivered_by_default() -> Result<()> {
+    let port = spawn_server(vec![
+        (qmsg_type::asynchronous, K::new_null()),
+        (qmsg_type::asynchronous, K::new_long(42)),
+    ])
+    .await;
+
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    assert!(!socket.skip_empty_async());
+
+    let (_, message) = socket.receive_message().await?;
+    assert_eq!(message.get_type(), qtype::NULL);
+    let (_, message) = socket.receive_message().await?;
+    assert_eq!(message.get_long()?, 42);
+    socket.shutdown().await
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::accept_handshake;
+use futures::StreamExt;
+use kdb_codec::*;
+use tokio::net::TcpListener;
+use tokio_util::codec::Framed;
+
+#[tokio::test]
+async fn raw_frame_is_decoded_by_peer() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let peer = tokio::spawn(async move {
+        let socket = accept_handshake(&listener).await;
+        let mut framed = Framed::new(socket, KdbCodec::new(true));
+        let first = framed.next().await.unwrap().unwrap();
+        let second = framed.next().await.unwrap().unwrap();
+        (first, second)
+    });
+
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    let frame = K::new_string(String::from("heartbeat[]"), qattribute::NONE)
+        .ipc_msg_encode(qmsg_type::asynchronous, false);
+    socket.send_raw_frame(&frame).await?;
+    // Frame written by the codec still follows correctly
+    socket.send_async_message(&"after[]").await?;
+
+    let (first, second) = peer.await.unwrap();
+    assert_eq!(first.message_type, qmsg_type::asynchronous);
+    assert_eq!(first.payload.as_string()?, "heartbeat[]");
+    assert_eq!(second.payload.as_string()?, "after[]");
+    socket.shutdown().await
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{accept_handshake, read_frame};
+use kdb_codec::*;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Answer each synchronous query with the number of the connection it arrived on. The first
+///  connection is dropped after one answer.
+async fn spawn_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut first = accept_handshake(&listener).await;
+        answer(&mut first, 1).await;
+        drop(first);
+        let mut second = accept_handshake(&listener).await;
+        while answer(&mut second, 2).await {}
+    });
+    port
+}
+
+/// Read one message and respond with `connection`. Returns `false` once the client is gone.
+async fn answer(socket: &mut TcpStream, connection: i64) -> bool {
+    match read_frame(socket).await {
+        Some(_) => common::answer(socket, &K::new_long(connection)).await,
+        None => false,
+    }
+}
+
+#[tokio::test]
+async fn reconnect_resumes_queries() -> Result<()> {
+    let port = spawn_server().await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    socket.set_force_encoding(Some(1));
+
+    assert_eq!(socket.send_sync_message(&"1+1").await?.get_long()?, 1);
+    // Closed by the server or reset, depending on timing
+    assert!(socket.send_sync_message(&"1+1").await.is_err());
+
+    socket.reconnect().await?;
+    assert_eq!(socket.send_sync_message(&"1+1").await?.get_long()?, 2);
+    assert_eq!(socket.send_sync_message(&"1+1").await?.get_long()?, 2);
+    assert_eq!(socket.force_encoding(), Some(1));
+    assert_eq!(socket.stats().messages_sent, 4);
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn reconnect_requires_connect_parameters() {
+    let (client, _server) = tokio::io::duplex(64);
+    let (reader, writer) = tokio::io::split(client);
+    let codec = KdbCodec::new(true);
+    let mut socket =
+        QStream::from_async_read_write(reader, writer, ConnectionMethod::TCP, codec, false);
+    assert!(matches!(
+        socket.reconnect().await,
+        Err(Error::IO(error)) if error.kind() == std::io::ErrorKind::Unsupported
+    ));
+}
+
+#[tokio::test]
+async fn reconnect_to_fresh_listener_keeps_codec_settings() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let first = tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        answer(&mut socket, 1).await;
+        // The listener goes away together with the connection
+    });
+    let mut socket = QStream::connect_with_options(
+        ConnectionMethod::TCP,
+        "127.0.0.1",
+        port,
+        "user:pass",
+        CompressionMode::Never,
+        ValidationMode::Lenient,
+    )
+    .await?;
+    assert_eq!(socket.send_sync_message(&"1+1").await?.get_long()?, 1);
+    first.await.unwrap();
+
+    // A restarted q process listens on the same port
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        while answer(&mut socket, 2).await {}
+    });
+    socket.reconnect().await?;
+    assert_eq!(socket.send_sync_message(&"1+1").await?.get_long()?, 2);
+    assert_eq!(socket.compression_mode(), CompressionMode::Never);
+    assert_eq!(socket.validation_mode(), ValidationMode::Lenient);
+    socket.shutdown().await
+}
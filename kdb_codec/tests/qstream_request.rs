@@ -0,0 +1,104 @@
+mod common;
+
+use common::{accept_handshake, answer, read_frame};
+use kdb_codec::*;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+
+/// Serve a handshake and echo every synchronous message back as a response, except `"hang"`
+///  which is never answered.
+async fn spawn_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        while let Some((message_type, query)) = read_frame(&mut socket).await {
+            if message_type == qmsg_type::synchronous && query.as_string().unwrap() != "hang" {
+                answer(&mut socket, &query).await;
+            }
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn request_completes_within_select() -> Result<()> {
+    let port = spawn_server().await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+
+    let response = tokio::select! {
+        response = socket.request(&"1+1") => response?,
+        _ = tokio::time::sleep(Duration::from_secs(5)) => panic!("request timed out"),
+    };
+    assert_eq!(response.as_string()?, "1+1");
+
+    // The future can be named and awaited later
+    let request: Request = socket.request(&K::new_string(String::from("2+2"), qattribute::NONE));
+    assert_eq!(request.await?.as_string()?, "2+2");
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn request_loses_to_timeout() -> Result<()> {
+    let port = spawn_server().await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+
+    let timed_out = tokio::select! {
+        _ = socket.request(&"hang") => false,
+        _ = tokio::time::sleep(Duration::from_millis(100)) => true,
+    };
+    assert!(timed_out);
+
+    // The stream is usable again once the request future is dropped
+    let response = tokio::time::timeout(Duration::from_secs(5), socket.request(&"3+3"))
+        .await
+        .expect("request timed out")?;
+    assert_eq!(response.as_string()?, "3+3");
+    socket.shutdown().await
+}
+
+#[tokio::test]
+async fn send_sync_message_times_out() -> Result<()> {
+    // Peer which reads messages but never responds
+    let (client, mut server) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let mut buffer = Vec::new();
+        let _ = server.read_to_end(&mut buffer).await;
+    });
+    let (reader, writer) = tokio::io::split(client);
+    let mut socket = QStream::from_async_read_write(
+        reader,
+        writer,
+        ConnectionMethod::TCP,
+        KdbCodec::new(true),
+        false,
+    );
+
+    let timeout = Duration::from_millis(100);
+    let result = socket.send_sync_message_timeout(&"1+1", timeout).await;
+    assert!(matches!(result, Err(Error::Timeout(t)) if t == timeout));
+    assert_eq!(socket.stats().messages_sent, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn send_sync_message_timeout_leaves_stream_usable() -> Result<()> {
+    let port = spawn_server().await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+
+    let result = socket
+        .send_sync_message_timeout(&"hang", Duration::from_millis(100))
+        .await;
+    assert!(matches!(result, Err(Error::Timeout(_))));
+
+    // `hang` is never answered, so the next response belongs to the retry
+    let response = socket
+        .send_sync_message_timeout(&"4+4", Duration::from_secs(5))
+        .await?;
+    assert_eq!(response.as_string()?, "4+4");
+    socket.shutdown().await
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use common::spawn_handshake_server;
+use kdb_codec::*;
+
+#[tokio::test]
+async fn close_twice_is_noop() -> Result<()> {
+    let port = spawn_handshake_server("127.0.0.1").await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+
+    assert!(!socket.is_closed());
+    socket.close().await?;
+    assert!(socket.is_closed());
+    // Second shutdown through the same handle must not touch the closed socket
+    socket.close().await?;
+    assert!(socket.is_closed());
+    // Consuming shutdown after close is also a no-op
+    socket.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{accept_handshake, answer, read_frame};
+use kdb_codec::*;
+use tokio::net::TcpListener;
+
+/// Serve a handshake and answer every synchronous message with `42`.
+async fn spawn_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener).await;
+        while let Some((message_type, _)) = read_frame(&mut socket).await {
+            if message_type == qmsg_type::synchronous {
+                answer(&mut socket, &K::new_long(42)).await;
+            }
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn stats_count_messages_and_bytes() -> Result<()> {
+    let port = spawn_server().await;
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "user:pass").await?;
+    assert_eq!(socket.stats(), ConnectionStats::default());
+
+    let query = K::new_string(String::from("1+1"), qattribute::NONE);
+    let response_length = K::new_long(42)
+        .ipc_msg_encode(qmsg_type::response, false)
+        .len() as u64;
+    let async_length = query.ipc_msg_encode(qmsg_type::asynchronous, false).len() as u64;
+    let sync_length = query.ipc_msg_encode(qmsg_type::synchronous, false).len() as u64;
+
+    socket.send_async_message(&query).await?;
+    let first = socket.stats();
+    assert_eq!(first.messages_sent, 1);
+    assert_eq!(first.bytes_sent, async_length);
+    assert_eq!(first.messages_received, 0);
+    assert_eq!(first.bytes_received, 0);
+    assert!(first.last_activity.is_some());
+
+    assert_eq!(socket.send_sync_message(&query).await?.get_long()?, 42);
+    assert_eq!(socket.send_sync_message(&query).await?.get_long()?, 42);
+    let second = socket.stats();
+    assert_eq!(second.messages_sent, 3);
+    assert_eq!(second.bytes_sent, async_length + 2 * sync_length);
+    assert_eq!(second.messages_received, 2);
+    assert_eq!(second.bytes_received, 2 * response_length);
+    assert!(second.last_activity >= first.last_activity);
+
+    let heartbeat = query.ipc_msg_encode(qmsg_type::asynchronous, false);
+    socket.send_raw_frame(&heartbeat).await?;
+    let third = socket.stats();
+    assert_eq!(third.messages_sent, 4);
+    assert_eq!(third.bytes_sent, second.bytes_sent + heartbeat.len() as u64);
+    assert!(third.last_activity >= second.last_activity);
+    socket.shutdown().await
+}
@@ -0,0 +1,36 @@
+use kdb_codec::*;
+use std::time::Duration;
+
+#[tokio::test]
+async fn timer_publishes_while_waiting() -> Result<()> {
+    let (client, server) = tokio::io::duplex(4096);
+    let (reader, writer) = tokio::io::split(client);
+    let codec = KdbCodec::new(true);
+    let mut client =
+        QStream::from_async_read_write(reader, writer, ConnectionMethod::TCP, codec, false);
+    let (reader, writer) = tokio::io::split(server);
+    let codec = KdbCodec::new(true);
+    let mut server =
+        QStream::from_async_read_write(reader, writer, ConnectionMethod::TCP, codec, true);
+
+    let mut published = 0;
+    server.on_timer(Duration::from_millis(10), move |socket| {
+        published += 1;
+        let update = k!([k!(sym: "upd"), k!(long: published)]);
+        Box::pin(async move { socket.send_async_message(&update).await })
+    });
+    let acceptor = tokio::spawn(async move {
+        // Only the timer writes until the client says goodbye
+        let (_, message) = server.receive_message().await?;
+        assert_eq!(message.as_string()?, "bye");
+        Ok::<(), Error>(())
+    });
+
+    for expected in 1..=3 {
+        let (message_type, message) = client.receive_message().await?;
+        assert_eq!(message_type, qmsg_type::asynchronous);
+        assert_eq!(format!("{}", message), format!("(`upd;{})", expected));
+    }
+    client.send_async_message(&"bye").await?;
+    acceptor.await.unwrap()
+}
@@ -210,3 +210,43 @@ fn test_decompress_big_endian() {
         assert_eq!(decompressed.len(), 8);
     }
 }
+
+#[test]
+fn test_decompress_size_smaller_than_data() {
+    // Size field declares 4 bytes of payload but 8 literals follow
+    let compressed = vec![
+        0x0C, 0x00, 0x00, 0x00, // 12 bytes total (4 after header)
+        0x00, // Control byte: all literal
+        0x41, 0x42, 0x43, 0x44, // "ABCD"
+        0x45, 0x46, 0x47, 0x48, // "EFGH"
+    ];
+
+    let result = decompress_sync(compressed, 1, None);
+
+    assert!(
+        matches!(result, Err(kdb_codec::Error::DeserializationError(_))),
+        "Inconsistent size field should be rejected"
+    );
+    let err_msg = result.unwrap_err().to_string();
+    assert!(err_msg.contains("trailing bytes"));
+}
+
+#[test]
+fn test_decompress_size_larger_than_data() {
+    // Size field declares 12 bytes of payload but only 8 literals follow
+    let compressed = vec![
+        0x14, 0x00, 0x00, 0x00, // 20 bytes total (12 after header)
+        0x00, // Control byte: all literal
+        0x41, 0x42, 0x43, 0x44, // "ABCD"
+        0x45, 0x46, 0x47, 0x48, // "EFGH"
+    ];
+
+    let result = decompress_sync(compressed, 1, None);
+
+    assert!(
+        matches!(result, Err(kdb_codec::Error::DeserializationError(_))),
+        "Under-filled buffer should be rejected"
+    );
+    let err_msg = result.unwrap_err().to_string();
+    assert!(err_msg.contains("unexpected end of compressed data"));
+}
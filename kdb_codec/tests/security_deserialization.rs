@@ -2,7 +2,44 @@
 //!
 //! Tests for integer overflow in list allocations, invalid UTF-8, and unbounded recursion
 
+use bytes::BytesMut;
 use kdb_codec::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use tokio_util::codec::Decoder;
+
+/// Allocator recording the largest single allocation made by the current thread while enabled.
+struct PeakAllocator;
+
+thread_local! {
+    static PEAK: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = PEAK.try_with(|peak| {
+            if let Some(largest) = peak.get() {
+                peak.set(Some(largest.max(layout.size())));
+            }
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Run `f` and return its result with the largest single allocation it made.
+fn peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    PEAK.with(|peak| peak.set(Some(0)));
+    let result = f();
+    let largest = PEAK.with(|peak| peak.replace(None)).unwrap();
+    (result, largest)
+}
 
 #[test]
 fn test_large_list_allocation_i64() {
@@ -136,6 +173,120 @@ fn test_symbol_list_with_invalid_utf8() {
     assert!(matches!(err, Error::InvalidUtf8));
 }
 
+#[test]
+fn test_symbol_exceeding_max_length() {
+    // Symbol far exceeding the limit is rejected after scanning only up to the limit,
+    // whether or not a null terminator follows
+    let mut bytes = vec![qtype::SYMBOL_ATOM as u8];
+    bytes.resize(1 + MAX_SYMBOL_LENGTH * 4, b'a');
+    let err = K::q_ipc_decode(&bytes, 1).expect_err("should reject symbol without terminator");
+    assert!(
+        matches!(err, Error::SymbolTooLong { max } if max == MAX_SYMBOL_LENGTH),
+        "expected SymbolTooLong, got: {err:?}"
+    );
+    bytes.push(0x00);
+    let err = K::q_ipc_decode(&bytes, 1).expect_err("should reject overlong symbol");
+    assert!(matches!(err, Error::SymbolTooLong { .. }));
+
+    // Same for error messages
+    bytes[0] = qtype::ERROR as u8;
+    let err = K::q_ipc_decode(&bytes, 1).expect_err("should reject overlong error");
+    assert!(matches!(err, Error::SymbolTooLong { .. }));
+
+    // Symbol of exactly the maximum length is accepted
+    let mut bytes = vec![qtype::SYMBOL_ATOM as u8];
+    bytes.resize(1 + MAX_SYMBOL_LENGTH, b'a');
+    bytes.push(0x00);
+    let symbol = K::q_ipc_decode(&bytes, 1).expect("symbol at the limit should decode");
+    assert_eq!(symbol.get_symbol().unwrap().len(), MAX_SYMBOL_LENGTH);
+}
+
+#[test]
+fn test_symbol_list_exceeding_configured_max_length() {
+    let symbols = K::new_symbol_list(vec!["short".to_string(), "x".repeat(100)], qattribute::NONE);
+    let message = symbols.ipc_msg_encode(qmsg_type::asynchronous, false);
+
+    let mut codec = KdbCodec::builder().max_symbol_len(16).build();
+    assert_eq!(codec.max_symbol_len(), 16);
+    let mut buffer = BytesMut::from(&message[..]);
+    let err = codec
+        .decode(&mut buffer)
+        .expect_err("should reject symbol longer than configured limit");
+    assert!(err
+        .to_string()
+        .contains("symbol exceeds maximum allowed length 16"));
+
+    codec.set_max_symbol_len(100);
+    let mut buffer = BytesMut::from(&message[..]);
+    let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+    assert_eq!(decoded.payload.as_vec::<S>().unwrap()[1].len(), 100);
+}
+
+#[test]
+fn test_compound_list_claiming_huge_size() {
+    // The header claims 4 million elements and enough bytes follow to pass the check of one byte
+    //  per element, but the first element is already invalid
+    let size: usize = 4_000_000;
+    let mut bytes = vec![qtype::COMPOUND_LIST as u8, 0x00];
+    bytes.extend_from_slice(&(size as u32).to_le_bytes());
+    bytes.resize(bytes.len() + size, 0xce);
+
+    let (result, largest) = peak_allocation(|| K::q_ipc_decode(&bytes, 1));
+    let err = result.expect_err("should reject invalid element");
+    assert!(
+        matches!(err, Error::InvalidType(-50)),
+        "expected InvalidType, got: {err:?}"
+    );
+    // Reserving room for every claimed element would take tens of MB
+    assert!(largest < 1 << 20, "allocated {largest} bytes up front");
+}
+
+#[test]
+fn test_symbol_list_claiming_huge_size() {
+    // Invalid UTF-8 symbols, one per two bytes
+    let size: usize = 4_000_000;
+    let mut bytes = vec![qtype::SYMBOL_LIST as u8, 0x00];
+    bytes.extend_from_slice(&(size as u32).to_le_bytes());
+    for _ in 0..size / 2 {
+        bytes.extend_from_slice(&[0xff, 0x00]);
+    }
+
+    let (result, largest) = peak_allocation(|| K::q_ipc_decode(&bytes, 1));
+    let err = result.expect_err("should reject invalid symbol");
+    assert!(
+        matches!(err, Error::InvalidUtf8),
+        "expected InvalidUtf8, got: {err:?}"
+    );
+    assert!(largest < 4 << 20, "allocated {largest} bytes up front");
+}
+
+#[test]
+fn test_codec_reports_truncated_compound_list() {
+    // Compound list claiming 3 elements but carrying only 2, framed with a consistent header
+    let mut payload = vec![qtype::COMPOUND_LIST as u8, 0x00];
+    payload.extend_from_slice(&3u32.to_le_bytes());
+    payload.extend_from_slice(&[qtype::BOOL_ATOM as u8, 0x01, qtype::BOOL_ATOM as u8, 0x00]);
+    let mut frame = vec![1, qmsg_type::asynchronous, 0, 0];
+    frame.extend_from_slice(&(8 + payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    let expected = K::q_ipc_decode(&payload, 1).unwrap_err().to_string();
+
+    // A valid message follows in the same buffer
+    let mut buffer = BytesMut::from(&frame[..]);
+    buffer.extend_from_slice(&K::new_long(42).ipc_msg_encode(qmsg_type::asynchronous, false));
+
+    let mut codec = KdbCodec::new(false);
+    let err = codec
+        .decode(&mut buffer)
+        .expect_err("should reject truncated compound list");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), expected);
+
+    let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+    assert_eq!(decoded.payload.get_long().unwrap(), 42);
+    assert!(buffer.is_empty());
+}
+
 #[test]
 fn test_deeply_nested_compound_list() {
     // Test deeply nested structure - should hit recursion depth limit
@@ -404,3 +555,115 @@ fn test_moderate_nesting_depth() {
 
     assert_eq!(current.get_int().unwrap(), 42);
 }
+
+#[test]
+fn test_disallowed_functions() {
+    // {x+1} sent as a lambda in the root namespace
+    let bytes = K::new_lambda(String::new(), String::from("{x+1}")).q_ipc_encode();
+    let limits = DeserializeLimits {
+        allow_functions: false,
+        ..Default::default()
+    };
+    let err = K::q_ipc_decode_with_limits(&bytes, 1, &limits).unwrap_err();
+    assert!(
+        matches!(err, Error::DisallowedType(qtype::LAMBDA)),
+        "expected DisallowedType, got: {err:?}"
+    );
+
+    // Functions nested in a list are refused as well
+    let nested = k!([
+        k!(long: 1),
+        K::new_lambda(String::new(), String::from("{x}"))
+    ])
+    .q_ipc_encode();
+    assert!(K::q_ipc_decode_with_limits(&nested, 1, &limits).is_err());
+
+    // Generic null shares the type of unary primitives but is not a function
+    let null = K::new_null().q_ipc_encode();
+    let k = K::q_ipc_decode_with_limits(&null, 1, &limits).unwrap();
+    assert_eq!(k.get_type(), qtype::NULL);
+
+    let limits = DeserializeLimits {
+        allow_functions: true,
+        ..Default::default()
+    };
+    let k = K::q_ipc_decode_with_limits(&bytes, 1, &limits).unwrap();
+    assert_eq!(k.get_type(), qtype::LAMBDA);
+}
+
+#[test]
+fn test_disallowed_tables() {
+    let table = k!(table: {"a" => k!(long: vec![1, 2]), "b" => k!(float: vec![0.5, 1.5])});
+    let keyed = table.clone().enkey(1).unwrap();
+    let limits = DeserializeLimits {
+        allow_tables: false,
+        ..Default::default()
+    };
+    for object in [table, keyed] {
+        let err = K::q_ipc_decode_with_limits(&object.q_ipc_encode(), 1, &limits).unwrap_err();
+        assert!(matches!(err, Error::DisallowedType(qtype::TABLE)));
+    }
+}
+
+#[test]
+fn test_max_total_elements() {
+    // 2 + 2 + 2 elements in total
+    let bytes = k!([k!(long: vec![1, 2]), k!(sym: vec!["a", "b"])]).q_ipc_encode();
+    let limits = DeserializeLimits {
+        max_total_elements: Some(5),
+        ..Default::default()
+    };
+    let err = K::q_ipc_decode_with_limits(&bytes, 1, &limits).unwrap_err();
+    assert!(
+        matches!(err, Error::TooManyElements { total: 6, max: 5 }),
+        "expected TooManyElements, got: {err:?}"
+    );
+
+    let limits = DeserializeLimits {
+        max_total_elements: Some(6),
+        ..Default::default()
+    };
+    assert!(K::q_ipc_decode_with_limits(&bytes, 1, &limits).is_ok());
+}
+
+#[test]
+fn test_max_symbol_len_limit() {
+    let bytes = k!(sym: vec!["abc", "abcdefgh"]).q_ipc_encode();
+    let limits = DeserializeLimits {
+        max_symbol_len: 4,
+        ..Default::default()
+    };
+    let err = K::q_ipc_decode_with_limits(&bytes, 1, &limits).unwrap_err();
+    assert!(
+        matches!(err, Error::SymbolTooLong { max: 4 }),
+        "expected SymbolTooLong, got: {err:?}"
+    );
+
+    let limits = DeserializeLimits {
+        max_symbol_len: 8,
+        ..Default::default()
+    };
+    assert!(K::q_ipc_decode_with_limits(&bytes, 1, &limits).is_ok());
+
+    // The codec keeps the symbol limit together with the other limits
+    let mut codec = KdbCodec::builder().max_symbol_len(4).build();
+    assert_eq!(codec.deserialize_limits().max_symbol_len, 4);
+    codec.set_max_symbol_len(16);
+    assert_eq!(codec.deserialize_limits().max_symbol_len, 16);
+}
+
+#[test]
+fn test_codec_refuses_functions() {
+    let mut codec = KdbCodec::builder().allow_functions(false).build();
+    assert!(!codec.deserialize_limits().allow_functions);
+
+    let message = K::new_lambda(String::new(), String::from("{x+1}"))
+        .ipc_msg_encode(qmsg_type::asynchronous, false);
+    let mut buffer = BytesMut::from(&message[..]);
+    assert!(codec.decode(&mut buffer).is_err());
+
+    codec.set_deserialize_limits(DeserializeLimits::default());
+    let mut buffer = BytesMut::from(&message[..]);
+    let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+    assert_eq!(decoded.payload.get_type(), qtype::LAMBDA);
+}
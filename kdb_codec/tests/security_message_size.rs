@@ -30,6 +30,41 @@ fn test_reject_oversized_message_header() {
     assert!(result.is_err(), "should reject oversized message header");
 }
 
+#[test]
+fn test_new_limits_message_size_by_default() {
+    // The builder keeps no limit unless one is set
+    assert_eq!(KdbCodec::builder().build().max_message_size(), None);
+    assert_eq!(
+        KdbCodec::builder()
+            .maybe_max_message_size(None)
+            .build()
+            .max_message_size(),
+        None
+    );
+
+    let mut codec = KdbCodec::new(false);
+    assert_eq!(codec.max_message_size(), Some(kdb_codec::MAX_MESSAGE_SIZE));
+    let mut buffer = BytesMut::new();
+
+    // Only the header of a message claiming 1GB has arrived
+    buffer.extend_from_slice(&[0x01, 0x01, 0x00, 0x00]);
+    buffer.extend_from_slice(&(1u32 << 30).to_le_bytes());
+
+    let err = codec
+        .decode(&mut buffer)
+        .expect_err("should reject oversized message header");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    // Nothing was reserved for the claimed body
+    assert!(buffer.capacity() < 1 << 30);
+
+    // The limit can still be lifted
+    codec.set_max_message_size(None);
+    let mut buffer = BytesMut::new();
+    buffer.extend_from_slice(&[0x01, 0x01, 0x00, 0x00]);
+    buffer.extend_from_slice(&64u32.to_le_bytes());
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+}
+
 #[test]
 fn test_reject_message_size_2gb() {
     let mut codec = KdbCodec::builder()
@@ -641,6 +641,71 @@ fn format_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn byte_format_test() -> Result<()> {
+    // Hex notation without separators as in q
+    assert_eq!(format!("{}", K::new_byte(42)), String::from("0x2a"));
+    assert_eq!(format!("{}", K::new_byte(0)), String::from("0x00"));
+    let q_byte_list = K::new_byte_list(vec![1, 2, 3], qattribute::NONE);
+    assert_eq!(format!("{}", q_byte_list), String::from("0x010203"));
+    let q_byte_list = K::new_byte_list(vec![0xff], qattribute::NONE);
+    assert_eq!(format!("{}", q_byte_list), String::from(",0xff"));
+
+    // Nested in a compound list
+    let q_compound = k!([k!(byte: 0x2a), k!(byte: vec![1, 2, 3])]);
+    assert_eq!(format!("{}", q_compound), String::from("(0x2a;0x010203)"));
+
+    Ok(())
+}
+
+#[test]
+fn debug_layout_test() -> Result<()> {
+    // nested compound list
+    let q_compound = K::new_compound_list(vec![
+        K::new_long_list(vec![1, 2, 3], qattribute::SORTED),
+        K::new_compound_list(vec![
+            K::new_float(1.5),
+            K::new_string(String::from("abc"), qattribute::NONE),
+        ]),
+    ]);
+    let layout = q_compound.debug_layout();
+    let lines = layout.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[0],
+        "qtype: 0 (compound list), attribute: 0, inner: list(len: 2)"
+    );
+    assert_eq!(
+        lines[1],
+        "  qtype: 7 (long list), attribute: 1, inner: list(len: 3)"
+    );
+    assert_eq!(
+        lines[2],
+        "  qtype: 0 (compound list), attribute: 0, inner: list(len: 2)"
+    );
+    assert_eq!(
+        lines[3],
+        "    qtype: -9 (float), attribute: 0, inner: float"
+    );
+    assert_eq!(
+        lines[4],
+        "    qtype: 10 (string), attribute: 0, inner: symbol(len: 3)"
+    );
+
+    // table holds a dictionary
+    let q_table = K::new_dictionary(
+        K::new_symbol_list(vec![String::from("a")], qattribute::NONE),
+        K::new_compound_list(vec![K::new_int_list(vec![1], qattribute::NONE)]),
+    )?
+    .flip()?;
+    let layout = q_table.debug_layout();
+    assert!(layout.starts_with("qtype: 98 (table), attribute: 0, inner: table\n"));
+    assert!(layout.contains("\n  qtype: 99 (dictionary), attribute: 0, inner: list(len: 2)\n"));
+    assert!(layout.contains("\n      qtype: 6 (int list), attribute: 0, inner: list(len: 1)"));
+
+    Ok(())
+}
+
 #[test]
 fn getter_test() -> Result<()> {
     // bool
@@ -1068,6 +1133,169 @@ fn getter_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn unary_primitive_test() -> Result<()> {
+    for (id, name) in [(1u8, "flip"), (2, "neg"), (13, "count"), (19, "value")] {
+        // little-endian async message carrying a unary primitive
+        let message = [1, 0, 0, 0, 10, 0, 0, 0, 101, id];
+        let (_, q_primitive) = K::ipc_msg_decode(&message)?;
+        assert_eq!(q_primitive.get_type(), qtype::UNARY_PRIMITIVE);
+        assert_eq!(q_primitive.unary_primitive_id()?, id);
+        assert_eq!(qprimitive::unary_name(id), Some(name));
+    }
+
+    // (::) has id 0
+    let (_, q_null) = K::ipc_msg_decode(&[1, 0, 0, 0, 10, 0, 0, 0, 101, 0])?;
+    assert_eq!(q_null.unary_primitive_id()?, 0);
+    assert_eq!(qprimitive::unary_name(0), Some("::"));
+
+    assert_eq!(qprimitive::unary_name(200), None);
+    assert_eq!(
+        K::new_long(1).unary_primitive_id().unwrap_err(),
+        Error::InvalidCast {
+            from: "long",
+            to: "null"
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn time_precision_test() -> Result<()> {
+    // 12:34:56.789 keeps its milliseconds through IPC
+    let millis = ((12 * 60 + 34) * 60 + 56) * 1000 + 789;
+    let q_time = K::new_time(Duration::milliseconds(millis));
+    assert_eq!(q_time.get_int(), Ok(millis as i32));
+    assert_eq!(format!("{}", q_time), String::from("12:34:56.789"));
+    let decoded = K::q_ipc_decode(&q_time.q_ipc_encode(), 1)?;
+    assert_eq!(decoded.get_type(), qtype::TIME_ATOM);
+    assert_eq!(decoded.get_time(), Ok(Duration::milliseconds(millis)));
+    assert_eq!(decoded.get_time()?.num_milliseconds() % 1000, 789);
+
+    // null and infinities of time, minute and second
+    let round_trip = |q_object: K| K::q_ipc_decode(&q_object.q_ipc_encode(), 1);
+    for (time, int) in [
+        (*qnull::TIME, qnull::INT),
+        (*qinf::TIME, qinf::INT),
+        (*qninf::TIME, qninf::INT),
+    ] {
+        let decoded = round_trip(K::new_time(time))?;
+        assert_eq!(decoded.get_time(), Ok(time));
+        assert_eq!(decoded.get_int(), Ok(int));
+    }
+    for (minute, int) in [
+        (*qnull::MINUTE, qnull::INT),
+        (*qinf::MINUTE, qinf::INT),
+        (*qninf::MINUTE, qninf::INT),
+    ] {
+        let decoded = round_trip(K::new_minute(minute))?;
+        assert_eq!(decoded.get_minute(), Ok(minute));
+        assert_eq!(decoded.get_int(), Ok(int));
+    }
+    for (second, int) in [
+        (*qnull::SECOND, qnull::INT),
+        (*qinf::SECOND, qinf::INT),
+        (*qninf::SECOND, qninf::INT),
+    ] {
+        let decoded = round_trip(K::new_second(second))?;
+        assert_eq!(decoded.get_second(), Ok(second));
+        assert_eq!(decoded.get_int(), Ok(int));
+    }
+    assert_eq!(
+        format!("{}", round_trip(K::new_time(*qnull::TIME))?),
+        String::from("0Nt")
+    );
+    assert_eq!(
+        format!("{}", round_trip(K::new_time(*qinf::TIME))?),
+        String::from("0Wt")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn month_test() -> Result<()> {
+    // q month is (year - 2000) * 12 + (month - 1)
+    for (year, month, int, display) in [
+        (2024, 3, 290, "2024.03m"),
+        (2000, 1, 0, "2000.01m"),
+        (1999, 12, -1, "1999.12m"),
+        (1999, 1, -12, "1999.01m"),
+        (1998, 12, -13, "1998.12m"),
+        (1970, 1, -360, "1970.01m"),
+    ] {
+        let q_month = K::new_month(NaiveDate::from_ymd_opt(year, month, 1).unwrap());
+        assert_eq!(q_month.get_int()?, int);
+        assert_eq!(format!("{}", q_month), String::from(display));
+        let decoded = K::q_ipc_decode(&q_month.q_ipc_encode(), 1)?;
+        assert_eq!(decoded.get_type(), qtype::MONTH_ATOM);
+        assert_eq!(
+            decoded.get_month()?,
+            NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+        );
+    }
+
+    // Any day of the month maps to the month
+    let q_month = K::new_month(NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+    assert_eq!(q_month.get_int()?, 290);
+
+    let q_month_list = K::new_month_list(
+        vec![
+            NaiveDate::from_ymd_opt(1999, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        ],
+        qattribute::NONE,
+    );
+    assert_eq!(q_month_list.as_vec::<I>()?, &vec![-12, 290]);
+    assert_eq!(
+        format!("{}", q_month_list),
+        String::from("1999.01 2024.03m")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn widening_getter_test() -> Result<()> {
+    let q_short_list = K::new_short_list(vec![-3, 7, qnull::SHORT], qattribute::NONE);
+    assert_eq!(q_short_list.as_vec_i64()?, vec![-3, 7, qnull::LONG]);
+
+    let q_int_list = K::new_int_list(vec![100_000, qinf::INT, qninf::INT], qattribute::NONE);
+    assert_eq!(
+        q_int_list.as_vec_i64()?,
+        vec![100_000, qinf::LONG, qninf::LONG]
+    );
+
+    let q_long_list = K::new_long_list(vec![1 << 40, -1], qattribute::SORTED);
+    assert_eq!(q_long_list.as_vec_i64()?, vec![1 << 40, -1]);
+
+    let widened = q_short_list.as_vec_f64()?;
+    assert_eq!(&widened[..2], &[-3.0, 7.0]);
+    assert!(widened[2].is_nan());
+    assert_eq!(
+        q_int_list.as_vec_f64()?,
+        vec![100_000.0, f64::INFINITY, f64::NEG_INFINITY]
+    );
+    assert_eq!(
+        K::new_real_list(vec![0.5, 1.5], qattribute::NONE).as_vec_f64()?,
+        vec![0.5, 1.5]
+    );
+
+    // not a numeric list
+    assert!(K::new_float_list(vec![1.0], qattribute::NONE)
+        .as_vec_i64()
+        .is_err());
+    assert!(K::new_long(1).as_vec_i64().is_err());
+    assert!(
+        K::new_symbol_list(vec![String::from("a")], qattribute::NONE)
+            .as_vec_f64()
+            .is_err()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn cast_test() -> Result<()> {
     // atom
@@ -1435,6 +1663,243 @@ fn length_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn dictionary_constructor_test() -> Result<()> {
+    // mixed value types are held in a compound list
+    let q_dictionary = K::dict_str_keys(vec![
+        ("qty", K::new_long(100)),
+        ("sym", K::new_symbol(String::from("AAPL"))),
+    ]);
+    assert_eq!(q_dictionary.get_type(), qtype::DICTIONARY);
+    assert_eq!(q_dictionary.len(), 2);
+    assert_eq!(q_dictionary[0].as_vec::<S>()?, &vec!["qty", "sym"]);
+    assert_eq!(q_dictionary[1].get_type(), qtype::COMPOUND_LIST);
+    assert_eq!(
+        q_dictionary
+            .try_find(&K::new_symbol(String::from("qty")))?
+            .get_long()?,
+        100
+    );
+    assert_eq!(
+        q_dictionary
+            .try_find(&K::new_symbol(String::from("sym")))?
+            .get_symbol()?,
+        "AAPL"
+    );
+    assert_eq!(
+        format!("{}", q_dictionary),
+        String::from("`qty`sym!(100;`AAPL)")
+    );
+
+    // same value types still stay in a compound list
+    let q_dictionary = K::dict_str_keys(vec![("a", K::new_long(1)), ("b", K::new_long(2))]);
+    assert_eq!(q_dictionary[1].get_type(), qtype::COMPOUND_LIST);
+
+    // empty
+    let q_dictionary = K::dict_str_keys(Vec::new());
+    assert_eq!(q_dictionary.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn compound_from_iter_test() -> Result<()> {
+    let q_compound_list = K::compound_from_iter((0..4).map(|i| K::new_long(i * i)));
+    assert_eq!(q_compound_list.get_type(), qtype::COMPOUND_LIST);
+    assert_eq!(q_compound_list.len(), 4);
+    assert_eq!(format!("{}", q_compound_list), String::from("(0;1;4;9)"));
+    assert_eq!(K::compound_from_iter(Vec::new()).len(), 0);
+
+    let q_compound_list =
+        K::compound_from_iter_result(["a", "b"].iter().map(|&s| Ok(K::new_symbol(s.to_string()))))?;
+    assert_eq!(format!("{}", q_compound_list), String::from("(`a;`b)"));
+
+    // Iteration stops at the first error
+    let mut visited = 0;
+    let result = K::compound_from_iter_result((0..5).map(|i| {
+        visited += 1;
+        if i == 2 {
+            Err(Error::IndexOutOfBounds {
+                length: 2,
+                index: i as usize,
+            })
+        } else {
+            Ok(K::new_long(i as i64))
+        }
+    }));
+    assert!(matches!(result, Err(Error::IndexOutOfBounds { .. })));
+    assert_eq!(visited, 3);
+
+    Ok(())
+}
+
+#[test]
+fn empty_list_test() -> Result<()> {
+    let mut q_long_list = K::empty_list(qtype::LONG_LIST)?;
+    assert_eq!(q_long_list.get_type(), qtype::LONG_LIST);
+    assert_eq!(q_long_list.len(), 0);
+    assert_eq!(q_long_list.as_vec::<J>()?, &Vec::<J>::new());
+    q_long_list.push(&42_i64)?;
+    assert_eq!(q_long_list.as_vec::<J>()?, &vec![42]);
+
+    let mut q_symbol_list = K::empty_list(qtype::SYMBOL_LIST)?;
+    assert_eq!(q_symbol_list.get_type(), qtype::SYMBOL_LIST);
+    assert_eq!(q_symbol_list.len(), 0);
+    q_symbol_list.push(&String::from("a"))?;
+    assert_eq!(format!("{}", q_symbol_list), String::from(",`a"));
+
+    let q_string = K::empty_list(qtype::STRING)?;
+    assert_eq!(q_string.as_string()?, "");
+    let q_timestamp_list = K::empty_list(qtype::TIMESTAMP_LIST)?;
+    assert_eq!(
+        q_timestamp_list.q_ipc_encode(),
+        K::new_timestamp_list(Vec::new(), qattribute::NONE).q_ipc_encode()
+    );
+
+    assert!(matches!(
+        K::empty_list(qtype::LONG_ATOM),
+        Err(Error::InvalidOperation { .. })
+    ));
+    assert!(K::empty_list(qtype::TABLE).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn as_bytes_test() -> Result<()> {
+    let q_byte_list = K::new_byte_list(vec![0x3c, 0x22, 0xf4], qattribute::NONE);
+    let bytes = q_byte_list.as_bytes()?;
+    assert_eq!(bytes, &[0x3c, 0x22, 0xf4]);
+    // Borrowed from the stored vector rather than copied
+    assert_eq!(bytes.as_ptr(), q_byte_list.as_vec::<G>()?.as_ptr());
+
+    let q_string = K::new_string(String::from("kdb+"), qattribute::NONE);
+    let bytes = q_string.as_bytes()?;
+    assert_eq!(bytes, b"kdb+");
+    assert_eq!(bytes.as_ptr(), q_string.as_string()?.as_ptr());
+
+    let q_char = K::new_char('q');
+    assert_eq!(q_char.as_bytes()?, b"q");
+
+    assert!(matches!(
+        K::new_long(42).as_bytes(),
+        Err(Error::InvalidOperation { .. })
+    ));
+    assert!(
+        K::new_symbol_list(vec![String::from("a")], qattribute::NONE)
+            .as_bytes()
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn iter_test() -> Result<()> {
+    let q_compound = k!([k!(long: 1), k!(sym: "a"), k!(string: "bc")]);
+    let elements = q_compound
+        .iter()?
+        .map(|element| format!("{}", element))
+        .collect::<Vec<_>>();
+    assert_eq!(elements, vec!["1", "`a", "\"bc\""]);
+
+    let mut q_compound = k!([k!(long: 1), k!(long: 2)]);
+    for element in q_compound.iter_mut()? {
+        *element = K::new_long(element.get_long()? + 1);
+    }
+    assert_eq!(format!("{}", q_compound), String::from("(2;3)"));
+
+    // Keys and values of a dictionary
+    let q_dictionary = k!(dict: k!(sym: vec!["a", "b"]) => k!(long: vec![1, 2]));
+    let mut parts = q_dictionary.iter()?;
+    assert_eq!(parts.next().unwrap().as_vec::<S>()?, &vec!["a", "b"]);
+    assert_eq!(parts.next().unwrap().as_vec::<J>()?, &vec![1, 2]);
+    assert!(parts.next().is_none());
+
+    let mut q_dictionary = q_dictionary;
+    q_dictionary.iter_mut()?.nth(1).unwrap().as_mut_vec::<J>()?[0] = 10;
+    assert_eq!(format!("{}", q_dictionary), String::from("`a`b!10 2"));
+
+    let mut q_long_list = k!(long: vec![1, 2, 3]);
+    assert!(matches!(
+        q_long_list.iter(),
+        Err(Error::InvalidOperation { .. })
+    ));
+    assert!(matches!(
+        q_long_list.iter_mut(),
+        Err(Error::InvalidOperation { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn keyed_table_introspection_test() -> Result<()> {
+    let q_table = k!(table: {
+        "sym" => k!(sym: vec!["a", "b"]),
+        "price" => k!(float: vec![1.5, 2.5]),
+        "size" => k!(long: vec![10, 20])
+    });
+    assert!(q_table.is_table());
+    assert!(!q_table.is_keyed_table());
+
+    let q_keyed_table = q_table.clone().enkey(1)?;
+    assert!(!q_keyed_table.is_table());
+    assert!(q_keyed_table.is_keyed_table());
+
+    // Survives IPC
+    let decoded = K::q_ipc_decode(&q_keyed_table.q_ipc_encode(), 1)?;
+    assert!(decoded.is_keyed_table());
+
+    // Dictionaries with a non-table key or value are not keyed tables
+    let q_dictionary = k!(dict: k!(sym: vec!["a", "b"]) => k!(long: vec![1, 2]));
+    assert!(!q_dictionary.is_table());
+    assert!(!q_dictionary.is_keyed_table());
+    let q_dictionary = K::new_dictionary(k!(sym: vec!["a", "b"]), q_table)?;
+    assert!(!q_dictionary.is_keyed_table());
+    assert!(!k!(long: 1).is_keyed_table());
+
+    Ok(())
+}
+
+#[test]
+fn flip_column_order_test() -> Result<()> {
+    // keys deliberately not in alphabetical order
+    let order = vec!["zeta", "alpha", "mid", "beta"];
+    let q_dictionary = K::new_dictionary(
+        K::new_symbol_list(
+            order.iter().map(|name| name.to_string()).collect(),
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_long_list(vec![1, 2], qattribute::NONE),
+            K::new_symbol_list(vec![String::from("a"), String::from("b")], qattribute::NONE),
+            K::new_float_list(vec![0.5, 1.5], qattribute::NONE),
+            K::new_bool_list(vec![true, false], qattribute::NONE),
+        ]),
+    )?;
+    let q_table = q_dictionary.flip()?;
+    assert_eq!(q_table.cols()?, order);
+    assert_eq!(
+        format!("{}", q_table),
+        String::from("+`zeta`alpha`mid`beta!(1 2;`a`b;0.5 1.5;10b)")
+    );
+
+    // inverse of flip
+    assert_eq!(q_table.get_dictionary()?[0].as_vec::<S>()?, &order);
+
+    // keying and unkeying
+    let q_keyed_table = q_table.clone().enkey(2)?;
+    assert_eq!(q_keyed_table.cols()?, order);
+    assert_eq!(q_keyed_table.unkey()?.cols()?, order);
+
+    // IPC round trip
+    let decoded = K::q_ipc_decode(&q_table.q_ipc_encode(), 1)?;
+    assert_eq!(decoded.cols()?, order);
+
+    Ok(())
+}
+
 #[test]
 fn push_pop_test() -> Result<()> {
     // empty list